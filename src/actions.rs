@@ -0,0 +1,86 @@
+use anyhow::anyhow;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::models::release::{Platform, ReleaseStatus, ReleaseSummary};
+
+pub fn is_github_actions() -> bool {
+    env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// Both files are shared with the other steps of the job, so they're appended to.
+fn append_to_env_file(env_var: &str, contents: &str) -> Result<(), anyhow::Error> {
+    let path = match env::var(env_var) {
+        Ok(path) => path,
+        Err(err) => return Err(anyhow!("Failed to get `{}`: {}", env_var, err)),
+    };
+
+    let mut file = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => file,
+        Err(err) => return Err(anyhow!("Failed to open `{}`: {}", path, err)),
+    };
+
+    match file.write_all(contents.as_bytes()) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!("Failed to write to `{}`: {}", path, err)),
+    }
+}
+
+pub fn write_outputs(summary: &ReleaseSummary) -> Result<(), anyhow::Error> {
+    let outputs = [
+        ("version", summary.version_number.clone()),
+        (
+            "modrinth_url",
+            summary.url(Platform::Modrinth).cloned().unwrap_or_default(),
+        ),
+        (
+            "github_release_url",
+            summary.url(Platform::Github).cloned().unwrap_or_default(),
+        ),
+        ("mrpack_sha512", summary.sha512.clone().unwrap_or_default()),
+    ];
+
+    let contents = outputs
+        .iter()
+        .map(|(name, value)| format!("{}={}\n", name, value))
+        .collect::<String>();
+
+    append_to_env_file("GITHUB_OUTPUT", &contents)
+}
+
+pub fn write_step_summary(summary: &ReleaseSummary) -> Result<(), anyhow::Error> {
+    let mut contents = format!(
+        "## {}\n\n| Platform | Status | Link |\n| --- | --- | --- |\n",
+        summary.version_name
+    );
+
+    for res in &summary.results {
+        let (status, link) = match &res.status {
+            ReleaseStatus::Succeeded { url } => ("Published", format!("[{}]({})", url, url)),
+            ReleaseStatus::AlreadyPresent { url } => {
+                ("Already released", format!("[{}]({})", url, url))
+            }
+            ReleaseStatus::Failed(_) => ("Failed", String::new()),
+            ReleaseStatus::Skipped => ("Skipped", String::new()),
+        };
+
+        contents.push_str(&format!(
+            "| {} | {} | {} |\n",
+            res.platform.formatted(),
+            status,
+            link
+        ));
+    }
+
+    if let Some(size) = summary.file_size {
+        contents.push_str(&format!(
+            "\nFile size: {:.2} MiB\n",
+            size as f64 / (1024.0 * 1024.0)
+        ));
+    }
+
+    contents.push('\n');
+
+    append_to_env_file("GITHUB_STEP_SUMMARY", &contents)
+}
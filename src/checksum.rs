@@ -0,0 +1,85 @@
+use anyhow::anyhow;
+use sha2::{Digest, Sha256, Sha512};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::models::util::{FileChecksums, OutputFileInfo, TempInfo};
+
+pub const CHECKSUMS_FILE_NAME: &str = "checksums.txt";
+
+/// Reads the file in chunks, so that large packs don't have to fit into memory.
+pub fn hash_file(file_info: &OutputFileInfo) -> Result<FileChecksums, anyhow::Error> {
+    let mut file = match File::open(&file_info.file_path) {
+        Ok(file) => file,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to open `{}` for hashing: {}",
+                file_info.file_name,
+                err
+            ))
+        }
+    };
+
+    let mut sha256 = Sha256::new();
+    let mut sha512 = Sha512::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to read `{}` for hashing: {}",
+                    file_info.file_name,
+                    err
+                ))
+            }
+        };
+
+        sha256.update(&buffer[..read]);
+        sha512.update(&buffer[..read]);
+    }
+
+    Ok(FileChecksums {
+        file_name: file_info.file_name.clone(),
+        sha256: format!("{:x}", sha256.finalize()),
+        sha512: format!("{:x}", sha512.finalize()),
+    })
+}
+
+/// Uses the BSD tag format, which `sha256sum -c` and `sha512sum -c` both understand.
+pub fn write_checksums_file(
+    tmp_dir_info: &TempInfo,
+    checksums: &[FileChecksums],
+) -> Result<OutputFileInfo, anyhow::Error> {
+    let mut contents = String::new();
+
+    for checksum in checksums {
+        contents.push_str(&format!(
+            "SHA256 ({}) = {}\n",
+            checksum.file_name, checksum.sha256
+        ));
+    }
+    for checksum in checksums {
+        contents.push_str(&format!(
+            "SHA512 ({}) = {}\n",
+            checksum.file_name, checksum.sha512
+        ));
+    }
+
+    let file_path: PathBuf = tmp_dir_info.dir_path.join(CHECKSUMS_FILE_NAME);
+
+    match fs::write(&file_path, contents) {
+        Ok(_) => Ok(OutputFileInfo {
+            file_name: CHECKSUMS_FILE_NAME.to_string(),
+            file_path,
+        }),
+        Err(err) => Err(anyhow!(
+            "Failed to write `{}`: {}",
+            CHECKSUMS_FILE_NAME,
+            err
+        )),
+    }
+}
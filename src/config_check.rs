@@ -0,0 +1,374 @@
+//! Problems of config files that parse, but can't be right: keys that don't exist and values
+//! that are empty. They're reported together, each with where it is in the file.
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+
+/// Keys serde accepts as another name of a field, which the parsed config only has under the
+/// field's name.
+const KEY_ALIASES: [(&str, &str); 3] = [
+    ("token_env_var", "token_env"),
+    ("dependency", "dependencies"),
+    ("embed_thumbnail_url", "thumbnail_image_url"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    /// Path of the key, e.g. `discord.webhooks[0].embed_color`.
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    pub fn new(key: &str, message: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Checks of values that are valid TOML of the right type, but not valid config.
+pub trait CheckConfig: Serialize {
+    fn problems(&self) -> Vec<ConfigProblem>;
+}
+
+pub fn check_not_empty(problems: &mut Vec<ConfigProblem>, key: &str, value: &str) {
+    if value.trim().is_empty() {
+        problems.push(ConfigProblem::new(
+            key,
+            &format!("`{}` can't be empty", key),
+        ));
+    }
+}
+
+/// Keys of `raw` the parsed config doesn't have, which serde ignored. Every field of the
+/// parsed config is serialized, unset ones as `null`, so they're also the keys suggested
+/// instead.
+pub fn unknown_keys(raw: &toml::Table, parsed: &serde_json::Value) -> Vec<ConfigProblem> {
+    let mut problems = vec![];
+    unknown_table_keys(raw, parsed, "", &mut problems);
+    problems
+}
+
+fn unknown_table_keys(
+    raw: &toml::Table,
+    parsed: &serde_json::Value,
+    path: &str,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let fields = match parsed {
+        serde_json::Value::Object(fields) => fields,
+        _ => return,
+    };
+
+    for (key, value) in raw {
+        let field = KEY_ALIASES
+            .iter()
+            .find(|(alias, _)| alias == key)
+            .map(|(_, field)| *field)
+            .filter(|field| fields.contains_key(*field))
+            .unwrap_or(key);
+        let key_path = join_key(path, key);
+
+        match fields.get(field) {
+            Some(parsed_value) => unknown_value_keys(value, parsed_value, &key_path, problems),
+            None => {
+                let message = match closest_key(key, fields.keys().map(|key| key.as_str())) {
+                    Some(suggestion) => {
+                        format!("unknown key `{}`, did you mean `{}`?", key_path, suggestion)
+                    }
+                    None => format!("unknown key `{}`", key_path),
+                };
+                problems.push(ConfigProblem::new(&key_path, &message));
+            }
+        }
+    }
+}
+
+fn unknown_value_keys(
+    raw: &toml::Value,
+    parsed: &serde_json::Value,
+    path: &str,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    match (raw, parsed) {
+        (toml::Value::Table(raw), _) => unknown_table_keys(raw, parsed, path, problems),
+        (toml::Value::Array(raw), serde_json::Value::Array(parsed)) => {
+            for (index, (raw, parsed)) in raw.iter().zip(parsed).enumerate() {
+                unknown_value_keys(raw, parsed, &format!("{}[{}]", path, index), problems);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+/// The candidate a typo of `key` most likely meant, if any is close enough.
+pub fn closest_key<'a>(key: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+
+    candidates
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Byte ranges of the keys of a TOML document, to point at the keys of problems.
+pub enum KeySpans {
+    Table(Vec<(toml::Spanned<String>, KeySpans)>),
+    Array(Vec<KeySpans>),
+    Value,
+}
+
+impl KeySpans {
+    /// Documents that don't parse have no spans.
+    pub fn parse(content: &str) -> Option<Self> {
+        toml::from_str(content).ok()
+    }
+
+    /// Span of a key path like `discord.webhooks[0].embed_color`.
+    pub fn find(&self, path: &str) -> Option<Range<usize>> {
+        let mut spans = self;
+        let mut found = None;
+
+        for part in path.split('.') {
+            let (key, indices) = match part.find('[') {
+                Some(index) => (&part[..index], &part[index..]),
+                None => (part, ""),
+            };
+
+            let (key_span, value) = match spans {
+                KeySpans::Table(keys) => keys.iter().find(|(name, _)| name.get_ref() == key)?,
+                _ => return None,
+            };
+            found = Some(key_span.span());
+            spans = value;
+
+            for index in indices.split(['[', ']']).filter(|index| !index.is_empty()) {
+                let index = index.parse::<usize>().ok()?;
+                spans = match spans {
+                    KeySpans::Array(items) => items.get(index)?,
+                    _ => return None,
+                };
+            }
+        }
+
+        found
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySpans {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeySpansVisitor)
+    }
+}
+
+struct KeySpansVisitor;
+
+impl<'de> Visitor<'de> for KeySpansVisitor {
+    type Value = KeySpans;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a TOML value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<KeySpans, E> {
+        Ok(KeySpans::Value)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<KeySpans, E> {
+        Ok(KeySpans::Value)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<KeySpans, E> {
+        Ok(KeySpans::Value)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<KeySpans, E> {
+        Ok(KeySpans::Value)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<KeySpans, E> {
+        Ok(KeySpans::Value)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeySpans, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(KeySpans::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<KeySpans, A::Error> {
+        let mut keys = vec![];
+        while let Some(key) = map.next_key()? {
+            keys.push((key, map.next_value()?));
+        }
+        Ok(KeySpans::Table(keys))
+    }
+}
+
+/// 1-based line and column of a byte offset.
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(index) => before[index + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+
+    (line, column)
+}
+
+/// Where the key is, also looking in the profile's table for keys it overrides.
+pub fn key_location(
+    content: &str,
+    spans: Option<&KeySpans>,
+    profile: Option<&str>,
+    key: &str,
+) -> Option<(usize, usize)> {
+    let spans = spans?;
+    let span = spans.find(key).or_else(|| {
+        profile.and_then(|profile| spans.find(&format!("profile.{}.{}", profile, key)))
+    })?;
+
+    Some(line_column(content, span.start))
+}
+
+/// One line per problem in the order of the file, prefixed with its location when it's known.
+pub fn format_problems(content: &str, profile: Option<&str>, problems: &[ConfigProblem]) -> String {
+    let spans = KeySpans::parse(content);
+
+    let mut located = problems
+        .iter()
+        .map(|problem| {
+            let location = key_location(content, spans.as_ref(), profile, &problem.key);
+            (location, problem)
+        })
+        .collect::<Vec<(Option<(usize, usize)>, &ConfigProblem)>>();
+    // Problems without a location go last
+    located.sort_by_key(|(location, _)| location.unwrap_or((usize::MAX, usize::MAX)));
+
+    located
+        .into_iter()
+        .map(|(location, problem)| match location {
+            Some((line, column)) => format!(
+                "\n  - line {}, column {}: {}",
+                line, column, problem.message
+            ),
+            None => format!("\n  - {}", problem.message),
+        })
+        .collect::<String>()
+}
+
+/// Describes an error of deserializing the config, which has no location of its own as it's
+/// deserialized from the merged tables. `span` is where the same error is in the file, when
+/// parsing the file alone fails the same way. A missing field that's close to a key that is
+/// there is pointed out as a typo.
+pub fn describe_parse_error(
+    content: &str,
+    profile: Option<&str>,
+    raw: &toml::Table,
+    err: &toml::de::Error,
+    span: Option<Range<usize>>,
+) -> String {
+    let message = err.message().trim().to_string();
+    // The path of the table is only in the displayed error, as `in `github``
+    let table_path = err
+        .to_string()
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix("in `")
+                .and_then(|rest| rest.strip_suffix('`'))
+        })
+        .map(|path| path.to_string());
+
+    let spans = KeySpans::parse(content);
+    let location = span
+        .map(|span| line_column(content, span.start))
+        .or_else(|| {
+            table_path
+                .as_deref()
+                .and_then(|path| key_location(content, spans.as_ref(), profile, path))
+        });
+
+    let mut description = match &table_path {
+        Some(path) => format!("{} in `{}`", message, path),
+        None => message.clone(),
+    };
+    if let Some((line, column)) = location {
+        description = format!("line {}, column {}: {}", line, column, description);
+    }
+
+    let missing_field = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'));
+    let table = match &table_path {
+        Some(path) => find_table(raw, path),
+        None => Some(raw),
+    };
+
+    if let (Some(field), Some(table)) = (missing_field, table) {
+        let typo =
+            closest_key(field, table.keys().map(|key| key.as_str())).filter(|key| *key != field);
+
+        if let Some(typo) = typo {
+            let typo_path = join_key(table_path.as_deref().unwrap_or(""), typo);
+            description.push_str(&format!(", did you mean `{}` instead of `{}`", field, typo));
+            if let Some((line, column)) = key_location(content, spans.as_ref(), profile, &typo_path)
+            {
+                description.push_str(&format!(" (line {}, column {})", line, column));
+            }
+            description.push('?');
+        }
+    }
+
+    description
+}
+
+fn find_table<'a>(raw: &'a toml::Table, path: &str) -> Option<&'a toml::Table> {
+    let mut table = raw;
+
+    for part in path.split('.') {
+        let (key, indices) = match part.find('[') {
+            Some(index) => (&part[..index], &part[index..]),
+            None => (part, ""),
+        };
+        let mut value = table.get(key)?;
+
+        for index in indices.split(['[', ']']).filter(|index| !index.is_empty()) {
+            value = value.as_array()?.get(index.parse::<usize>().ok()?)?;
+        }
+        table = value.as_table()?;
+    }
+
+    Some(table)
+}
@@ -0,0 +1,82 @@
+//! Tokens and webhook URLs stored in the OS keyring, keyed by the environment variable they
+//! would otherwise be read from. Environment variables always take precedence, so CI is
+//! unaffected.
+
+#[cfg(feature = "keyring")]
+use anyhow::anyhow;
+
+/// Service name of peony's keyring entries.
+pub const KEYRING_SERVICE: &str = "peony";
+
+/// Maps platform names to the default environment variable of their token, anything else is
+/// used as the environment variable name itself.
+pub fn credential_env_var(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "github" => "GITHUB_TOKEN",
+        "gitlab" => "GITLAB_TOKEN",
+        "gitea" => "GITEA_TOKEN",
+        "modrinth" => "MODRINTH_TOKEN",
+        "curseforge" => "CURSEFORGE_TOKEN",
+        "discord" => "WEBHOOK_URL",
+        "slack" => "SLACK_WEBHOOK_URL",
+        "matrix" => "MATRIX_ACCESS_TOKEN",
+        "mastodon" => "MASTODON_TOKEN",
+        "smtp" => "SMTP_PASSWORD",
+        _ => name,
+    }
+    .to_string()
+}
+
+#[cfg(feature = "keyring")]
+pub fn read_credential(env_var: &str) -> Option<String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, env_var).ok()?;
+
+    match entry.get_password() {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(err) => {
+            tracing::debug!("Failed to read `{}` from the keyring: {}", env_var, err);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn read_credential(_env_var: &str) -> Option<String> {
+    None
+}
+
+#[cfg(feature = "keyring")]
+pub fn store_credential(env_var: &str, secret: &str) -> Result<(), anyhow::Error> {
+    let entry = match keyring::Entry::new(KEYRING_SERVICE, env_var) {
+        Ok(entry) => entry,
+        Err(err) => return Err(anyhow!("Failed to open keyring entry: {}", err)),
+    };
+
+    match entry.set_password(secret) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!(
+            "Failed to store `{}` in the keyring: {}",
+            env_var,
+            err
+        )),
+    }
+}
+
+#[cfg(feature = "keyring")]
+pub fn remove_credential(env_var: &str) -> Result<(), anyhow::Error> {
+    let entry = match keyring::Entry::new(KEYRING_SERVICE, env_var) {
+        Ok(entry) => entry,
+        Err(err) => return Err(anyhow!("Failed to open keyring entry: {}", err)),
+    };
+
+    match entry.delete_password() {
+        Ok(_) => Ok(()),
+        Err(keyring::Error::NoEntry) => Err(anyhow!("`{}` isn't in the keyring", env_var)),
+        Err(err) => Err(anyhow!(
+            "Failed to remove `{}` from the keyring: {}",
+            env_var,
+            err
+        )),
+    }
+}
@@ -0,0 +1,376 @@
+use anyhow::anyhow;
+use reqwest::multipart::{Form, Part};
+use serde::de::DeserializeOwned;
+use tracing::{info, warn};
+
+use crate::models::{
+    curseforge::{
+        GameVersion, GameVersionType, ProjectRelation, RelationType, UploadFileRequest,
+        UploadFileResponse, UploadRelations, CURSEFORGE_UPLOAD_API, CURSEFORGE_URL,
+    },
+    modrinth::{version::VersionType, Loader},
+    project_type::mc_mod::{
+        config::{curseforge::CurseforgeConfig as ModCurseforgeConfig, ModConfig},
+        version::ModVersionInfo,
+    },
+    project_type::modpack::config::ModpackConfig,
+    util::OutputFileInfo,
+    CurseforgeConfig, NetworkConfig,
+};
+use crate::progress::UploadBar;
+use crate::redact::Secret;
+use crate::util::{error_body, file_part, file_size, read_secret_env, send_with_retry};
+
+pub async fn create_curseforge_release(
+    config: &ModpackConfig,
+    curseforge_config: &CurseforgeConfig,
+    output_file_info: &OutputFileInfo,
+    changelog: &str,
+    version_name: &str,
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let upload_req = UploadFileRequest {
+        changelog: changelog.to_string(),
+        changelog_type: "markdown".to_string(),
+        display_name: version_name.to_string(),
+        game_versions: curseforge_config.game_versions.clone(),
+        release_type: version_type,
+        relations: None,
+    };
+
+    let project_url = format!(
+        "{}/projects/{}",
+        CURSEFORGE_URL, curseforge_config.project_id
+    );
+
+    if dry_run {
+        info!(
+            "{}",
+            describe_curseforge_release(
+                curseforge_config.project_id,
+                &upload_req,
+                output_file_info
+            )
+        );
+        return Ok(project_url);
+    }
+
+    let curseforge_token = read_secret_env(&curseforge_config.token_env())?;
+
+    info!("Uploading to CurseForge...");
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let bar = UploadBar::new(
+        &output_file_info.file_name,
+        file_size(&output_file_info.file_path)?,
+    );
+    let progress = bar.callback();
+
+    let file_id = upload_file(
+        curseforge_config.project_id,
+        &curseforge_token,
+        &upload_req,
+        || {
+            file_part(
+                &output_file_info.file_path,
+                &output_file_info.file_name,
+                "application/zip",
+                Some(&progress),
+            )
+        },
+        &network,
+    )
+    .await?;
+
+    Ok(match file_id {
+        Some(id) => format!("{}/files/{}", project_url, id),
+        None => project_url,
+    })
+}
+
+/// Uploads a jar of the mod, tagged with its Minecraft versions and loaders and related to
+/// the projects its metadata depends on.
+pub async fn create_mod_curseforge_release(
+    config: &ModConfig,
+    curseforge_config: &ModCurseforgeConfig,
+    mod_files: &ModVersionInfo,
+    changelog: &str,
+    version_name: &str,
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let network = config.project.network.clone().unwrap_or_default();
+    let project_url = format!(
+        "{}/projects/{}",
+        CURSEFORGE_URL, curseforge_config.project_id
+    );
+
+    // The game version IDs are looked up with the token, so dry runs only show configured ones
+    let game_versions = match &curseforge_config.game_versions {
+        Some(game_versions) => game_versions.clone(),
+        None if dry_run => vec![],
+        None => {
+            let curseforge_token = read_secret_env(&curseforge_config.token_env())?;
+            get_game_version_ids(
+                &curseforge_token,
+                &mod_files.game_versions,
+                &mod_files.loaders,
+                &network,
+            )
+            .await?
+        }
+    };
+
+    let upload_req = UploadFileRequest {
+        changelog: changelog.to_string(),
+        changelog_type: "markdown".to_string(),
+        display_name: version_name.to_string(),
+        game_versions,
+        release_type: version_type,
+        relations: mod_relations(curseforge_config, mod_files),
+    };
+
+    if dry_run {
+        info!(
+            "{}",
+            describe_curseforge_release(
+                curseforge_config.project_id,
+                &upload_req,
+                &OutputFileInfo {
+                    file_name: mod_files.mod_file.name.clone(),
+                    file_path: mod_files.mod_file.path.clone(),
+                }
+            )
+        );
+        return Ok(project_url);
+    }
+
+    let curseforge_token = read_secret_env(&curseforge_config.token_env())?;
+
+    info!("Uploading `{}` to CurseForge...", mod_files.mod_file.name);
+
+    let file_id = upload_file(
+        curseforge_config.project_id,
+        &curseforge_token,
+        &upload_req,
+        || {
+            file_part(
+                &mod_files.mod_file.path,
+                &mod_files.mod_file.name,
+                "application/java-archive",
+                None,
+            )
+        },
+        &network,
+    )
+    .await?;
+
+    Ok(match file_id {
+        Some(id) => format!("{}/files/{}", project_url, id),
+        None => project_url,
+    })
+}
+
+/// The relations of the config, then what the jar's metadata depends on as long as there's a
+/// slug for the mod ID.
+fn mod_relations(
+    curseforge_config: &ModCurseforgeConfig,
+    mod_files: &ModVersionInfo,
+) -> Option<UploadRelations> {
+    let mut projects = curseforge_config.relations.clone().unwrap_or_default();
+    let slugs = curseforge_config.slugs();
+    let mut unmapped: Vec<&str> = vec![];
+
+    for dependency in &mod_files.dependencies {
+        match slugs.get(&dependency.mod_id) {
+            Some(slug) if !projects.iter().any(|project| project.slug == *slug) => {
+                projects.push(ProjectRelation {
+                    slug: slug.clone(),
+                    relation_type: RelationType::from(dependency.dependency_type),
+                })
+            }
+            Some(_) => (),
+            None => unmapped.push(&dependency.mod_id),
+        }
+    }
+
+    if !unmapped.is_empty() {
+        warn!(
+            "No CurseForge project for the mod IDs {}, add them to `curseforge.slugs` to list \
+             them as relations",
+            unmapped
+                .iter()
+                .map(|mod_id| format!("`{}`", mod_id))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+
+    match projects.is_empty() {
+        true => None,
+        false => Some(UploadRelations { projects }),
+    }
+}
+
+/// Looks up the ids CurseForge has for the Minecraft versions and loaders, failing on any it
+/// doesn't know.
+pub async fn get_game_version_ids(
+    curseforge_token: &Secret,
+    game_versions: &[String],
+    loaders: &[Loader],
+    network: &NetworkConfig,
+) -> Result<Vec<u32>, anyhow::Error> {
+    let version_types: Vec<GameVersionType> =
+        get_upload_api(curseforge_token, "game/version-types", network).await?;
+    let versions: Vec<GameVersion> =
+        get_upload_api(curseforge_token, "game/versions", network).await?;
+
+    let type_ids = |matches: &dyn Fn(&str) -> bool| {
+        version_types
+            .iter()
+            .filter(|version_type| matches(&version_type.slug))
+            .map(|version_type| version_type.id)
+            .collect::<Vec<u32>>()
+    };
+    let minecraft_types = type_ids(&|slug| slug.starts_with("minecraft-"));
+    let loader_types = type_ids(&|slug| slug == "modloader");
+
+    let find = |type_ids: &[u32], name: &str| {
+        versions
+            .iter()
+            .find(|version| {
+                type_ids.contains(&version.game_version_type_id)
+                    && version.name.eq_ignore_ascii_case(name)
+            })
+            .map(|version| version.id)
+    };
+
+    let mut ids = vec![];
+    for game_version in game_versions {
+        match find(&minecraft_types, game_version) {
+            Some(id) => ids.push(id),
+            None => {
+                return Err(anyhow!(
+                    "CurseForge has no Minecraft version `{}`",
+                    game_version
+                ))
+            }
+        }
+    }
+    for loader in loaders {
+        match find(&loader_types, &loader.formatted()) {
+            Some(id) => ids.push(id),
+            None => return Err(anyhow!("CurseForge has no loader `{}`", loader.formatted())),
+        }
+    }
+
+    Ok(ids)
+}
+
+async fn get_upload_api<T: DeserializeOwned>(
+    curseforge_token: &Secret,
+    path: &str,
+    network: &NetworkConfig,
+) -> Result<T, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!("{}/{}", CURSEFORGE_UPLOAD_API, path))
+            .header("X-Api-Token", curseforge_token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get CurseForge `{}`: {}", path, err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to get CurseForge `{}`: {}",
+            path,
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<T>().await {
+        Ok(json) => Ok(json),
+        Err(err) => Err(anyhow!("Failed to parse CurseForge `{}`: {}", path, err)),
+    }
+}
+
+/// Returns the id of the uploaded file. CurseForge explains rejected uploads in a JSON body,
+/// which is passed on as it is.
+async fn upload_file(
+    project_id: u32,
+    curseforge_token: &Secret,
+    upload_req: &UploadFileRequest,
+    file_part: impl Fn() -> Result<Part, anyhow::Error>,
+    network: &NetworkConfig,
+) -> Result<Option<u32>, anyhow::Error> {
+    let metadata = match serde_json::to_string(upload_req) {
+        Ok(json) => json,
+        Err(err) => return Err(anyhow!("Failed to serialize CurseForge metadata: {}", err)),
+    };
+
+    let res = match send_with_retry(network, |client| {
+        let form = Form::new()
+            .text("metadata", metadata.clone())
+            .part("file", file_part()?);
+
+        Ok(client
+            .post(format!(
+                "{}/projects/{}/upload-file",
+                CURSEFORGE_UPLOAD_API, project_id
+            ))
+            .header("X-Api-Token", curseforge_token.expose())
+            .multipart(form)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Error uploading file to CurseForge: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to upload file to CurseForge: {}",
+            error_body(res).await
+        ));
+    }
+
+    info!("Successfully uploaded file to CurseForge!");
+
+    Ok(res
+        .json::<UploadFileResponse>()
+        .await
+        .ok()
+        .map(|json| json.id))
+}
+
+pub fn describe_curseforge_release(
+    project_id: u32,
+    upload_req: &UploadFileRequest,
+    output_file_info: &OutputFileInfo,
+) -> String {
+    format!(
+        "[dry run] Would upload file to CurseForge project `{}`:\n  \
+         Display name: {}\n  \
+         Release type: {}\n  \
+         Game versions: {}\n  \
+         File: {}\n  \
+         Changelog:\n{}",
+        project_id,
+        upload_req.display_name,
+        upload_req.release_type.formatted(),
+        upload_req
+            .game_versions
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        output_file_info.file_name,
+        upload_req.changelog
+    )
+}
@@ -0,0 +1,255 @@
+//! `peony diff`, what changed between two exported .mrpack files.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use tracing::warn;
+
+use crate::models::{
+    diff::{DependencyChange, ModChange, OverrideChanges, PackDiff},
+    modrinth::ModrinthUrl,
+    project_type::modpack::index::{MrpackContents, MrpackIndexFile},
+    NetworkConfig,
+};
+use crate::modrinth::get_versions_from_hashes;
+use crate::pack::read_mrpack;
+
+/// Reads both packs and looks up the version numbers of changed files on Modrinth. When
+/// that fails, the diff only has file names.
+pub async fn diff_mrpacks(
+    old_path: &Path,
+    new_path: &Path,
+    modrinth_url: &ModrinthUrl,
+    network: &NetworkConfig,
+) -> Result<PackDiff, anyhow::Error> {
+    let old = read_mrpack(old_path)?;
+    let new = read_mrpack(new_path)?;
+
+    let mut diff = diff_contents(&old, &new);
+
+    if let Err(err) = resolve_versions(&mut diff, modrinth_url, network).await {
+        warn!("{}, showing file names only", err);
+    }
+
+    Ok(diff)
+}
+
+/// Files are matched by their Modrinth project, falling back to the path, like the `index`
+/// changelog source does.
+pub fn diff_contents(old: &MrpackContents, new: &MrpackContents) -> PackDiff {
+    let old_files: HashMap<String, &MrpackIndexFile> = old
+        .index
+        .files
+        .iter()
+        .map(|file| (file.key(), file))
+        .collect();
+    let new_files: HashMap<String, &MrpackIndexFile> = new
+        .index
+        .files
+        .iter()
+        .map(|file| (file.key(), file))
+        .collect();
+
+    let mut diff = PackDiff::default();
+
+    for file in &new.index.files {
+        match old_files.get(&file.key()) {
+            None => diff.added.push(mod_change(None, Some(file))),
+            // Files that are replaced without being renamed only differ in their hashes
+            Some(old_file) if old_file.path != file.path || old_file.hashes != file.hashes => {
+                diff.updated.push(mod_change(Some(old_file), Some(file)))
+            }
+            Some(_) => (),
+        }
+    }
+
+    for file in &old.index.files {
+        if !new_files.contains_key(&file.key()) {
+            diff.removed.push(mod_change(Some(file), None));
+        }
+    }
+
+    for changes in [&mut diff.added, &mut diff.removed, &mut diff.updated] {
+        changes.sort_by_key(|change| change.file_name().to_lowercase());
+    }
+
+    let dependency_names = old
+        .index
+        .dependencies
+        .keys()
+        .chain(new.index.dependencies.keys())
+        .collect::<BTreeSet<&String>>();
+
+    for name in dependency_names {
+        let old_version = old.index.dependencies.get(name);
+        let new_version = new.index.dependencies.get(name);
+
+        if old_version != new_version {
+            diff.dependencies.push(DependencyChange {
+                name: name.clone(),
+                old: old_version.cloned(),
+                new: new_version.cloned(),
+            });
+        }
+    }
+
+    diff.overrides = diff_overrides(&old.overrides, &new.overrides);
+
+    diff
+}
+
+fn mod_change(old: Option<&MrpackIndexFile>, new: Option<&MrpackIndexFile>) -> ModChange {
+    let sha1 =
+        |file: Option<&MrpackIndexFile>| file.and_then(|file| file.hashes.get("sha1").cloned());
+
+    ModChange {
+        project_id: new.or(old).and_then(|file| file.modrinth_project_id()),
+        old_path: old.map(|file| file.path.clone()),
+        new_path: new.map(|file| file.path.clone()),
+        old_version: None,
+        new_version: None,
+        old_sha1: sha1(old),
+        new_sha1: sha1(new),
+    }
+}
+
+fn diff_overrides(old: &BTreeMap<String, u32>, new: &BTreeMap<String, u32>) -> OverrideChanges {
+    let mut changes = OverrideChanges::default();
+
+    for (path, crc32) in new {
+        match old.get(path) {
+            None => changes.added.push(path.clone()),
+            Some(old_crc32) if old_crc32 != crc32 => changes.changed.push(path.clone()),
+            Some(_) => (),
+        }
+    }
+
+    changes.removed = old
+        .keys()
+        .filter(|path| !new.contains_key(*path))
+        .cloned()
+        .collect();
+
+    changes
+}
+
+/// One request for every changed file, hashes Modrinth doesn't know keep their file name.
+async fn resolve_versions(
+    diff: &mut PackDiff,
+    modrinth_url: &ModrinthUrl,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    let changes = diff
+        .added
+        .iter_mut()
+        .chain(diff.removed.iter_mut())
+        .chain(diff.updated.iter_mut())
+        .collect::<Vec<&mut ModChange>>();
+
+    let hashes = changes
+        .iter()
+        .flat_map(|change| [change.old_sha1.clone(), change.new_sha1.clone()])
+        .flatten()
+        .collect::<Vec<String>>();
+
+    if hashes.is_empty() {
+        return Ok(());
+    }
+
+    let versions = get_versions_from_hashes(modrinth_url, hashes, "sha1", network).await?;
+    let version_number = |sha1: &Option<String>| {
+        sha1.as_ref()
+            .and_then(|sha1| versions.get(sha1))
+            .map(|version| version.version_number.clone())
+    };
+
+    for change in changes {
+        change.old_version = version_number(&change.old_sha1);
+        change.new_version = version_number(&change.new_sha1);
+    }
+
+    Ok(())
+}
+
+/// Sections like the `index` changelog source's, empty ones are left out.
+pub fn diff_markdown(diff: &PackDiff, modrinth_url: &ModrinthUrl) -> String {
+    if diff.is_empty() {
+        return "No changes".to_string();
+    }
+
+    let link = |change: &ModChange| match &change.project_id {
+        Some(project_id) => format!(
+            "[{}]({}/project/{})",
+            change.file_name(),
+            modrinth_url.knossos,
+            project_id
+        ),
+        None => format!("`{}`", change.file_name()),
+    };
+    let version = |version: &Option<String>, path: &Option<String>| match (version, path) {
+        (Some(version), _) => version.clone(),
+        (None, Some(path)) => path.rsplit('/').next().unwrap_or(path).to_string(),
+        (None, None) => "none".to_string(),
+    };
+    let with_version = |change: &ModChange, version: &Option<String>| match version {
+        Some(version) => format!("- {} `{}`", link(change), version),
+        None => format!("- {}", link(change)),
+    };
+
+    let dependencies = diff
+        .dependencies
+        .iter()
+        .map(|dependency| {
+            format!(
+                "- {}: `{}` → `{}`",
+                dependency.name,
+                dependency.old.as_deref().unwrap_or("none"),
+                dependency.new.as_deref().unwrap_or("none")
+            )
+        })
+        .collect::<Vec<String>>();
+    let added = diff
+        .added
+        .iter()
+        .map(|change| with_version(change, &change.new_version))
+        .collect();
+    let removed = diff
+        .removed
+        .iter()
+        .map(|change| with_version(change, &change.old_version))
+        .collect();
+    let updated = diff
+        .updated
+        .iter()
+        .map(|change| {
+            let old = version(&change.old_version, &change.old_path);
+            let new = version(&change.new_version, &change.new_path);
+
+            match old == new {
+                // Only the hashes differ and Modrinth doesn't know the files
+                true => format!("- {}: file changed", link(change)),
+                false => format!("- {}: `{}` → `{}`", link(change), old, new),
+            }
+        })
+        .collect();
+    let overrides = |paths: &[String]| {
+        paths
+            .iter()
+            .map(|path| format!("- `{}`", path))
+            .collect::<Vec<String>>()
+    };
+
+    [
+        ("Dependencies", dependencies),
+        ("Added mods", added),
+        ("Removed mods", removed),
+        ("Updated mods", updated),
+        ("Added overrides", overrides(&diff.overrides.added)),
+        ("Removed overrides", overrides(&diff.overrides.removed)),
+        ("Changed overrides", overrides(&diff.overrides.changed)),
+    ]
+    .into_iter()
+    .filter(|(_, lines)| !lines.is_empty())
+    .map(|(title, lines)| format!("### {}\n{}", title, lines.join("\n")))
+    .collect::<Vec<String>>()
+    .join("\n\n")
+}
@@ -1,118 +1,1390 @@
+use crate::models::discord::{
+    AllowedMentions, Attachment, Embed, EmbedField, EmbedFooter, EmbedImage, ExecuteWebhookRequest,
+    WebhookFile,
+};
 use crate::models::modrinth::project::ProjectResponse;
 use crate::models::modrinth::ModrinthUrl;
-use crate::models::{DiscordConfig, GithubConfig};
+use crate::models::{
+    forge::Forge,
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    DiscordConfig, NetworkConfig,
+};
+use crate::util::{error_body, read_token, read_webhook_env, send_with_retry};
+use crate::version::render_template;
 use anyhow::anyhow;
 use chrono::Utc;
-use serenity::model::channel::Embed;
-use serenity::model::webhook::Webhook;
-use std::env;
+use reqwest::multipart::{Form, Part};
+use tracing::{info, warn};
 
-pub async fn send_discord_webhook(
+// https://discord.com/developers/docs/resources/channel#embed-object-embed-limits
+const EMBED_TITLE_LIMIT: usize = 256;
+const EMBED_FOOTER_LIMIT: usize = 2048;
+pub const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+const EMBED_TOTAL_LIMIT: usize = 6000;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn send_discord_notification(
     discord_config: &DiscordConfig,
+    release: &ReleaseSummary,
     modrinth_url: &ModrinthUrl,
-    modrinth_project_id: &String,
-    github_config: &GithubConfig,
-    version_name: &String,
-    changelog: &String,
+    // The project ID and token environment variable, when Modrinth is configured
+    modrinth: Option<(&str, String)>,
+    forge: Option<Forge<'_>>,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<Vec<WebhookResult>, anyhow::Error> {
+    // The project is only looked up when the version was published to Modrinth,
+    // so that Modrinth-less releases don't need a Modrinth token.
+    let modrinth_project = match modrinth {
+        Some((project_id, token_env)) if release.is_published(Platform::Modrinth) => {
+            Some(get_modrinth_project(modrinth_url, project_id, &token_env, network).await?)
+        }
+        _ => None,
+    };
+
+    let fields = match discord_config.show_fields.unwrap_or(false) {
+        true => {
+            let mut fields = release_fields(release);
+            fields.extend(file_fields(release));
+            fields
+        }
+        false => vec![],
+    };
+
+    let values = description_values(discord_config, release, modrinth_url, &modrinth_project);
+    let template = discord_config.description_template();
+
+    let now = Utc::now();
+    let release_time = now.format("%b, %d %Y %r");
+    let release_timestamp = now.to_rfc3339();
+
+    let title = truncate_chars(
+        &format!("{} {}", discord_config.title_emoji, release.version_name),
+        EMBED_TITLE_LIMIT,
+    );
+
+    let footer = truncate_chars(
+        &render_footer(
+            discord_config,
+            release,
+            &modrinth_project,
+            &format!("{} UTC", release_time),
+        )?,
+        EMBED_FOOTER_LIMIT,
+    );
+
+    // The changelog gets whatever is left of both the description and the total embed limit
+    let description_limit = description_limit(&title, &footer, &fields);
+    let changelog_budget = description_limit
+        .saturating_sub(render_description(&template, &values, "")?.chars().count());
+
+    let full_changelog_url = forge.map(|forge| match release.url(forge.platform()) {
+        Some(url) => url.clone(),
+        None => forge.release_url(&forge.tag_name(&release.version_number)),
+    });
+
+    let changelog = release
+        .changelog
+        .render_discord(changelog_budget, full_changelog_url.as_deref());
+
+    // Only attached when the embed doesn't have all of it
+    let changelog_file = match discord_config.attach_changelog.unwrap_or(false)
+        && changelog
+            != release
+                .changelog
+                .render_discord(usize::MAX, full_changelog_url.as_deref())
+    {
+        true => Some(WebhookFile {
+            file_name: changelog_file_name(&release.version_number),
+            content: release.changelog.render_github(),
+        }),
+        false => None,
+    };
+    let description = truncate_chars(
+        &render_description(&template, &values, &changelog)?,
+        description_limit,
+    );
+
+    let thread_name = discord_config.thread_name(&release.version_name);
+
+    let project_color = modrinth_project.as_ref().and_then(|project| project.color);
+
+    let mut results = vec![];
+
+    // Webhooks are executed independently, so that one failing doesn't stop the others
+    for webhook_config in discord_config.webhooks() {
+        let embed_color = match webhook_config.embed_color.or(discord_config.embed_color) {
+            Some(color) => color.0,
+            None => match project_color {
+                Some(color) => color as u32,
+                None => 0x232634,
+            },
+        };
+
+        let ping_role = webhook_config
+            .discord_ping_role
+            .clone()
+            .unwrap_or_else(|| discord_config.discord_ping_role.clone());
+
+        let embed = Embed {
+            title: title.clone(),
+            description: description.clone(),
+            color: embed_color,
+            image: discord_config
+                .embed_image_url
+                .clone()
+                .map(|url| EmbedImage { url }),
+            thumbnail: discord_config
+                .thumbnail_image_url
+                .clone()
+                .map(|url| EmbedImage { url }),
+            fields: fields.clone(),
+            footer: EmbedFooter {
+                text: footer.clone(),
+            },
+            timestamp: release_timestamp.clone(),
+        };
+
+        let role_id = role_id_from_mention(&ping_role);
+
+        let request = ExecuteWebhookRequest {
+            content: match &role_id {
+                Some(id) => format!("<@&{}>", id),
+                None => ping_role,
+            },
+            embeds: vec![embed],
+            thread_name: thread_name.clone(),
+            // Only the configured role is pinged, even if the content mentions others
+            allowed_mentions: AllowedMentions {
+                parse: if discord_config.everyone.unwrap_or(false) {
+                    vec!["everyone".to_string()]
+                } else {
+                    vec![]
+                },
+                roles: role_id.into_iter().collect(),
+            },
+            attachments: changelog_file
+                .iter()
+                .enumerate()
+                .map(|(id, file)| Attachment {
+                    id,
+                    filename: file.file_name.clone(),
+                })
+                .collect(),
+        };
+
+        if dry_run {
+            let request_preview = match serde_json::to_string_pretty(&request) {
+                Ok(json) => json,
+                Err(err) => return Err(anyhow!("Failed to serialize Discord webhook: {}", err)),
+            };
+
+            info!(
+                "[dry run] Would send Discord webhook `{}`:\n{}",
+                webhook_config.env_var, request_preview
+            );
+            if let Some(file) = &changelog_file {
+                info!("[dry run] Would attach `{}`", file.file_name);
+            }
+            continue;
+        }
+
+        let error = match execute_webhook(
+            &webhook_config.env_var,
+            &request,
+            changelog_file.as_ref(),
+            discord_config.thread_id,
+            network,
+        )
+        .await
+        {
+            Ok(_) => {
+                info!("Sent Discord webhook `{}`!", webhook_config.env_var);
+                None
+            }
+            Err(err) => {
+                warn!("{}", err);
+                Some(err.to_string())
+            }
+        };
+
+        results.push(WebhookResult {
+            notifier: Notifier::Discord,
+            target: webhook_config.env_var,
+            error,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Rate limits are handled by `send_with_retry`, as Discord sends `Retry-After` on 429s.
+/// With a `file`, the request is sent as multipart with the payload in `payload_json`.
+pub async fn execute_webhook(
+    env_var: &str,
+    request: &ExecuteWebhookRequest,
+    file: Option<&WebhookFile>,
+    thread_id: Option<u64>,
+    network: &NetworkConfig,
 ) -> Result<(), anyhow::Error> {
-    let modrinth_token = match env::var("MODRINTH_TOKEN") {
-        Ok(token) => token,
+    let url = read_webhook_env(env_var)?;
+
+    let mut url = match reqwest::Url::parse(url.expose()) {
+        Ok(url) => url,
         Err(err) => {
             return Err(anyhow!(
-                "Failed to get `MODRINTH_TOKEN` from environment: {}",
+                "Invalid Discord webhook url in `{}`: {}",
+                env_var,
                 err
             ))
         }
     };
 
-    let modrinth_project = match reqwest::Client::new()
-        .get(format!(
-            "{}/project/{}",
-            modrinth_url.labrinth, modrinth_project_id
-        ))
-        .header("Authorization", modrinth_token)
-        .send()
-        .await
+    url.query_pairs_mut().append_pair("wait", "true");
+    if let Some(thread_id) = thread_id {
+        url.query_pairs_mut()
+            .append_pair("thread_id", &thread_id.to_string());
+    }
+
+    let payload_json = match serde_json::to_string(request) {
+        Ok(json) => json,
+        Err(err) => return Err(anyhow!("Failed to serialize Discord webhook: {}", err)),
+    };
+
+    let res = match send_with_retry(network, |client| {
+        let builder = client.post(url.clone());
+
+        match file {
+            Some(file) => {
+                let part = match Part::text(file.content.clone())
+                    .file_name(file.file_name.clone())
+                    .mime_str("text/markdown")
+                {
+                    Ok(part) => part,
+                    Err(err) => {
+                        return Err(anyhow!("Failed to attach `{}`: {}", file.file_name, err))
+                    }
+                };
+                let form = Form::new()
+                    .text("payload_json", payload_json.clone())
+                    .part("files[0]", part);
+
+                Ok(builder.multipart(form))
+            }
+            None => Ok(builder.json(request)),
+        }
+    })
+    .await
     {
-        Ok(res) => match res.json::<ProjectResponse>().await {
-            Ok(json) => json,
-            Err(err) => {
-                return Err(anyhow!(
-                    "Error parsing response from get project: {}\n\
-                     Make sure your Modrinth token can read projects!",
-                    err.to_string()
-                ))
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to send Discord webhook `{}`: {}",
+                env_var,
+                err
+            ))
+        }
+    };
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to send Discord webhook `{}`: {}",
+            env_var,
+            error_body(res).await
+        ))
+    }
+}
+
+/// The embed's `timestamp` is shown next to the footer in the time zone of each viewer, so the
+/// release time is only part of it with `legacy_footer_time`.
+pub fn render_footer(
+    discord_config: &DiscordConfig,
+    release: &ReleaseSummary,
+    modrinth_project: &Option<ProjectResponse>,
+    release_time: &str,
+) -> Result<String, anyhow::Error> {
+    let project_type = modrinth_project
+        .as_ref()
+        .map(|project| project.project_type.formatted())
+        .unwrap_or_default();
+    let version_type = release.version_type.formatted();
+
+    if let Some(template) = &discord_config.footer_text {
+        let footer = render_template(
+            "discord.footer_text",
+            template,
+            &[
+                ("project_type", project_type.as_str()),
+                ("version_type", version_type.as_str()),
+                ("version", release.version_name.as_str()),
+                ("release_time", release_time),
+            ],
+        )?;
+        return Ok(footer.trim().to_string());
+    }
+
+    let mut parts = vec![project_type, version_type];
+    if discord_config.legacy_footer_time.unwrap_or(false) {
+        parts.push(release_time.to_string());
+    }
+
+    Ok(parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join(" | "))
+}
+
+/// Values of the description placeholders, empty for platforms the release isn't on.
+pub fn description_values(
+    discord_config: &DiscordConfig,
+    release: &ReleaseSummary,
+    modrinth_url: &ModrinthUrl,
+    modrinth_project: &Option<ProjectResponse>,
+) -> Vec<(&'static str, String)> {
+    let url = |platform| release.url(platform).cloned().unwrap_or_default();
+
+    // The details are embed fields instead when `show_fields` is set
+    let release_info = match discord_config.show_fields.unwrap_or(false) {
+        true => String::new(),
+        false => release_fields(release)
+            .iter()
+            .map(|field| format!("{}: {}", field.name, field.value))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+
+    let maven = match (
+        &release.maven_coordinates,
+        discord_config.show_maven.unwrap_or(false),
+    ) {
+        (Some(coordinates), true) => format!("Maven:\n```\n{}\n```", coordinates),
+        _ => String::new(),
+    };
+
+    let downloads = release
+        .downloads
+        .iter()
+        .map(|download| {
+            format!(
+                "{}: [{}]({})",
+                download
+                    .loaders
+                    .iter()
+                    .map(|loader| loader.formatted())
+                    .collect::<Vec<String>>()
+                    .join("/"),
+                download.file_name,
+                download.url
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let server_pack = match (&release.server_pack_file_name, &release.server_pack_url) {
+        (Some(file_name), Some(url)) => format!("Server pack: [{}]({})", file_name, url),
+        _ => String::new(),
+    };
+
+    vec![
+        ("version", release.version_name.clone()),
+        ("version_number", release.version_number.clone()),
+        ("version_type", release.version_type.formatted()),
+        ("mc_version", release.game_versions.join(", ")),
+        (
+            "loaders",
+            release
+                .loaders
+                .iter()
+                .map(|loader| loader.formatted())
+                .collect::<Vec<String>>()
+                .join(", "),
+        ),
+        (
+            "pack_name",
+            modrinth_project
+                .as_ref()
+                .map(|project| project.title.clone())
+                .unwrap_or_default(),
+        ),
+        ("release_info", release_info),
+        ("github_emoji", discord_config.github_emoji_id.clone()),
+        ("github_url", url(Platform::Github)),
+        (
+            "gitlab_emoji",
+            discord_config.gitlab_emoji_id.clone().unwrap_or_default(),
+        ),
+        ("gitlab_url", url(Platform::Gitlab)),
+        (
+            "gitea_emoji",
+            discord_config.gitea_emoji_id.clone().unwrap_or_default(),
+        ),
+        ("gitea_url", url(Platform::Gitea)),
+        ("modrinth_emoji", discord_config.modrinth_emoji_id.clone()),
+        (
+            "modrinth_url",
+            // Linked to the project page, which is only looked up once the version is published
+            match modrinth_project {
+                Some(project) => format!(
+                    "{}/{}/{}",
+                    modrinth_url.knossos,
+                    project.project_type.url_segment(),
+                    project.slug
+                ),
+                None => String::new(),
+            },
+        ),
+        (
+            "curseforge_emoji",
+            discord_config
+                .curseforge_emoji_id
+                .clone()
+                .unwrap_or_default(),
+        ),
+        ("curseforge_url", url(Platform::Curseforge)),
+        ("maven", maven),
+        ("downloads", downloads),
+        ("server_pack", server_pack),
+        (
+            "server_pack_url",
+            release.server_pack_url.clone().unwrap_or_default(),
+        ),
+    ]
+}
+
+/// Renders the description template with `changelog` for `{changelog}`. Lines with an empty
+/// `{..._url}` are left out, so that platforms the release isn't on don't leave an emoji
+/// behind, as are lines of only empty placeholders. The blank lines around them are merged.
+pub fn render_description(
+    template: &str,
+    values: &[(&'static str, String)],
+    changelog: &str,
+) -> Result<String, anyhow::Error> {
+    let mut placeholders = values
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect::<Vec<(&str, &str)>>();
+    placeholders.push(("changelog", changelog));
+
+    let is_empty = |name: &str| {
+        placeholders
+            .iter()
+            .any(|(key, value)| *key == name && value.trim().is_empty())
+    };
+
+    let mut kept: Vec<&str> = vec![];
+    for line in template.lines() {
+        let names = line_placeholders(line);
+        let rest = names.iter().fold(line.to_string(), |rest, name| {
+            rest.replace(&format!("{{{}}}", name), "")
+        });
+
+        let missing_url = names
+            .iter()
+            .any(|name| name.ends_with("_url") && is_empty(name));
+        let only_empty =
+            !names.is_empty() && names.iter().all(|name| is_empty(name)) && rest.trim().is_empty();
+
+        if missing_url || only_empty {
+            continue;
+        }
+        // Blank lines left next to each other by the lines above
+        if line.trim().is_empty() && kept.last().is_none_or(|last| last.trim().is_empty()) {
+            continue;
+        }
+        kept.push(line);
+    }
+
+    let rendered = render_template(
+        "discord.description_template",
+        &kept.join("\n"),
+        &placeholders,
+    )?;
+
+    Ok(rendered.trim().to_string())
+}
+
+/// Names of the `{placeholder}`s of a template line, skipping `{{` and `}}`.
+fn line_placeholders(line: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let name = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                names.push(name);
             }
+            _ => (),
+        }
+    }
+
+    names
+}
+
+pub fn release_fields(release: &ReleaseSummary) -> Vec<EmbedField> {
+    let mut fields = vec![EmbedField {
+        name: "Version".to_string(),
+        value: format!("`{}`", release.version_number),
+        inline: true,
+    }];
+
+    if !release.game_versions.is_empty() {
+        fields.push(EmbedField {
+            name: "Minecraft".to_string(),
+            value: release.game_versions.join(", "),
+            inline: true,
+        });
+    }
+
+    if !release.loaders.is_empty() {
+        fields.push(EmbedField {
+            name: "Loaders".to_string(),
+            value: release
+                .loaders
+                .iter()
+                .map(|loader| loader.formatted())
+                .collect::<Vec<String>>()
+                .join(", "),
+            inline: true,
+        });
+    }
+
+    fields
+}
+
+pub fn file_fields(release: &ReleaseSummary) -> Vec<EmbedField> {
+    let mut fields = vec![];
+
+    if let Some(file_name) = &release.file_name {
+        fields.push(EmbedField {
+            name: "File".to_string(),
+            value: format!("`{}`", file_name),
+            inline: true,
+        });
+    }
+
+    if let Some(file_size) = release.file_size {
+        fields.push(EmbedField {
+            name: "File size".to_string(),
+            value: format!("{:.2} MiB", file_size as f64 / (1024.0 * 1024.0)),
+            inline: true,
+        });
+    }
+
+    if let Some(mod_count) = release.mod_count {
+        fields.push(EmbedField {
+            name: "Mods".to_string(),
+            value: mod_count.to_string(),
+            inline: true,
+        });
+    }
+
+    fields
+}
+
+/// Accepts both a raw role id and a `<@&id>` mention.
+pub fn role_id_from_mention(ping_role: &str) -> Option<String> {
+    let ping_role = ping_role.trim();
+    let id = match ping_role.strip_prefix("<@&") {
+        Some(rest) => rest.strip_suffix('>')?,
+        None => ping_role,
+    };
+
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) {
+        Some(id.to_string())
+    } else {
+        None
+    }
+}
+
+/// What's left of the total embed limit once the title, footer and fields are counted,
+/// capped at the description limit.
+pub fn description_limit(title: &str, footer: &str, fields: &[EmbedField]) -> usize {
+    let fields_length: usize = fields
+        .iter()
+        .map(|field| field.name.chars().count() + field.value.chars().count())
+        .sum();
+
+    EMBED_DESCRIPTION_LIMIT.min(
+        EMBED_TOTAL_LIMIT
+            .saturating_sub(title.chars().count() + footer.chars().count() + fields_length),
+    )
+}
+
+pub fn truncate_chars(text: &str, limit: usize) -> String {
+    text.chars().take(limit).collect()
+}
+
+/// Cuts long changelogs at the last line break that fits, so that Markdown links and
+/// list items aren't split, and links to the full changelog instead.
+pub fn fit_changelog(changelog: &str, limit: usize, full_changelog_url: Option<&str>) -> String {
+    if changelog.chars().count() <= limit {
+        return changelog.to_string();
+    }
+
+    let suffix = match full_changelog_url {
+        Some(url) => format!("\n\n… [Full changelog]({})", url),
+        None => "\n\n…".to_string(),
+    };
+    let truncated = truncate_chars(changelog, limit.saturating_sub(suffix.chars().count()));
+
+    let cut = match truncated.rfind('\n') {
+        Some(index) => &truncated[..index],
+        None => match truncated.rfind(' ') {
+            Some(index) => &truncated[..index],
+            None => "",
         },
-        Err(err) => return Err(anyhow!("Error getting project from project id: {}", err)),
     };
 
-    let description = format!(
-        "\
-        **New release!**\n\n\
-        {} [GitHub](https://github.com/{}/{}/releases/latest)\n\
-        {} [Modrinth]({}/project/{})\n\n\
-        {}
-        ",
-        discord_config.github_emoji_id,
-        github_config.repo_owner,
-        github_config.repo_name,
-        discord_config.modrinth_emoji_id,
-        modrinth_url.knossos,
-        modrinth_project.slug,
-        changelog
-    );
+    truncate_chars(&format!("{}{}", cut.trim_end(), suffix), limit)
+}
 
-    let embed_color = match discord_config.embed_color {
-        Some(color) => color,
-        None => match discord_config.embed_color {
-            Some(color) => color,
-            None => match modrinth_project.color {
-                Some(color) => color as u32,
-                None => 0x232634,
+/// `CHANGELOG-<version>.md`, with anything but letters, digits, `.`, `-`, `_` and `+` of the
+/// version replaced.
+pub fn changelog_file_name(version: &str) -> String {
+    let version = version
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+') {
+                true => c,
+                false => '-',
             },
+        )
+        .collect::<String>();
+
+    format!("CHANGELOG-{}.md", version)
+}
+
+pub async fn get_modrinth_project(
+    modrinth_url: &ModrinthUrl,
+    project_id: &str,
+    token_env: &str,
+    network: &NetworkConfig,
+) -> Result<ProjectResponse, anyhow::Error> {
+    let modrinth_token = read_token(&[token_env.to_string()])?;
+
+    match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!("{}/project/{}", modrinth_url.labrinth, project_id))
+            .header("Authorization", modrinth_token.expose()))
+    })
+    .await
+    {
+        Ok(res) => match res.json::<ProjectResponse>().await {
+            Ok(json) => Ok(json),
+            Err(err) => Err(anyhow!(
+                "Error parsing response from get project: {}\n\
+                 Make sure your Modrinth token can read projects!",
+                err.to_string()
+            )),
         },
-    } as i32;
+        Err(err) => Err(anyhow!("Error getting project from project id: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::changelog::Changelog;
+    use crate::models::release::tests::test_summary;
+    use crate::models::release::{PlatformResult, ReleaseStatus};
+    use crate::models::GithubConfig;
+    use crate::test_support::config_with;
+    use std::sync::{Arc, Mutex};
+
+    // Registered as a secret once the webhook is executed, so it's masked in every test's errors
+    const WEBHOOK_PATH: &str = "/api/webhooks/1/peony-test-webhook-token";
 
-    let release_time = Utc::now().format("%b, %d %Y %r");
+    const DISCORD_CONFIG: &str = r#"
+github_emoji_id = "<:github:1>"
+modrinth_emoji_id = "<:modrinth:2>"
+discord_ping_role = "123456789"
+title_emoji = "🌸"
+"#;
 
-    let embed = Embed::fake(|mut e| {
-        if let Some(url) = &discord_config.embed_image_url {
-            e = e.image(url)
+    /// Answers webhooks executed against `server` with 200, keeping the payloads they sent.
+    async fn webhook_mock(
+        server: &mut mockito::ServerGuard,
+        hits: usize,
+    ) -> (mockito::Mock, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let payloads = Arc::new(Mutex::new(vec![]));
+        let received = payloads.clone();
+
+        let mock = server
+            .mock("POST", WEBHOOK_PATH)
+            .match_query(mockito::Matcher::UrlEncoded("wait".into(), "true".into()))
+            .with_status(200)
+            .with_body_from_request(move |request| {
+                let payload = serde_json::from_slice(request.body().unwrap()).unwrap();
+                received.lock().unwrap().push(payload);
+                b"{}".to_vec()
+            })
+            .expect(hits)
+            .create_async()
+            .await;
+
+        (mock, payloads)
+    }
+
+    fn field(name_length: usize, value_length: usize) -> EmbedField {
+        EmbedField {
+            name: "n".repeat(name_length),
+            value: "v".repeat(value_length),
+            inline: true,
         }
+    }
 
-        if let Some(url) = &discord_config.thumbnail_image_url {
-            e = e.thumbnail(url)
+    /// Lines of `line_length` characters, including their line break.
+    fn changelog_lines(count: usize, line_length: usize) -> String {
+        vec!["-".repeat(line_length - 1); count].join("\n")
+    }
+
+    #[test]
+    fn changelog_at_description_limit_is_kept() {
+        let changelog = "a".repeat(EMBED_DESCRIPTION_LIMIT);
+
+        assert_eq!(
+            fit_changelog(&changelog, EMBED_DESCRIPTION_LIMIT, None),
+            changelog
+        );
+    }
+
+    #[test]
+    fn changelog_over_description_limit_is_cut_at_a_line() {
+        // 4097 characters, one more than fits
+        let changelog = format!("{}\n{}", changelog_lines(40, 100), "a".repeat(97));
+        assert_eq!(changelog.chars().count(), EMBED_DESCRIPTION_LIMIT + 1);
+
+        let url = "https://github.com/LilydevMC/peony-test/releases/tag/1.0.0";
+        let fitted = fit_changelog(&changelog, EMBED_DESCRIPTION_LIMIT, Some(url));
+
+        assert!(fitted.chars().count() <= EMBED_DESCRIPTION_LIMIT);
+        assert_eq!(
+            fitted,
+            format!(
+                "{}\n\n… [Full changelog]({})",
+                changelog_lines(40, 100),
+                url
+            )
+        );
+    }
+
+    #[test]
+    fn changelog_without_line_breaks_is_cut_at_a_space() {
+        let changelog = "word ".repeat(10);
+
+        assert_eq!(fit_changelog(&changelog, 20, None), "word word word\n\n…");
+    }
+
+    #[test]
+    fn title_at_limit_is_kept() {
+        let title = "🌸".repeat(EMBED_TITLE_LIMIT);
+
+        assert_eq!(truncate_chars(&title, EMBED_TITLE_LIMIT), title);
+        assert_eq!(
+            truncate_chars(&format!("{}!", title), EMBED_TITLE_LIMIT),
+            title
+        );
+    }
+
+    #[test]
+    fn description_limit_is_what_the_total_limit_leaves() {
+        // 1904 characters leave exactly the description limit
+        let title = "t".repeat(EMBED_TITLE_LIMIT);
+        let footer = "f".repeat(1000);
+        let fields = vec![field(48, 600)];
+        assert_eq!(
+            description_limit(&title, &footer, &fields),
+            EMBED_DESCRIPTION_LIMIT
+        );
+
+        let fields = vec![field(48, 600), field(1, 0)];
+        assert_eq!(
+            description_limit(&title, &footer, &fields),
+            EMBED_DESCRIPTION_LIMIT - 1
+        );
+
+        assert_eq!(description_limit("", "", &[]), EMBED_DESCRIPTION_LIMIT);
+        assert_eq!(
+            description_limit(&title, &"f".repeat(EMBED_TOTAL_LIMIT), &[]),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn long_release_fits_embed_limits() {
+        let mut server = mockito::Server::new_async().await;
+        let (webhook, payloads) = webhook_mock(&mut server, 1).await;
+        std::env::set_var(
+            "PEONY_TEST_LONG_RELEASE_WEBHOOK",
+            format!("{}{}", server.url(), WEBHOOK_PATH),
+        );
+
+        let config = config_with::<DiscordConfig>(
+            DISCORD_CONFIG,
+            r#"
+footer_text = "{version}{version}{version}{version}{version}{version}{version}{version}"
+show_fields = true
+
+[[webhooks]]
+env_var = "PEONY_TEST_LONG_RELEASE_WEBHOOK"
+"#,
+        );
+        let github_config: GithubConfig = toml::from_str(
+            r#"
+repo_owner = "LilydevMC"
+repo_name = "peony-test"
+"#,
+        )
+        .unwrap();
+        let mut release = test_summary();
+        release.version_name = "v".repeat(300);
+        release.changelog = Changelog::from_intro(changelog_lines(100, 80));
+
+        let results = send_discord_notification(
+            &config,
+            &release,
+            &ModrinthUrl::new(&None),
+            None,
+            Some(Forge::Github(&github_config)),
+            &NetworkConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        webhook.assert_async().await;
+        assert!(results.iter().all(|result| result.error.is_none()));
+
+        let payloads = payloads.lock().unwrap();
+        let embed = &payloads[0]["embeds"][0];
+        let length = |value: &serde_json::Value| value.as_str().unwrap().chars().count();
+        let title = length(&embed["title"]);
+        let description = length(&embed["description"]);
+        let footer = length(&embed["footer"]["text"]);
+
+        assert_eq!(title, EMBED_TITLE_LIMIT);
+        assert_eq!(footer, EMBED_FOOTER_LIMIT);
+        assert!(description <= EMBED_DESCRIPTION_LIMIT);
+        assert!(
+            title + description + footer <= EMBED_TOTAL_LIMIT,
+            "{}",
+            description
+        );
+        assert!(embed["description"].as_str().unwrap().ends_with(
+            "… [Full changelog](https://github.com/LilydevMC/peony-test/releases/tag/1.0.0)"
+        ));
+    }
+
+    #[test]
+    fn role_id_from_raw_id_or_mention() {
+        assert_eq!(
+            role_id_from_mention("123456789"),
+            Some("123456789".to_string())
+        );
+        assert_eq!(
+            role_id_from_mention("<@&123456789>"),
+            Some("123456789".to_string())
+        );
+        assert_eq!(
+            role_id_from_mention(" <@&123456789>\n"),
+            Some("123456789".to_string())
+        );
+    }
+
+    #[test]
+    fn no_role_id_from_other_mentions() {
+        assert_eq!(role_id_from_mention(""), None);
+        assert_eq!(role_id_from_mention("@everyone"), None);
+        assert_eq!(role_id_from_mention("<@123456789>"), None);
+        assert_eq!(role_id_from_mention("<@&123456789"), None);
+        assert_eq!(role_id_from_mention("<@&>"), None);
+        assert_eq!(role_id_from_mention("<@&12ab>"), None);
+    }
+
+    #[tokio::test]
+    async fn webhooks_only_allow_their_role_to_be_pinged() {
+        let mut server = mockito::Server::new_async().await;
+        let (webhook, payloads) = webhook_mock(&mut server, 2).await;
+        let url = format!("{}{}", server.url(), WEBHOOK_PATH);
+        std::env::set_var("PEONY_TEST_ROLE_WEBHOOK", &url);
+        std::env::set_var("PEONY_TEST_ROLE_OVERRIDE_WEBHOOK", &url);
+
+        let config = config_with::<DiscordConfig>(
+            DISCORD_CONFIG,
+            r#"
+[[webhooks]]
+env_var = "PEONY_TEST_ROLE_WEBHOOK"
+
+[[webhooks]]
+env_var = "PEONY_TEST_ROLE_OVERRIDE_WEBHOOK"
+discord_ping_role = "<@&987654321>"
+"#,
+        );
+        send_discord_notification(
+            &config,
+            &test_summary(),
+            &ModrinthUrl::new(&None),
+            Some(("AABBCCDD", "PEONY_TEST_UNSET_MODRINTH_TOKEN".to_string())),
+            None,
+            &NetworkConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        webhook.assert_async().await;
+        let payloads = payloads.lock().unwrap();
+        assert_eq!(payloads[0]["content"], "<@&123456789>");
+        assert_eq!(
+            payloads[0]["allowed_mentions"],
+            serde_json::json!({ "parse": [], "roles": ["123456789"] })
+        );
+        assert_eq!(payloads[1]["content"], "<@&987654321>");
+        assert_eq!(
+            payloads[1]["allowed_mentions"],
+            serde_json::json!({ "parse": [], "roles": ["987654321"] })
+        );
+    }
+
+    #[tokio::test]
+    async fn everyone_is_only_pinged_when_allowed() {
+        let mut server = mockito::Server::new_async().await;
+        let (webhook, payloads) = webhook_mock(&mut server, 2).await;
+        let url = format!("{}{}", server.url(), WEBHOOK_PATH);
+        std::env::set_var("PEONY_TEST_EVERYONE_WEBHOOK", &url);
+
+        for everyone in [false, true] {
+            let config = config_with::<DiscordConfig>(
+                DISCORD_CONFIG,
+                &format!(
+                    r#"
+everyone = {}
+
+[[webhooks]]
+env_var = "PEONY_TEST_EVERYONE_WEBHOOK"
+discord_ping_role = "@everyone"
+"#,
+                    everyone
+                ),
+            );
+            send_discord_notification(
+                &config,
+                &test_summary(),
+                &ModrinthUrl::new(&None),
+                Some(("AABBCCDD", "PEONY_TEST_UNSET_MODRINTH_TOKEN".to_string())),
+                None,
+                &NetworkConfig::default(),
+                false,
+            )
+            .await
+            .unwrap();
         }
 
-        e.title(format!("{} {}", discord_config.title_emoji, version_name))
-            .color(embed_color)
-            .description(description)
-            .footer(|f| {
-                f.text(format!(
-                    "{} | {} UTC",
-                    modrinth_project.project_type.formatted(),
-                    release_time
-                ))
+        webhook.assert_async().await;
+        let payloads = payloads.lock().unwrap();
+        assert_eq!(payloads[0]["content"], "@everyone");
+        assert_eq!(
+            payloads[0]["allowed_mentions"],
+            serde_json::json!({ "parse": [], "roles": [] })
+        );
+        assert_eq!(
+            payloads[1]["allowed_mentions"],
+            serde_json::json!({ "parse": ["everyone"], "roles": [] })
+        );
+    }
+
+    fn test_embed() -> Embed {
+        Embed {
+            title: "🌸 Test Pack 1.0.0".to_string(),
+            description: "**New release!**".to_string(),
+            color: 0x232634,
+            image: None,
+            thumbnail: None,
+            fields: vec![],
+            footer: EmbedFooter {
+                text: "Modpack | Release".to_string(),
+            },
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    fn test_request(embed: Embed) -> ExecuteWebhookRequest {
+        ExecuteWebhookRequest {
+            content: "<@&123456789>".to_string(),
+            embeds: vec![embed],
+            thread_name: None,
+            allowed_mentions: AllowedMentions {
+                parse: vec![],
+                roles: vec!["123456789".to_string()],
+            },
+            attachments: vec![],
+        }
+    }
+
+    #[test]
+    fn webhook_payload_skips_unset_fields() {
+        let payload = serde_json::to_value(test_request(test_embed())).unwrap();
+
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "content": "<@&123456789>",
+                "embeds": [{
+                    "title": "🌸 Test Pack 1.0.0",
+                    "description": "**New release!**",
+                    "color": 0x232634,
+                    "footer": { "text": "Modpack | Release" },
+                    "timestamp": "2024-01-01T00:00:00+00:00",
+                }],
+                "allowed_mentions": { "parse": [], "roles": ["123456789"] },
             })
-    });
+        );
+    }
 
-    let http = serenity::http::Http::new("token");
-    let url = match env::var("WEBHOOK_URL") {
-        Ok(url) => url,
-        Err(err) => return Err(anyhow!("Failed to get webhook url: {}", err)),
-    };
+    #[test]
+    fn webhook_payload_with_every_field() {
+        let mut request = test_request(Embed {
+            image: Some(EmbedImage {
+                url: "https://example.com/banner.png".to_string(),
+            }),
+            thumbnail: Some(EmbedImage {
+                url: "https://example.com/icon.png".to_string(),
+            }),
+            fields: vec![EmbedField {
+                name: "Version".to_string(),
+                value: "`1.0.0`".to_string(),
+                inline: true,
+            }],
+            ..test_embed()
+        });
+        request.thread_name = Some("Test Pack 1.0.0".to_string());
+        request.attachments = vec![Attachment {
+            id: 0,
+            filename: "CHANGELOG-1.0.0.md".to_string(),
+        }];
 
-    let webhook = Webhook::from_url(&http, &url).await?;
+        let payload = serde_json::to_value(request).unwrap();
 
-    match webhook
-        .execute(&http, true, |w| {
-            w.content(&discord_config.discord_ping_role)
-                .embeds(vec![embed])
-        })
+        assert_eq!(payload["thread_name"], "Test Pack 1.0.0");
+        assert_eq!(
+            payload["attachments"],
+            serde_json::json!([{ "id": 0, "filename": "CHANGELOG-1.0.0.md" }])
+        );
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["image"]["url"], "https://example.com/banner.png");
+        assert_eq!(embed["thumbnail"]["url"], "https://example.com/icon.png");
+        assert_eq!(
+            embed["fields"],
+            serde_json::json!([{ "name": "Version", "value": "`1.0.0`", "inline": true }])
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limited_webhook_is_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let rate_limited = server
+            .mock("POST", WEBHOOK_PATH)
+            .match_query(mockito::Matcher::Any)
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .with_body(r#"{"message": "You are being rate limited.", "retry_after": 1.0, "global": false}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let (webhook, payloads) = webhook_mock(&mut server, 1).await;
+        std::env::set_var(
+            "PEONY_TEST_RATE_LIMITED_WEBHOOK",
+            format!("{}{}", server.url(), WEBHOOK_PATH),
+        );
+
+        let start = std::time::Instant::now();
+        execute_webhook(
+            "PEONY_TEST_RATE_LIMITED_WEBHOOK",
+            &test_request(test_embed()),
+            None,
+            None,
+            &NetworkConfig::default(),
+        )
         .await
-    {
-        Ok(_) => Ok(()),
-        Err(err) => Err(anyhow!("Failed to send Discord webhook: {}", err)),
+        .unwrap();
+
+        assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+        rate_limited.assert_async().await;
+        webhook.assert_async().await;
+        assert_eq!(payloads.lock().unwrap()[0]["content"], "<@&123456789>");
+    }
+
+    #[tokio::test]
+    async fn webhook_errors_name_the_env_var_but_not_the_url() {
+        let mut server = mockito::Server::new_async().await;
+        let webhook = server
+            .mock("POST", WEBHOOK_PATH)
+            .match_query(mockito::Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"message": "Invalid Form Body", "code": 50035}"#)
+            .create_async()
+            .await;
+        std::env::set_var(
+            "PEONY_TEST_INVALID_WEBHOOK",
+            format!("{}{}", server.url(), WEBHOOK_PATH),
+        );
+
+        let err = execute_webhook(
+            "PEONY_TEST_INVALID_WEBHOOK",
+            &test_request(test_embed()),
+            None,
+            None,
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+
+        webhook.assert_async().await;
+        assert!(err.starts_with("Failed to send Discord webhook `PEONY_TEST_INVALID_WEBHOOK`"));
+        assert!(err.contains("Invalid Form Body"), "{}", err);
+        assert!(!err.contains("token"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn changelog_is_attached_as_multipart() {
+        let mut server = mockito::Server::new_async().await;
+        let webhook = server
+            .mock("POST", WEBHOOK_PATH)
+            .match_query(mockito::Matcher::Any)
+            .match_header(
+                "content-type",
+                mockito::Matcher::Regex("^multipart/form-data; boundary=".to_string()),
+            )
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(r#"name="payload_json""#.to_string()),
+                mockito::Matcher::Regex(
+                    r#"name="files\[0\]"; filename="CHANGELOG-1.0.0.md""#.to_string(),
+                ),
+                mockito::Matcher::Regex("# Changes".to_string()),
+            ]))
+            .with_status(200)
+            .with_body("{}")
+            .create_async()
+            .await;
+        std::env::set_var(
+            "PEONY_TEST_ATTACHMENT_WEBHOOK",
+            format!("{}{}", server.url(), WEBHOOK_PATH),
+        );
+
+        execute_webhook(
+            "PEONY_TEST_ATTACHMENT_WEBHOOK",
+            &test_request(test_embed()),
+            Some(&WebhookFile {
+                file_name: "CHANGELOG-1.0.0.md".to_string(),
+                content: "# Changes".to_string(),
+            }),
+            None,
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        webhook.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn webhook_read_in_preflight_is_masked_as_its_id() {
+        use crate::models::project_type::modpack::config::ModpackConfig;
+        use crate::release::ReleaseOptions;
+        use crate::validate::preflight_modpack;
+
+        const PREFLIGHT_WEBHOOK_PATH: &str = "/api/webhooks/2/peony-preflight-webhook-token";
+
+        let mut server = mockito::Server::new_async().await;
+        let webhook = server
+            .mock("POST", PREFLIGHT_WEBHOOK_PATH)
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("{}")
+            .create_async()
+            .await;
+        let url = format!("{}{}", server.url(), PREFLIGHT_WEBHOOK_PATH);
+        std::env::set_var("PEONY_TEST_PREFLIGHT_WEBHOOK", &url);
+
+        // Pinned, so that the preflight doesn't look for packwiz on the system
+        let config: ModpackConfig = toml::from_str(
+            r#"
+config_format_version = 1
+version_name_format = "{pack_name} {version}"
+
+[modrinth]
+project_id = "AABBCCDD"
+
+[packwiz]
+version = "0.1.0"
+
+[discord]
+github_emoji_id = "<:github:1>"
+modrinth_emoji_id = "<:modrinth:2>"
+discord_ping_role = "123456789"
+title_emoji = "🌸"
+
+[[discord.webhooks]]
+env_var = "PEONY_TEST_PREFLIGHT_WEBHOOK"
+"#,
+        )
+        .unwrap();
+        let opts = ReleaseOptions {
+            discord: true,
+            dry_run: true,
+            ..Default::default()
+        };
+        preflight_modpack(&config, &opts).unwrap();
+
+        execute_webhook(
+            "PEONY_TEST_PREFLIGHT_WEBHOOK",
+            &test_request(test_embed()),
+            None,
+            None,
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        webhook.assert_async().await;
+        assert_eq!(
+            crate::redact::redact(&format!("Sent to {}", url)),
+            "Sent to <webhook 2>"
+        );
+    }
+
+    const GITHUB_RELEASE_URL: &str = "https://github.com/LilydevMC/peony-test/releases/tag/1.0.0";
+
+    /// Released to GitHub, while CurseForge failed and Modrinth was skipped.
+    fn github_only_summary() -> ReleaseSummary {
+        let mut release = test_summary();
+        release.results = vec![
+            PlatformResult {
+                platform: Platform::Github,
+                status: ReleaseStatus::Succeeded {
+                    url: GITHUB_RELEASE_URL.to_string(),
+                },
+            },
+            PlatformResult {
+                platform: Platform::Curseforge,
+                status: ReleaseStatus::Failed("Invalid game version".to_string()),
+            },
+            PlatformResult {
+                platform: Platform::Modrinth,
+                status: ReleaseStatus::Skipped,
+            },
+        ];
+        release
+    }
+
+    fn render(config: &DiscordConfig, release: &ReleaseSummary, changelog: &str) -> String {
+        let values = description_values(config, release, &ModrinthUrl::new(&None), &None);
+
+        render_description(&config.description_template(), &values, changelog).unwrap()
+    }
+
+    #[test]
+    fn default_description_collapses_missing_platforms() {
+        let description = render(
+            &config_with(DISCORD_CONFIG, ""),
+            &github_only_summary(),
+            "- Fixed things",
+        );
+
+        assert_eq!(
+            description,
+            format!(
+                "**New release!**\n\n\
+                 Version: `1.0.0`\n\n\
+                 <:github:1> [GitHub]({})\n\n\
+                 - Fixed things",
+                GITHUB_RELEASE_URL
+            )
+        );
+    }
+
+    #[test]
+    fn empty_changelog_leaves_no_trailing_lines() {
+        let description = render(&config_with(DISCORD_CONFIG, ""), &github_only_summary(), "");
+
+        assert!(
+            description.ends_with(&format!("[GitHub]({})", GITHUB_RELEASE_URL)),
+            "{}",
+            description
+        );
+    }
+
+    #[test]
+    fn custom_description_template() {
+        let config = config_with::<DiscordConfig>(
+            DISCORD_CONFIG,
+            r#"
+description_template = """
+{version} for {mc_version} is out!
+{modrinth_emoji} {modrinth_url}
+{github_emoji} {github_url}
+
+
+{pack_name}
+Changes: {changelog}
+Braces: {{changelog}}
+"""
+"#,
+        );
+        let mut release = github_only_summary();
+        release.game_versions = vec!["1.20.1".to_string(), "1.20.2".to_string()];
+
+        let description = render(&config, &release, "- Fixed things");
+
+        assert_eq!(
+            description,
+            format!(
+                "Test Pack 1.0.0 for 1.20.1, 1.20.2 is out!\n\
+                 <:github:1> {}\n\n\
+                 Changes: - Fixed things\n\
+                 Braces: {{changelog}}",
+                GITHUB_RELEASE_URL
+            )
+        );
+    }
+
+    #[test]
+    fn lines_with_text_and_empty_placeholders_are_kept() {
+        let description = render_description(
+            "Pack: {pack_name}\n{pack_name}{maven}\nDone",
+            &[("pack_name", String::new()), ("maven", " ".to_string())],
+            "",
+        )
+        .unwrap();
+
+        assert_eq!(description, "Pack: \nDone");
+    }
+
+    #[test]
+    fn unknown_description_placeholder_fails() {
+        let err = render_description(
+            "{version} {changelg}",
+            &[("version", "1.0.0".to_string())],
+            "",
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(
+            err.starts_with(
+                "Unknown placeholder `{changelg}` in discord.description_template, \
+                 valid placeholders are: {version}, {changelog}"
+            ),
+            "{}",
+            err
+        );
     }
 }
@@ -0,0 +1,216 @@
+use crate::discord::fit_changelog;
+use crate::matrix::escape_html;
+use crate::models::{
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    EmailConfig, NetworkConfig, SmtpSecurity,
+};
+use crate::util::read_secret_env;
+use crate::version::render_template;
+use anyhow::anyhow;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use pulldown_cmark::{html, Parser};
+use tracing::{info, warn};
+
+/// Mail clients cope with much more, but nobody reads a changelog this long in an email.
+const CHANGELOG_LIMIT: usize = 50_000;
+
+/// Emails the release to the addresses of the config. Failures are only logged and returned in
+/// the result, as the release already happened.
+pub async fn send_release_email(
+    email_config: &EmailConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> WebhookResult {
+    let error = match send_email(email_config, release, network, dry_run).await {
+        Ok(_) => None,
+        Err(err) => {
+            warn!("{}", err);
+            Some(err.to_string())
+        }
+    };
+
+    WebhookResult {
+        notifier: Notifier::Email,
+        target: email_config.host.clone(),
+        error,
+    }
+}
+
+async fn send_email(
+    email_config: &EmailConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<(), anyhow::Error> {
+    let message = release_message(email_config, release)?;
+
+    if dry_run {
+        info!(
+            "[dry run] Would email {} via `{}`:\n{}",
+            email_config.to.join(", "),
+            email_config.host,
+            String::from_utf8_lossy(&message.formatted())
+        );
+        return Ok(());
+    }
+
+    let transport = smtp_transport(email_config, network)?;
+
+    match transport.send(message).await {
+        Ok(_) => {
+            info!("Sent release email to {}", email_config.to.join(", "));
+            Ok(())
+        }
+        Err(err) => Err(anyhow!(
+            "Failed to send email via `{}`: {}",
+            email_config.host,
+            err
+        )),
+    }
+}
+
+/// A `multipart/alternative` message, with the changelog as HTML and as the Markdown it's
+/// written in for mail clients that only show plain text.
+pub fn release_message(
+    email_config: &EmailConfig,
+    release: &ReleaseSummary,
+) -> Result<Message, anyhow::Error> {
+    let subject = render_template(
+        "email.subject",
+        &email_config.subject(),
+        &[
+            ("version", release.version_number.as_str()),
+            ("version_name", release.version_name.as_str()),
+            ("version_type", release.version_type.formatted().as_str()),
+        ],
+    )?;
+
+    let changelog = fit_changelog(&release.changelog.render_github(), CHANGELOG_LIMIT, None);
+    let links = [
+        Platform::Github,
+        Platform::Gitlab,
+        Platform::Gitea,
+        Platform::Modrinth,
+        Platform::Curseforge,
+    ]
+    .into_iter()
+    .filter_map(|platform| release.url(platform).map(|url| (platform.formatted(), url)))
+    .collect::<Vec<(String, &String)>>();
+
+    let mut plain = format!(
+        "{} ({})\n\n{}\n",
+        release.version_name, release.version_number, changelog
+    );
+    if !links.is_empty() {
+        plain.push('\n');
+        for (name, url) in &links {
+            plain.push_str(&format!("{}: {}\n", name, url));
+        }
+    }
+
+    let mut body = format!(
+        "<h2>{}</h2>\n<p>Version <code>{}</code></p>\n",
+        escape_html(&release.version_name),
+        escape_html(&release.version_number)
+    );
+    html::push_html(&mut body, Parser::new(&changelog));
+    if !links.is_empty() {
+        body.push_str("<ul>\n");
+        for (name, url) in &links {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape_html(url),
+                name
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"></head>\n<body>\n{}</body>\n</html>\n",
+        body
+    );
+
+    let mut builder = Message::builder()
+        .from(parse_mailbox(&email_config.from)?)
+        .subject(subject);
+    for address in &email_config.to {
+        builder = builder.to(parse_mailbox(address)?);
+    }
+
+    match builder.multipart(MultiPart::alternative_plain_html(plain, html)) {
+        Ok(message) => Ok(message),
+        Err(err) => Err(anyhow!("Failed to build release email: {}", err)),
+    }
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, anyhow::Error> {
+    match address.parse() {
+        Ok(mailbox) => Ok(mailbox),
+        Err(err) => Err(anyhow!("Invalid email address `{}`: {}", address, err)),
+    }
+}
+
+/// Logs in with the password of `password_env` when there's a username.
+pub fn smtp_transport(
+    email_config: &EmailConfig,
+    network: &NetworkConfig,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, anyhow::Error> {
+    let builder = match email_config.security() {
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email_config.host)
+        }
+        SmtpSecurity::Ssl => AsyncSmtpTransport::<Tokio1Executor>::relay(&email_config.host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            &email_config.host,
+        )),
+    };
+
+    let mut builder = match builder {
+        Ok(builder) => builder
+            .port(email_config.port())
+            .timeout(Some(network.timeout())),
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to set up SMTP connection to `{}`: {}",
+                email_config.host,
+                err
+            ))
+        }
+    };
+
+    if let Some(username) = &email_config.username {
+        let password = read_secret_env(&email_config.password_env())?;
+        builder = builder.credentials(Credentials::new(
+            username.clone(),
+            password.expose().to_string(),
+        ));
+    }
+
+    Ok(builder.build())
+}
+
+/// Connects and logs in without sending anything, for `peony validate`.
+pub async fn check_smtp_connection(
+    email_config: &EmailConfig,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    let transport = smtp_transport(email_config, network)?;
+
+    match transport.test_connection().await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(anyhow!(
+            "SMTP server `{}:{}` didn't respond",
+            email_config.host,
+            email_config.port()
+        )),
+        Err(err) => Err(anyhow!(
+            "Failed to connect to SMTP server `{}:{}`: {}",
+            email_config.host,
+            email_config.port(),
+            err
+        )),
+    }
+}
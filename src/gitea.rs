@@ -0,0 +1,179 @@
+use anyhow::anyhow;
+use reqwest::multipart::Form;
+use std::path::Path;
+use tracing::info;
+
+use crate::models::{
+    gitea::{CreateGiteaReleaseRequest, GiteaAttachment, GiteaReleaseResponse},
+    modrinth::version::VersionType,
+    project_type::modpack::{config::ModpackConfig, PackFile},
+    util::OutputFileInfo,
+    version::VersionInfo,
+    GiteaConfig, NetworkConfig,
+};
+use crate::progress::UploadBar;
+use crate::redact::Secret;
+use crate::util::{
+    error_body, file_part, file_size, mime_type_from_file_name, read_secret_env, send_with_retry,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_gitea_release(
+    config: &ModpackConfig,
+    gitea_config: &GiteaConfig,
+    pack_file: &PackFile,
+    output_file_info: &OutputFileInfo,
+    version_info: &VersionInfo,
+    changelog: &str,
+    checksums_file_info: &OutputFileInfo,
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let release_req = CreateGiteaReleaseRequest {
+        tag_name: gitea_config.tag_name(&pack_file.version),
+        target_commitish: gitea_config.target_commitish.clone(),
+        name: version_info.version_name.clone(),
+        body: changelog.to_string(),
+        prerelease: !matches!(version_type, VersionType::Release),
+    };
+
+    if dry_run {
+        info!(
+            "[dry run] Would create Gitea release in `{}`:\n  \
+             Tag name: {}\n  \
+             Target: {}\n  \
+             Release name: {}\n  \
+             Prerelease: {}\n  \
+             Assets: {}, {}\n  \
+             Body:\n{}",
+            gitea_config.web_url(),
+            release_req.tag_name,
+            release_req
+                .target_commitish
+                .clone()
+                .unwrap_or_else(|| "default branch".to_string()),
+            release_req.name,
+            release_req.prerelease,
+            output_file_info.file_name,
+            checksums_file_info.file_name,
+            release_req.body
+        );
+        return Ok(format!(
+            "{}/releases/tag/{}",
+            gitea_config.web_url(),
+            release_req.tag_name
+        ));
+    }
+
+    info!("Creating Gitea release...");
+
+    let token_env = gitea_config.token_env();
+    let gitea_token = read_secret_env(&token_env)?;
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let res = match send_with_retry(&network, |client| {
+        Ok(client
+            .post(format!("{}/releases", gitea_config.api_url()))
+            .header("Authorization", format!("token {}", gitea_token.expose()))
+            .json(&release_req))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to create Gitea release: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to create Gitea release: {}",
+            error_body(res).await
+        ));
+    }
+
+    let release = match res.json::<GiteaReleaseResponse>().await {
+        Ok(json) => json,
+        Err(err) => return Err(anyhow!("Failed to parse Gitea release: {}", err)),
+    };
+
+    info!("Successfully created Gitea release!");
+
+    for file_info in [output_file_info, checksums_file_info] {
+        upload_gitea_attachment(
+            gitea_config,
+            release.id,
+            &file_info.file_name,
+            &file_info.file_path,
+            &gitea_token,
+            &network,
+        )
+        .await?;
+    }
+
+    Ok(release.html_url)
+}
+
+pub async fn upload_gitea_attachment(
+    gitea_config: &GiteaConfig,
+    release_id: i64,
+    file_name: &str,
+    file_path: &Path,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<GiteaAttachment, anyhow::Error> {
+    info!("Uploading Gitea release attachment `{}`...", file_name);
+
+    let bar = UploadBar::new(file_name, file_size(file_path)?);
+    let progress = bar.callback();
+
+    let res = match send_with_retry(network, |client| {
+        let form = Form::new().part(
+            "attachment",
+            file_part(
+                file_path,
+                file_name,
+                mime_type_from_file_name(file_name),
+                Some(&progress),
+            )?,
+        );
+
+        Ok(client
+            .post(format!(
+                "{}/releases/{}/assets",
+                gitea_config.api_url(),
+                release_id
+            ))
+            .query(&[("name", file_name)])
+            .header("Authorization", format!("token {}", token.expose()))
+            .multipart(form)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to upload Gitea release attachment `{}`: {}",
+                file_name,
+                err
+            ))
+        }
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to upload Gitea release attachment `{}`: {}",
+            file_name,
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<GiteaAttachment>().await {
+        Ok(json) => Ok(json),
+        Err(err) => Err(anyhow!(
+            "Failed to parse Gitea release attachment `{}`: {}",
+            file_name,
+            err
+        )),
+    }
+}
@@ -1,23 +1,37 @@
 use anyhow::anyhow;
+use reqwest::header::CONTENT_LENGTH;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::{env, fs};
+use std::sync::Mutex;
+use tracing::{info, warn};
 
+use crate::mod_list::append_to_release_body;
+use crate::models::modrinth::version::VersionType;
 use crate::models::project_type::mc_mod::config::ModConfig;
 use crate::models::project_type::mc_mod::{ModInfo, ModJars};
 use crate::models::{
+    changelog::Changelog,
+    forge::Forge,
     github::*,
-    project_type::modpack::{config::ModpackConfig, PackFile},
-    util::OutputFileInfo,
+    project_type::modpack::{config::ModpackConfig, index::ModList, PackFile},
+    util::{FileChecksums, OutputFileInfo},
     version::VersionInfo,
-    GithubConfig,
+    GithubConfig, NetworkConfig, OnExisting,
 };
-use crate::models::modrinth::version::VersionType;
+use crate::progress::UploadBar;
+use crate::redact::Secret;
+use crate::util::{encode_path_segment, error_body, file_body, file_size, send_with_retry};
 
-pub async fn generate_changelog(config: &GithubConfig) -> Result<String, anyhow::Error> {
-    println!("Generating changelog...");
+pub async fn generate_changelog(
+    config: &GithubConfig,
+    project_dir: &Path,
+    network: &NetworkConfig,
+) -> Result<Changelog, anyhow::Error> {
+    info!("Generating changelog...");
 
     let first_commit = match Command::new("git")
         .args(["rev-list", "--max-parents=0", "HEAD"])
+        .current_dir(project_dir)
         .output()
     {
         Ok(output) => match String::from_utf8(output.stdout) {
@@ -27,19 +41,12 @@ pub async fn generate_changelog(config: &GithubConfig) -> Result<String, anyhow:
         Err(err) => return Err(anyhow!("Failed to get first commit: {}", err)),
     };
 
-    let latest_release = match reqwest::Client::new()
-        .get(format!(
-            "https://api.github.com/repos/{}/{}/releases/latest",
-            config.repo_owner, config.repo_name
-        ))
-        .header("User-Agent", env!("CARGO_PKG_NAME"))
-        .send()
-        .await
+    let latest_release = match send_with_retry(network, |client| {
+        Ok(client.get(format!("{}/releases/latest", config.api_url())))
+    })
+    .await
     {
-        Ok(res) => match res.json::<ReleaseResponse>().await {
-            Ok(json) => Some(json),
-            Err(_) => None,
-        },
+        Ok(res) => res.json::<ReleaseResponse>().await.ok(),
         Err(_) => None,
     };
 
@@ -48,239 +55,1255 @@ pub async fn generate_changelog(config: &GithubConfig) -> Result<String, anyhow:
         None => first_commit,
     };
 
-    let full_changelog = format!(
-        "https://github.com/{}/{}/compare/{}..HEAD",
-        config.repo_owner, config.repo_name, compare_first
-    );
+    let full_changelog = format!("{}/compare/{}..HEAD", config.web_url(), compare_first);
 
-    println!("Successfully generated changelog!");
+    info!("Successfully generated changelog!");
 
-    Ok(format!("[Full Changelog]({})", full_changelog))
+    Ok(Changelog {
+        full_changelog_url: Some(full_changelog),
+        ..Default::default()
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_modpack_release(
     config: &ModpackConfig,
+    github_config: &GithubConfig,
     pack_file: &PackFile,
     output_file_info: &OutputFileInfo,
+    server_pack_file_info: Option<&OutputFileInfo>,
     version_info: &VersionInfo,
-    changelog: &str,
-    version_type: VersionType
-) -> Result<(), anyhow::Error> {
-    println!("Creating GitHub release...");
-
-    let github_token = match env::var("GITHUB_TOKEN") {
-        Ok(token) => token,
-        Err(err) => return Err(anyhow!("Failed to get `GITHUB_TOKEN`: {}", err)),
-    };
+    changelog: &Changelog,
+    checksums: &FileChecksums,
+    checksums_file_info: &OutputFileInfo,
+    mod_list: Option<&ModList>,
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let mut body = format!(
+        "{}\n\n**SHA-512** (`{}`): `{}`",
+        changelog.render_github(),
+        checksums.file_name,
+        checksums.sha512
+    );
+    if let Some(mod_list) = mod_list.filter(|_| github_config.mod_list_in_release_body()) {
+        append_to_release_body(&mut body, &mod_list.entries);
+    }
 
     let new_release_req_body = CreateReleaseRequest {
-        tag_name: pack_file.version.clone(),
+        tag_name: github_config.tag_name(&pack_file.version),
+        target_commitish: github_config.target_commitish.clone(),
         name: Some(version_info.version_name.clone()),
-        body: Some(changelog.to_owned()),
-        prerelease: match version_type {
-            VersionType::Release => false,
-            _ => true
-        }
+        body: Some(body),
+        prerelease: github_config.is_prerelease(&pack_file.version, version_type),
+        draft: true,
     };
 
-    let new_release_response =
-        create_github_release(&config.github, &new_release_req_body, &github_token).await;
-
-    match new_release_response {
-        Ok(release_res) => {
-            println!("Successfully created GitHub release!");
-            println!("Uploading release asset to GitHub release...");
-
-            match reqwest::Client::new()
-                .post(format!(
-                    "https://uploads.github.com/repos/{}/{}/releases/{}/assets?name=\"{}\"",
-                    config.github.repo_owner,
-                    config.github.repo_name,
-                    release_res.id,
-                    &output_file_info.file_name
-                ))
-                .header("User-Agent", env!("CARGO_PKG_NAME"))
-                .header("Accept", "application/vnd.github+json")
-                .header("Content-Type", "application/zip")
-                .bearer_auth(github_token)
-                .body(version_info.file_contents.clone())
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    println!("Successfully uploaded release asset!");
-                    Ok(())
-                }
-                Err(err) => Err(anyhow::Error::from(err)),
-            }
-        }
-        Err(err) => Err(err),
+    let mod_list_files = mod_list
+        .filter(|_| github_config.mod_list())
+        .map(|mod_list| mod_list.files.as_slice())
+        .unwrap_or_default();
+
+    let mut asset_files = vec![output_file_info];
+    asset_files.extend(server_pack_file_info);
+    asset_files.push(checksums_file_info);
+    asset_files.extend(mod_list_files);
+
+    if dry_run {
+        info!(
+            "{}",
+            describe_github_release(github_config, &new_release_req_body, &asset_files)
+        );
+        return Ok(release_tag_url(
+            github_config,
+            &new_release_req_body.tag_name,
+        ));
     }
+
+    info!("Creating GitHub release...");
+
+    let github_token = github_config.token()?;
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let mut assets = vec![ReleaseAsset {
+        file_name: output_file_info.file_name.clone(),
+        content_type: "application/zip",
+        file_path: output_file_info.file_path.clone(),
+    }];
+
+    if let Some(server_pack_file_info) = server_pack_file_info {
+        assets.push(ReleaseAsset {
+            file_name: server_pack_file_info.file_name.clone(),
+            content_type: "application/zip",
+            file_path: server_pack_file_info.file_path.clone(),
+        });
+    }
+
+    assets.push(ReleaseAsset {
+        file_name: checksums_file_info.file_name.clone(),
+        content_type: "text/plain",
+        file_path: checksums_file_info.file_path.clone(),
+    });
+
+    for file_info in mod_list_files {
+        assets.push(ReleaseAsset {
+            file_name: file_info.file_name.clone(),
+            content_type: if file_info.file_name.ends_with(".csv") {
+                "text/csv"
+            } else {
+                "text/markdown"
+            },
+            file_path: file_info.file_path.clone(),
+        });
+    }
+
+    release_to_github(
+        github_config,
+        &new_release_req_body,
+        &assets,
+        &github_token,
+        &network,
+    )
+    .await
+}
+
+pub fn release_tag_url(config: &GithubConfig, tag_name: &str) -> String {
+    Forge::Github(config).release_url(tag_name)
+}
+
+pub fn release_asset_url(config: &GithubConfig, tag_name: &str, file_name: &str) -> String {
+    format!(
+        "{}/releases/download/{}/{}",
+        config.web_url(),
+        tag_name,
+        file_name.replace(' ', "%20")
+    )
+}
+
+pub fn describe_github_release(
+    config: &GithubConfig,
+    new_release_body: &CreateReleaseRequest,
+    assets: &[&OutputFileInfo],
+) -> String {
+    format!(
+        "[dry run] Would create GitHub release in `{}/{}`:\n  \
+         Tag name: {}\n  \
+         Target: {}\n  \
+         Release name: {}\n  \
+         Prerelease: {}\n  \
+         Draft: {}\n  \
+         Assets: {}\n  \
+         Body:\n{}",
+        config.repo_owner,
+        config.repo_name,
+        new_release_body.tag_name,
+        new_release_body
+            .target_commitish
+            .clone()
+            .unwrap_or_else(|| "default branch".to_string()),
+        new_release_body.name.clone().unwrap_or_default(),
+        new_release_body.prerelease,
+        config.is_draft(),
+        assets
+            .iter()
+            .map(|asset| asset.file_name.clone())
+            .collect::<Vec<String>>()
+            .join(", "),
+        new_release_body.body.clone().unwrap_or_default()
+    )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_mod_release(
     config: &ModConfig,
+    github_config: &GithubConfig,
     mod_info: &ModInfo,
-    mod_jars: &ModJars,
-    changelog: &str,
+    mod_jars: &[ModJars],
+    changelog: &Changelog,
     version_name: &String,
-    version_type: VersionType
-) -> Result<(), anyhow::Error> {
-    println!("Creating GitHub release...");
-
-    let github_token = match env::var("GITHUB_TOKEN") {
-        Ok(token) => token,
-        Err(err) => return Err(anyhow!("Failed to get `GITHUB_TOKEN`: {}", err)),
-    };
-
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
     let new_release_req_body = CreateReleaseRequest {
-        tag_name: mod_info.version.clone(),
+        tag_name: github_config.tag_name(&mod_info.version),
+        target_commitish: github_config.target_commitish.clone(),
         name: Some(version_name.into()),
-        body: Some(changelog.to_owned()),
-        prerelease: match version_type {
-            VersionType::Release => false,
-            _ => true
-        }
+        body: Some(changelog.render_github()),
+        prerelease: github_config.is_prerelease(&mod_info.version, version_type),
+        draft: true,
     };
 
-    let new_release_response =
-        create_github_release(&config.github, &new_release_req_body, &github_token).await;
+    // Every artifact's jars go in the same release
+    let mut assets = vec![];
 
-    upload_mod_jars(
-        mod_jars,
-        &config.github,
-        new_release_response.unwrap().id,
-        github_token,
+    for jars in mod_jars {
+        for jar in [
+            Some(&jars.mod_jar),
+            jars.sources_jar.as_ref(),
+            jars.javadoc_jar.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            assets.push(ReleaseAsset {
+                file_name: jar.file_name.clone(),
+                content_type: "application/java-archive",
+                file_path: jar.file_path.clone(),
+            });
+        }
+    }
+
+    if dry_run {
+        let asset_files = assets
+            .iter()
+            .map(|asset| OutputFileInfo {
+                file_name: asset.file_name.clone(),
+                file_path: asset.file_path.clone(),
+            })
+            .collect::<Vec<OutputFileInfo>>();
+        info!(
+            "{}",
+            describe_github_release(
+                github_config,
+                &new_release_req_body,
+                &asset_files.iter().collect::<Vec<&OutputFileInfo>>()
+            )
+        );
+        return Ok(release_tag_url(
+            github_config,
+            &new_release_req_body.tag_name,
+        ));
+    }
+
+    info!("Creating GitHub release...");
+
+    let github_token = github_config.token()?;
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    release_to_github(
+        github_config,
+        &new_release_req_body,
+        &assets,
+        &github_token,
+        &network,
     )
     .await
 }
 
-pub async fn upload_mod_jars(
-    mod_jars: &ModJars,
-    github_config: &GithubConfig,
-    release_id: i32,
-    token: String,
-) -> Result<(), anyhow::Error> {
-    let mod_jar_contents = match fs::read(&mod_jars.mod_jar.file_path) {
-        Ok(file_contents) => file_contents,
-        Err(err) => return Err(anyhow!("Failed to read mod jar: {}", err)),
+/// Creates the release, unless one with the same tag exists and `on_existing` says to
+/// update or skip it instead.
+pub async fn release_to_github(
+    config: &GithubConfig,
+    release_req: &CreateReleaseRequest,
+    assets: &[ReleaseAsset],
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    if config.on_existing() != OnExisting::Fail {
+        if let Some(existing) =
+            get_release_by_tag(config, &release_req.tag_name, token, network).await?
+        {
+            if config.on_existing() == OnExisting::Skip {
+                info!(
+                    "GitHub release `{}` already exists, skipping",
+                    release_req.tag_name
+                );
+                return Ok(existing.html_url);
+            }
+
+            return update_github_release(config, existing, release_req, assets, token, network)
+                .await;
+        }
+    }
+
+    let release_res = create_github_release(config, release_req, token, network).await?;
+
+    info!("Successfully created GitHub release!");
+
+    let release_id = release_res.id;
+    remember_created_release(config, &release_res, network);
+
+    let res = finish_github_release(config, release_res, assets, token, network).await;
+
+    // Incomplete releases were already deleted
+    if res.is_err() {
+        forget_created_release(release_id);
+    }
+
+    res
+}
+
+/// A release created by this run, which an aborted run can delete again.
+#[derive(Debug, Clone)]
+pub struct CreatedRelease {
+    pub config: GithubConfig,
+    pub release: ReleaseResponse,
+    pub network: NetworkConfig,
+}
+
+static CREATED_RELEASES: Mutex<Vec<CreatedRelease>> = Mutex::new(Vec::new());
+
+fn remember_created_release(
+    config: &GithubConfig,
+    release: &ReleaseResponse,
+    network: &NetworkConfig,
+) {
+    if let Ok(mut created) = CREATED_RELEASES.lock() {
+        created.push(CreatedRelease {
+            config: config.clone(),
+            release: release.clone(),
+            network: network.clone(),
+        });
+    }
+}
+
+fn forget_created_release(release_id: i32) {
+    if let Ok(mut created) = CREATED_RELEASES.lock() {
+        created.retain(|created| created.release.id != release_id);
+    }
+}
+
+/// Releases created so far by this run. Updated releases aren't included, they existed before.
+pub fn created_releases() -> Vec<CreatedRelease> {
+    match CREATED_RELEASES.lock() {
+        Ok(created) => created.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Deletes a release created by this run, along with its tag.
+pub async fn delete_created_release(created: &CreatedRelease) -> Result<(), anyhow::Error> {
+    let token = created.config.token()?;
+
+    info!("Deleting GitHub release `{}`...", created.release.tag_name);
+    delete_github_release(&created.config, &created.release, &token, &created.network).await;
+    forget_created_release(created.release.id);
+
+    Ok(())
+}
+
+pub async fn get_release_by_tag(
+    config: &GithubConfig,
+    tag_name: &str,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<Option<ReleaseResponse>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!(
+                "{}/releases/tags/{}",
+                config.api_url(),
+                encode_path_segment(tag_name)
+            ))
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to get GitHub release `{}`: {}",
+                tag_name,
+                err
+            ))
+        }
     };
 
-    let sources_jar_contents = match &mod_jars.sources_jar {
-        Some(jar) => match fs::read(&jar.file_path) {
-            Ok(file_contents) => Some(file_contents),
-            Err(err) => {
-                return Err(anyhow!(
-                    "Failed to read sources jar, even though it exists: {}",
-                    err
-                ))
-            }
+    match res.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => match res.json::<ReleaseResponse>().await {
+            Ok(json) => Ok(Some(json)),
+            Err(err) => Err(anyhow!(
+                "Failed to parse GitHub release `{}`: {}",
+                tag_name,
+                err
+            )),
         },
-        None => None,
+        status => Err(anyhow!(
+            "Failed to get GitHub release `{}`: `{}`",
+            tag_name,
+            status
+        )),
+    }
+}
+
+/// Returns `None` when the repository has no published release yet.
+/// Public repositories can be read without a token, at a lower rate limit.
+pub async fn get_latest_release(
+    config: &GithubConfig,
+    token: Option<&Secret>,
+    network: &NetworkConfig,
+) -> Result<Option<ReleaseResponse>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        let req = client
+            .get(format!("{}/releases/latest", config.api_url()))
+            .header("Accept", "application/vnd.github+json");
+
+        Ok(match token {
+            Some(token) => req.bearer_auth(token.expose()),
+            None => req,
+        })
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get latest GitHub release: {}", err)),
+    };
+
+    match res.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => match res.json::<ReleaseResponse>().await {
+            Ok(json) => Ok(Some(json)),
+            Err(err) => Err(anyhow!("Failed to parse latest GitHub release: {}", err)),
+        },
+        _ => Err(anyhow!(
+            "Failed to get latest GitHub release: {}",
+            error_body(res).await
+        )),
+    }
+}
+
+/// The newest published release other than the one tagged `tag_name`, so that re-running a
+/// release doesn't compare it with itself.
+pub async fn get_previous_release(
+    config: &GithubConfig,
+    tag_name: &str,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<Option<ReleaseResponse>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!("{}/releases", config.api_url()))
+            .query(&[("per_page", "30")])
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to list GitHub releases: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to list GitHub releases: {}",
+            error_body(res).await
+        ));
+    }
+
+    // Listed newest first
+    match res.json::<Vec<ReleaseResponse>>().await {
+        Ok(releases) => Ok(releases
+            .into_iter()
+            .find(|release| !release.draft && release.tag_name != tag_name)),
+        Err(err) => Err(anyhow!("Failed to parse GitHub releases: {}", err)),
+    }
+}
+
+/// Date of the commit a tag or branch points to. Returns `None` when there's no such ref,
+/// e.g. when the tag of a release was deleted.
+pub async fn get_commit_date(
+    config: &GithubConfig,
+    reference: &str,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<Option<String>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!("{}/commits/{}", config.api_url(), reference))
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get commit `{}`: {}", reference, err)),
     };
 
-    // Upload mod jar
+    match res.status() {
+        reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::UNPROCESSABLE_ENTITY => Ok(None),
+        status if status.is_success() => match res.json::<CommitResponse>().await {
+            Ok(json) => Ok(json.commit.committer.and_then(|committer| committer.date)),
+            Err(err) => Err(anyhow!("Failed to parse commit `{}`: {}", reference, err)),
+        },
+        _ => Err(anyhow!(
+            "Failed to get commit `{}`: {}",
+            reference,
+            error_body(res).await
+        )),
+    }
+}
+
+/// Searches the pull requests merged after `since`, or all of them, a page of 100 at a time.
+/// The search API returns at most 1000 results per query.
+pub async fn get_merged_pull_requests(
+    config: &GithubConfig,
+    since: Option<&str>,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<Vec<SearchIssue>, anyhow::Error> {
+    const PER_PAGE: usize = 100;
 
-    println!(
-        "Uploading mod jar as GitHub Release asset `{}`...",
-        &mod_jars.mod_jar.file_name
+    let mut query = format!(
+        "repo:{}/{} is:pr is:merged",
+        config.repo_owner, config.repo_name
     );
+    if let Some(since) = since {
+        query.push_str(&format!(" merged:>{}", since));
+    }
 
-    match reqwest::Client::new()
-        .post(format!(
-            "https://uploads.github.com/repos/{}/{}/releases/{}/assets?name=\"{}\"",
-            github_config.repo_owner,
-            github_config.repo_name,
-            release_id,
-            &mod_jars.mod_jar.file_name
-        ))
-        .header("User-Agent", env!("CARGO_PKG_NAME"))
-        .header("Accept", "application/vnd.github+json")
-        .header("Content-Type", "application/java-archive")
-        .bearer_auth(&token)
-        .body(mod_jar_contents)
-        .send()
+    let mut pull_requests = vec![];
+    let mut page = 1;
+
+    loop {
+        let res = match send_with_retry(network, |client| {
+            Ok(client
+                .get(config.search_url())
+                .query(&[
+                    ("q", query.clone()),
+                    ("per_page", PER_PAGE.to_string()),
+                    ("page", page.to_string()),
+                ])
+                .header("Accept", "application/vnd.github+json")
+                .bearer_auth(token.expose()))
+        })
         .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(anyhow!("Failed to search pull requests: {}", err)),
+        };
+
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "Failed to search pull requests: {}",
+                error_body(res).await
+            ));
+        }
+
+        let results = match res.json::<SearchIssuesResponse>().await {
+            Ok(json) => json,
+            Err(err) => return Err(anyhow!("Failed to parse pull request search: {}", err)),
+        };
+
+        if results.incomplete_results {
+            warn!("GitHub's pull request search timed out, the changelog may be incomplete");
+        }
+
+        let is_last_page = results.items.len() < PER_PAGE;
+        pull_requests.extend(results.items);
+
+        if is_last_page || pull_requests.len() >= results.total_count as usize {
+            break;
+        }
+        if pull_requests.len() >= 1000 {
+            warn!(
+                "Only the first 1000 of {} pull requests are listed",
+                results.total_count
+            );
+            break;
+        }
+
+        page += 1;
+    }
+
+    Ok(pull_requests)
+}
+
+/// Replaces the body of an existing release, along with any assets of the same name.
+pub async fn update_github_release(
+    config: &GithubConfig,
+    existing: ReleaseResponse,
+    release_req: &CreateReleaseRequest,
+    assets: &[ReleaseAsset],
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    info!(
+        "Updating existing GitHub release `{}`...",
+        existing.tag_name
+    );
+
+    let update_req = UpdateReleaseRequest {
+        name: release_req.name.clone(),
+        body: release_req.body.clone(),
+        ..Default::default()
+    };
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .patch(format!("{}/releases/{}", config.api_url(), existing.id))
+            .json(&update_req)
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
     {
-        Ok(_) => {
-            println!(
-                "Successfully uploaded GitHub Release asset `{}`!",
-                &mod_jars.mod_jar.file_name
-            )
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to update GitHub release: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to update GitHub release: {}",
+            error_body(res).await
+        ));
+    }
+
+    // Every asset is tried, so that one failure doesn't leave the others out of date
+    let mut failures = vec![];
+
+    for asset in assets {
+        let old_asset = existing
+            .assets
+            .iter()
+            .find(|old_asset| old_asset.name == asset.file_name);
+
+        let res = match old_asset {
+            Some(old_asset) => {
+                replace_release_asset(config, existing.id, old_asset, asset, token, network).await
+            }
+            None => {
+                upload_release_asset(config, existing.id, asset, &asset.file_name, token, network)
+                    .await
+                    .map(|_| ())
+            }
+        };
+
+        if let Err(err) = res {
+            failures.push(err.to_string());
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(anyhow!(
+            "Failed to update the assets of GitHub release `{}`:\n  - {}",
+            existing.tag_name,
+            failures.join("\n  - ")
+        ));
+    }
+
+    info!("Successfully updated GitHub release!");
+
+    Ok(existing.html_url)
+}
+
+/// The new file is uploaded under a temporary name first, so the old one is only deleted once
+/// its replacement is on the release. Errors say which file the release is left with.
+async fn replace_release_asset(
+    config: &GithubConfig,
+    release_id: i32,
+    old_asset: &GithubAsset,
+    asset: &ReleaseAsset,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    let temp_name = format!("peony-upload-{}", asset.file_name);
+
+    let uploaded =
+        match upload_release_asset(config, release_id, asset, &temp_name, token, network).await {
+            Ok(uploaded) => uploaded,
+            Err(err) => return Err(anyhow!("{}, the old `{}` is kept", err, asset.file_name)),
+        };
+
+    if let Err(err) = delete_release_asset(config, old_asset, token, network).await {
+        if let Err(delete_err) = delete_release_asset(config, &uploaded, token, network).await {
+            warn!("{}", delete_err);
         }
+        return Err(anyhow!("{}, the old `{}` is kept", err, asset.file_name));
+    }
+
+    match rename_release_asset(config, &uploaded, &asset.file_name, token, network).await {
+        Ok(()) => Ok(()),
+        Err(err) => Err(anyhow!(
+            "{}, the old `{}` was deleted and the new one is on the release as `{}`",
+            err,
+            asset.file_name,
+            uploaded.name
+        )),
+    }
+}
+
+pub async fn rename_release_asset(
+    config: &GithubConfig,
+    asset: &GithubAsset,
+    name: &str,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    match send_with_retry(network, |client| {
+        Ok(client
+            .patch(format!("{}/releases/assets/{}", config.api_url(), asset.id))
+            .json(&UpdateAssetRequest {
+                name: name.to_string(),
+            })
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) if res.status().is_success() => Ok(()),
+        Ok(res) => Err(anyhow!(
+            "Failed to rename GitHub release asset `{}` to `{}`: {}",
+            asset.name,
+            name,
+            error_body(res).await
+        )),
+        Err(err) => Err(anyhow!(
+            "Failed to rename GitHub release asset `{}` to `{}`: {}",
+            asset.name,
+            name,
+            err
+        )),
+    }
+}
+
+pub async fn delete_release_asset(
+    config: &GithubConfig,
+    asset: &GithubAsset,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    match send_with_retry(network, |client| {
+        Ok(client
+            .delete(format!("{}/releases/assets/{}", config.api_url(), asset.id))
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) if res.status().is_success() => Ok(()),
+        Ok(res) => Err(anyhow!(
+            "Failed to delete GitHub release asset `{}`: `{}`",
+            asset.name,
+            res.status()
+        )),
+        Err(err) => Err(anyhow!(
+            "Failed to delete GitHub release asset `{}`: {}",
+            asset.name,
+            err
+        )),
+    }
+}
+
+pub struct ReleaseAsset {
+    pub file_name: String,
+    pub content_type: &'static str,
+    pub file_path: PathBuf,
+}
+
+/// Releases are created as drafts and only published once every asset is uploaded, so that
+/// users never see a release without its files. Failed releases are deleted again.
+pub async fn finish_github_release(
+    config: &GithubConfig,
+    release: ReleaseResponse,
+    assets: &[ReleaseAsset],
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    let res = async {
+        for asset in assets {
+            upload_release_asset(config, release.id, asset, &asset.file_name, token, network)
+                .await?;
+        }
+
+        if config.is_draft() {
+            info!("Leaving GitHub release as a draft");
+            return Ok(release.html_url.clone());
+        }
+
+        publish_github_release(config, release.id, token, network).await
+    }
+    .await;
+
+    if res.is_err() {
+        info!("Deleting incomplete GitHub release...");
+        delete_github_release(config, &release, token, network).await;
+    }
+
+    res
+}
+
+pub async fn upload_release_asset(
+    config: &GithubConfig,
+    release_id: i32,
+    asset: &ReleaseAsset,
+    name: &str,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<GithubAsset, anyhow::Error> {
+    info!("Uploading GitHub release asset `{}`...", asset.file_name);
+
+    let size = file_size(&asset.file_path)?;
+    let bar = UploadBar::new(&asset.file_name, size);
+    let progress = bar.callback();
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .post(format!(
+                "{}/releases/{}/assets",
+                config.upload_url(),
+                release_id
+            ))
+            .query(&[("name", name)])
+            .header("Accept", "application/vnd.github+json")
+            .header("Content-Type", asset.content_type)
+            .header(CONTENT_LENGTH, size)
+            .bearer_auth(token.expose())
+            .body(file_body(&asset.file_path, Some(&progress))?)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
         Err(err) => {
             return Err(anyhow!(
                 "Failed to upload GitHub release asset `{}`: {}",
-                &mod_jars.mod_jar.file_name,
+                asset.file_name,
                 err
             ))
         }
-    }
+    };
 
-    // Upload sources jar
-    if let Some(file_contents) = sources_jar_contents {
-        println!(
-            "Uploading sources jar as GitHub Release asset `{}`...",
-            &mod_jars.sources_jar.clone().unwrap().file_name
-        );
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to upload GitHub release asset `{}`: {}",
+            asset.file_name,
+            error_body(res).await
+        ));
+    }
 
-        match reqwest::Client::new()
-            .post(format!(
-                "https://uploads.github.com/repos/{}/{}/releases/{}/assets?name=\"{}\"",
-                github_config.repo_owner,
-                github_config.repo_name,
-                release_id,
-                &mod_jars.sources_jar.clone().unwrap().file_name
+    let uploaded = match res.json::<GithubAsset>().await {
+        Ok(json) => json,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to parse uploaded GitHub release asset `{}`: {}",
+                asset.file_name,
+                err
             ))
-            .header("User-Agent", env!("CARGO_PKG_NAME"))
+        }
+    };
+
+    if uploaded.size as u64 != size {
+        return Err(anyhow!(
+            "GitHub release asset `{}` is {} bytes, but the local file is {} bytes",
+            asset.file_name,
+            uploaded.size,
+            size
+        ));
+    }
+
+    info!(
+        "Successfully uploaded GitHub release asset `{}`!",
+        asset.file_name
+    );
+
+    Ok(uploaded)
+}
+
+pub async fn publish_github_release(
+    config: &GithubConfig,
+    release_id: i32,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .patch(format!("{}/releases/{}", config.api_url(), release_id))
+            .json(&UpdateReleaseRequest {
+                draft: Some(false),
+                ..Default::default()
+            })
             .header("Accept", "application/vnd.github+json")
-            .header("Content-Type", "application/java-archive")
-            .bearer_auth(&token)
-            .body(file_contents)
-            .send()
-            .await
-        {
-            Ok(_) => {
-                println!(
-                    "Successfully uploaded GitHub Release asset `{}`!",
-                    &mod_jars.sources_jar.clone().unwrap().file_name
-                )
-            }
-            Err(err) => {
-                return Err(anyhow!(
-                    "Failed to upload GitHub release asset `{}`: {}",
-                    &mod_jars.sources_jar.clone().unwrap().file_name,
-                    err
-                ))
-            }
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to publish GitHub release: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to publish GitHub release: {}",
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<ReleaseResponse>().await {
+        Ok(json) => {
+            info!("Published GitHub release!");
+            Ok(json.html_url)
         }
+        Err(err) => Err(anyhow!("Failed to parse published GitHub release: {}", err)),
     }
+}
 
-    Ok(())
+/// Best effort, as this only runs once something else already failed.
+pub async fn delete_github_release(
+    config: &GithubConfig,
+    release: &ReleaseResponse,
+    token: &Secret,
+    network: &NetworkConfig,
+) {
+    let urls = [
+        format!("{}/releases/{}", config.api_url(), release.id),
+        format!(
+            "{}/git/refs/tags/{}",
+            config.api_url(),
+            encode_path_segment(&release.tag_name)
+        ),
+    ];
+
+    for url in urls {
+        match send_with_retry(network, |client| {
+            Ok(client
+                .delete(&url)
+                .header("Accept", "application/vnd.github+json")
+                .bearer_auth(token.expose()))
+        })
+        .await
+        {
+            // Drafts don't have a tag until they're published
+            Ok(res)
+                if res.status().is_success() || res.status() == reqwest::StatusCode::NOT_FOUND => {}
+            Ok(res) => warn!("Failed to delete `{}`: `{}`", url, res.status()),
+            Err(err) => warn!("Failed to delete `{}`: {}", url, err),
+        }
+    }
 }
 
 pub async fn create_github_release(
     config: &GithubConfig,
     new_release_body: &CreateReleaseRequest,
-    token: &String,
+    token: &Secret,
+    network: &NetworkConfig,
 ) -> Result<ReleaseResponse, anyhow::Error> {
-    match reqwest::Client::new()
-        .post(format!(
-            "https://api.github.com/repos/{}/{}/releases",
-            config.repo_owner.clone(),
-            config.repo_name.clone()
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .post(format!("{}/releases", config.api_url()))
+            .json(&new_release_body)
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to create GitHub release: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to create GitHub release: {}",
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<ReleaseResponse>().await {
+        Ok(json) => Ok(json),
+        Err(err) => Err(anyhow!("Failed to parse created GitHub release: {}", err)),
+    }
+}
+
+pub async fn tag_exists(
+    config: &GithubConfig,
+    tag_name: &str,
+    network: &NetworkConfig,
+) -> Result<bool, anyhow::Error> {
+    let github_token = config.token().ok();
+
+    let res = match send_with_retry(network, |client| {
+        let req = client
+            .get(format!(
+                "{}/git/ref/tags/{}",
+                config.api_url(),
+                encode_path_segment(tag_name)
+            ))
+            .header("Accept", "application/vnd.github+json");
+
+        Ok(match &github_token {
+            Some(token) => req.bearer_auth(token.expose()),
+            None => req,
+        })
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get GitHub tag `{}`: {}", tag_name, err)),
+    };
+
+    match res.status() {
+        status if status.is_success() => Ok(true),
+        reqwest::StatusCode::NOT_FOUND => Ok(false),
+        status => Err(anyhow!(
+            "Failed to get GitHub tag `{}`: `{}`",
+            tag_name,
+            status
+        )),
+    }
+}
+
+/// Fails before anything is exported or uploaded if the release can't be created.
+pub async fn check_release_target(
+    config: &GithubConfig,
+    version: &str,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    config.check_tag_name(version)?;
+
+    let branch = match &config.target_commitish {
+        Some(branch) => branch,
+        None => return Ok(()),
+    };
+
+    let github_token = config.token().ok();
+
+    let res = match send_with_retry(network, |client| {
+        let req = client
+            .get(format!("{}/branches/{}", config.api_url(), branch))
+            .header("Accept", "application/vnd.github+json");
+
+        Ok(match &github_token {
+            Some(token) => req.bearer_auth(token.expose()),
+            None => req,
+        })
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get GitHub branch `{}`: {}", branch, err)),
+    };
+
+    match res.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::NOT_FOUND => Err(anyhow!(
+            "Target branch `{}` doesn't exist in `{}/{}`",
+            branch,
+            config.repo_owner,
+            config.repo_name
+        )),
+        status => Err(anyhow!(
+            "Failed to get GitHub branch `{}`: `{}`",
+            branch,
+            status
+        )),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_support::{test_dir, upload_sink};
+    use std::fs::File;
+
+    fn github_config(api_base_url: &str) -> GithubConfig {
+        toml::from_str(&format!(
+            r#"
+repo_owner = "LilydevMC"
+repo_name = "peony-test"
+api_base_url = "{0}"
+upload_base_url = "{0}"
+"#,
+            api_base_url
         ))
-        .json(&new_release_body)
-        .header("User-Agent", env!("CARGO_PKG_NAME"))
-        .header("Accept", "application/vnd.github+json")
-        .bearer_auth(token)
-        .send()
+        .unwrap()
+    }
+
+    /// A release as GitHub returns it, with the assets of `asset_json`.
+    pub(crate) fn release_json(id: i32, tag_name: &str, assets: &[String]) -> String {
+        let assets = assets
+            .iter()
+            .map(|asset| serde_json::from_str::<serde_json::Value>(asset).unwrap())
+            .collect::<Vec<serde_json::Value>>();
+        let user_url = "https://api.github.com/users/LilydevMC";
+
+        serde_json::json!({
+            "url": format!("https://api.github.com/releases/{}", id),
+            "html_url": format!("https://github.com/LilydevMC/peony-test/releases/tag/{}", tag_name),
+            "assets_url": format!("https://api.github.com/releases/{}/assets", id),
+            "upload_url": format!("https://uploads.github.com/releases/{}/assets", id),
+            "tarball_url": null,
+            "zipball_url": null,
+            "id": id,
+            "node_id": "RE_1",
+            "tag_name": tag_name,
+            "target_commitish": "main",
+            "name": tag_name,
+            "body": "",
+            "draft": true,
+            "prerelease": false,
+            "created_at": "2024-01-01T00:00:00Z",
+            "published_at": null,
+            "author": {
+                "name": null,
+                "email": null,
+                "login": "LilydevMC",
+                "id": 1,
+                "node_id": "U_1",
+                "avatar_url": "https://avatars.githubusercontent.com/u/1",
+                "gravatar_url": null,
+                "url": user_url,
+                "html_url": "https://github.com/LilydevMC",
+                "followers_url": format!("{}/followers", user_url),
+                "following_url": format!("{}/following", user_url),
+                "gists_url": format!("{}/gists", user_url),
+                "starred_url": format!("{}/starred", user_url),
+                "subscriptions_url": format!("{}/subscriptions", user_url),
+                "organizations_url": format!("{}/orgs", user_url),
+                "repos_url": format!("{}/repos", user_url),
+                "events_url": format!("{}/events", user_url),
+                "received_events_url": format!("{}/received_events", user_url),
+                "type": "Organization",
+                "site_admin": false,
+                "starred_at": null,
+            },
+            "assets": assets,
+            "body_html": null,
+            "body_text": null,
+            "mentions_count": null,
+            "discussion_url": null,
+            "reactions": null,
+        })
+        .to_string()
+    }
+
+    pub(crate) fn asset_json(id: i32, name: &str, size: u64) -> String {
+        serde_json::json!({
+            "url": format!("https://api.github.com/assets/{}", id),
+            "browser_download_url": format!("https://github.com/download/{}", name),
+            "id": id,
+            "node_id": "RA_1",
+            "name": name,
+            "label": null,
+            "state": "uploaded",
+            "content_type": "application/zip",
+            "size": size,
+            "download_count": 0,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn existing_tag() {
+        let mut server = mockito::Server::new_async().await;
+        let found = server
+            .mock("GET", "/repos/LilydevMC/peony-test/git/ref/tags/1.0.0")
+            .with_status(200)
+            .with_body(r#"{"ref": "refs/tags/1.0.0"}"#)
+            .create_async()
+            .await;
+        let missing = server
+            .mock("GET", "/repos/LilydevMC/peony-test/git/ref/tags/1.1.0")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let config = github_config(&server.url());
+        let network = NetworkConfig::default();
+
+        assert!(tag_exists(&config, "1.0.0", &network).await.unwrap());
+        assert!(!tag_exists(&config, "1.1.0", &network).await.unwrap());
+        found.assert_async().await;
+        missing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn tag_is_a_single_path_segment() {
+        let mut server = mockito::Server::new_async().await;
+        let found = server
+            .mock(
+                "GET",
+                "/repos/LilydevMC/peony-test/git/ref/tags/mod%2F1.0.0%2Bfabric",
+            )
+            .with_status(200)
+            .with_body(r#"{"ref": "refs/tags/mod/1.0.0+fabric"}"#)
+            .create_async()
+            .await;
+
+        let exists = tag_exists(
+            &github_config(&server.url()),
+            "mod/1.0.0+fabric",
+            &NetworkConfig::default(),
+        )
         .await
-    {
-        Ok(res) => match res.json::<ReleaseResponse>().await {
-            Ok(json) => Ok(json),
-            Err(err) => Err(anyhow::Error::from(err)),
-        },
-        Err(err) => Err(anyhow::Error::from(err)),
+        .unwrap();
+
+        assert!(exists);
+        found.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn tag_check_fails_on_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/repos/LilydevMC/peony-test/git/ref/tags/1.0.0")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        let err = tag_exists(
+            &github_config(&server.url()),
+            "1.0.0",
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().contains("401"), "{}", err);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn asset_upload_streams_large_file() {
+        use crate::test_support::peak_memory;
+
+        const FILE_SIZE: u64 = 512 * 1024 * 1024;
+
+        let dir = test_dir();
+        let file_path = dir.dir_path.join("Pack-1.0.0.mrpack");
+        File::create(&file_path)
+            .unwrap()
+            .set_len(FILE_SIZE)
+            .unwrap();
+
+        let (url, server) = upload_sink(asset_json(1, "Pack-1.0.0.mrpack", FILE_SIZE)).await;
+        let asset = ReleaseAsset {
+            file_name: "Pack-1.0.0.mrpack".to_string(),
+            content_type: "application/zip",
+            file_path,
+        };
+
+        let memory_before = peak_memory();
+        let uploaded = upload_release_asset(
+            &github_config(&url),
+            7,
+            &asset,
+            &asset.file_name,
+            &Secret::new("test-token".to_string()),
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap();
+        let memory_growth = peak_memory().saturating_sub(memory_before);
+
+        let (head, received) = server.await.unwrap();
+        assert!(
+            head.starts_with(
+                "POST /repos/LilydevMC/peony-test/releases/7/assets?name=Pack-1.0.0.mrpack "
+            ),
+            "{}",
+            head
+        );
+        assert_eq!(received, FILE_SIZE);
+        assert_eq!(uploaded.name, "Pack-1.0.0.mrpack");
+        assert!(
+            memory_growth < FILE_SIZE / 4,
+            "uploading grew memory by {} bytes",
+            memory_growth
+        );
     }
 }
@@ -0,0 +1,229 @@
+use anyhow::anyhow;
+use reqwest::header::CONTENT_LENGTH;
+use std::path::Path;
+use tracing::info;
+
+use crate::models::{
+    gitlab::{
+        CreateGitlabReleaseRequest, GitlabProject, GitlabReleaseAssets, GitlabReleaseLink,
+        GitlabReleaseResponse,
+    },
+    project_type::modpack::{config::ModpackConfig, PackFile},
+    util::OutputFileInfo,
+    version::VersionInfo,
+    GitlabConfig, NetworkConfig,
+};
+use crate::progress::UploadBar;
+use crate::redact::Secret;
+use crate::util::{error_body, file_body, file_size, read_secret_env, send_with_retry};
+
+/// GitLab releases can't hold files themselves, so the files are uploaded to the project's
+/// generic package registry and linked from the release.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_gitlab_release(
+    config: &ModpackConfig,
+    gitlab_config: &GitlabConfig,
+    pack_file: &PackFile,
+    output_file_info: &OutputFileInfo,
+    version_info: &VersionInfo,
+    changelog: &str,
+    checksums_file_info: &OutputFileInfo,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let tag_name = gitlab_config.tag_name(&pack_file.version);
+    let package_name = package_name(&pack_file.name);
+
+    if dry_run {
+        info!(
+            "[dry run] Would create GitLab release in `{}`:\n  \
+             Tag name: {}\n  \
+             Release name: {}\n  \
+             Package: {} {}\n  \
+             Assets: {}, {}\n  \
+             Description:\n{}",
+            gitlab_config.web_url(),
+            tag_name,
+            version_info.version_name,
+            package_name,
+            pack_file.version,
+            output_file_info.file_name,
+            checksums_file_info.file_name,
+            changelog
+        );
+        return Ok(format!(
+            "{}/-/releases/{}",
+            gitlab_config.web_url(),
+            tag_name
+        ));
+    }
+
+    info!("Creating GitLab release...");
+
+    let token_env = gitlab_config.token_env();
+    let gitlab_token = read_secret_env(&token_env)?;
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let mut links = vec![];
+
+    for file_info in [output_file_info, checksums_file_info] {
+        let url = upload_package_file(
+            gitlab_config,
+            &package_name,
+            &pack_file.version,
+            &file_info.file_name,
+            &file_info.file_path,
+            &gitlab_token,
+            &network,
+        )
+        .await?;
+
+        links.push(GitlabReleaseLink {
+            name: file_info.file_name.clone(),
+            url,
+            link_type: "package".to_string(),
+        });
+    }
+
+    let ref_name = match &gitlab_config.ref_name {
+        Some(ref_name) => ref_name.clone(),
+        None => get_default_branch(gitlab_config, &gitlab_token, &network).await?,
+    };
+
+    let release_req = CreateGitlabReleaseRequest {
+        name: version_info.version_name.clone(),
+        tag_name,
+        ref_name,
+        description: changelog.to_string(),
+        assets: GitlabReleaseAssets { links },
+    };
+
+    let res = match send_with_retry(&network, |client| {
+        Ok(client
+            .post(format!("{}/releases", gitlab_config.api_url()))
+            .header("PRIVATE-TOKEN", gitlab_token.expose())
+            .json(&release_req))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to create GitLab release: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to create GitLab release: {}",
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<GitlabReleaseResponse>().await {
+        Ok(json) => {
+            info!("Successfully created GitLab release!");
+            Ok(json.links.self_url)
+        }
+        Err(err) => Err(anyhow!("Failed to parse GitLab release: {}", err)),
+    }
+}
+
+/// Returns the download URL of the uploaded file.
+pub async fn upload_package_file(
+    gitlab_config: &GitlabConfig,
+    package_name: &str,
+    version: &str,
+    file_name: &str,
+    file_path: &Path,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    info!(
+        "Uploading `{}` to the GitLab package registry...",
+        file_name
+    );
+
+    let url = format!(
+        "{}/packages/generic/{}/{}/{}",
+        gitlab_config.api_url(),
+        package_name,
+        version,
+        file_name
+    );
+
+    let size = file_size(file_path)?;
+    let bar = UploadBar::new(file_name, size);
+    let progress = bar.callback();
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .put(&url)
+            .header("PRIVATE-TOKEN", token.expose())
+            .header(CONTENT_LENGTH, size)
+            .body(file_body(file_path, Some(&progress))?)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to upload `{}` to GitLab: {}",
+                file_name,
+                err
+            ))
+        }
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to upload `{}` to GitLab: {}",
+            file_name,
+            error_body(res).await
+        ));
+    }
+
+    Ok(url)
+}
+
+pub async fn get_default_branch(
+    gitlab_config: &GitlabConfig,
+    token: &Secret,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(gitlab_config.api_url())
+            .header("PRIVATE-TOKEN", token.expose()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get GitLab project: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "GitLab returned `{}` for project `{}`",
+            res.status(),
+            gitlab_config.project_id
+        ));
+    }
+
+    match res.json::<GitlabProject>().await {
+        Ok(project) => Ok(project.default_branch),
+        Err(err) => Err(anyhow!("Failed to parse GitLab project: {}", err)),
+    }
+}
+
+/// Generic package names may only contain letters, digits, `.`, `_` and `-`.
+pub fn package_name(pack_name: &str) -> String {
+    pack_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+//! Commands from `[hooks]`, run in the project directory at points of a modpack release.
+
+use anyhow::anyhow;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+/// Runs `commands` one after another with the shell, stopping at the first that fails.
+/// `env` is exported to each of them, e.g. `PEONY_VERSION`.
+pub fn run_hooks(
+    stage: &str,
+    commands: &[String],
+    project_dir: &Path,
+    env: &[(&str, String)],
+) -> Result<(), anyhow::Error> {
+    for command in commands {
+        info!("Running {} hook `{}`...", stage, command);
+
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+
+        let status = shell
+            .arg(command)
+            .current_dir(project_dir)
+            .envs(env.iter().map(|(name, value)| (name, value)))
+            .status();
+
+        match status {
+            Ok(status) if status.success() => (),
+            Ok(status) => {
+                return Err(anyhow!(
+                    "{} hook `{}` failed with {}",
+                    stage,
+                    command,
+                    status
+                ))
+            }
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to run {} hook `{}`: {}",
+                    stage,
+                    command,
+                    err
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
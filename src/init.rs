@@ -0,0 +1,376 @@
+use anyhow::anyhow;
+use std::io::Write;
+use std::path::Path;
+use std::{fs, io};
+
+use peony::pack::get_pack_file;
+
+pub struct InitOptions {
+    pub pack_name: Option<String>,
+    pub repo_owner: String,
+    pub repo_name: String,
+    pub modrinth_project_id: String,
+    pub discord: bool,
+}
+
+pub fn prompt(question: &str, default: Option<&str>) -> Result<String, anyhow::Error> {
+    match default {
+        Some(default) => print!("{} [{}]: ", question, default),
+        None => print!("{}: ", question),
+    }
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => (),
+        Err(err) => return Err(anyhow!("Failed to read answer from stdin: {}", err)),
+    }
+
+    let answer = answer.trim();
+    if answer.is_empty() {
+        match default {
+            Some(default) => Ok(default.to_string()),
+            None => prompt(question, default),
+        }
+    } else {
+        Ok(answer.to_string())
+    }
+}
+
+/// Like [`prompt`], without echoing the answer.
+#[cfg(feature = "keyring")]
+pub fn prompt_secret(question: &str) -> Result<String, anyhow::Error> {
+    let answer = match rpassword::prompt_password(format!("{}: ", question)) {
+        Ok(answer) => answer,
+        Err(err) => return Err(anyhow!("Failed to read answer from stdin: {}", err)),
+    };
+
+    let answer = answer.trim();
+    if answer.is_empty() {
+        prompt_secret(question)
+    } else {
+        Ok(answer.to_string())
+    }
+}
+
+pub fn prompt_bool(question: &str, default: bool) -> Result<bool, anyhow::Error> {
+    let answer = prompt(question, Some(if default { "Y/n" } else { "y/N" }))?;
+
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+pub fn pack_name_from_dir(project_dir: &Path) -> Option<String> {
+    if !project_dir.join("pack.toml").exists() {
+        return None;
+    }
+
+    get_pack_file(project_dir).ok().map(|pack| pack.name)
+}
+
+pub fn generate_modpack_config(options: &InitOptions) -> String {
+    let header = match &options.pack_name {
+        Some(name) => format!("# Peony release config for {}\n", name),
+        None => "# Peony release config\n".to_string(),
+    };
+
+    let discord_lines = [
+        "[discord]",
+        "# Emojis shown next to the GitHub and Modrinth links, e.g. \"<:github:1234567890>\"",
+        "github_emoji_id = \"\"",
+        "modrinth_emoji_id = \"\"",
+        "# curseforge_emoji_id = \"\"",
+        "# gitlab_emoji_id = \"\"",
+        "# gitea_emoji_id = \"\"",
+        "# Message content sent alongside the embed, usually a role id or `<@&id>` mention.",
+        "discord_ping_role = \"\"",
+        "title_emoji = \"\"",
+        "# embed_image_url = \"\"",
+        "# thumbnail_image_url = \"\"",
+        "# Defaults to the Modrinth project color.",
+        "# embed_color = \"#232634\"",
+        "# Post into a forum channel as a new post, or into an existing thread.",
+        "# thread_name = \"{version}\"",
+        "# thread_id = 1234567890",
+        "# Allow pinging @everyone and @here, only the role above is pinged otherwise.",
+        "# everyone = false",
+        "# Show the version, Minecraft version, loaders, file size and mod count as embed fields.",
+        "# show_fields = false",
+        "",
+        "# Footer of the embed, with `{project_type}`, `{version_type}`, `{version}` and `{release_time}` placeholders.",
+        "# footer_text = \"{project_type} | {version_type}\"",
+        "",
+        "# Attach the full changelog as a .md file when it's cut to fit the embed.",
+        "# attach_changelog = false",
+        "",
+        "# Description of the embed, lines with the URL of a platform the release isn't on are left out.",
+        "# description_template = \"**New release!**\\n\\n{github_emoji} [GitHub]({github_url})\\n{modrinth_emoji} [Modrinth]({modrinth_url})\\n\\n{changelog}\"",
+        "",
+        "# Extra webhooks, each URL read from its own environment variable, used instead of `WEBHOOK_URL`.",
+        "# [[discord.webhooks]]",
+        "# env_var = \"STAFF_WEBHOOK_URL\"",
+        "# discord_ping_role = \"\"",
+        "# embed_color = \"#232634\"",
+    ];
+
+    // When Discord isn't wanted, the whole section is commented out so it can be enabled later.
+    let discord_section = discord_lines
+        .iter()
+        .map(|line| {
+            if options.discord || line.is_empty() || line.starts_with('#') {
+                format!("{}\n", line)
+            } else {
+                format!("# {}\n", line)
+            }
+        })
+        .collect::<String>();
+
+    format!(
+        "\
+{header}
+config_format_version = 1
+# Strings can read environment variables with ${{VAR}} or ${{VAR:-default}}, use $${{ for a
+# literal ${{. `[hooks]` is left to the shell running the commands.
+# Available placeholders: {{pack_name}}, {{version}}, {{mc_version}}, {{loader}}.
+# Use {{{{ and }}}} for literal braces.
+version_name_format = \"{{pack_name}} v{{version}} for Minecraft {{mc_version}} ({{loader}})\"
+# Name of the uploaded .mrpack instead of the one packwiz picks, with the placeholders
+# {{name}}, {{version}} and {{mc_version}}.
+# output_filename = \"{{name}}-{{mc_version}}-{{version}}\"
+# Extra files uploaded to Modrinth next to the .mrpack, relative to this directory.
+# additional_files = [\"build/*-server.zip\"]
+# Left out when copying the pack to a temporary directory for the export, in gitignore syntax.
+# Files ignored by .gitignore are never copied.
+# copy_excludes = [\".git\", \"build/\", \".gradle/\", \"node_modules/\"]
+# Export in this directory instead of a copy, which skips the copy but runs packwiz on your
+# files. pack.toml and the index are restored and exported files removed afterwards, but
+# leftover exports can make the .mrpack ambiguous, see `--latest`.
+# in_place = false
+
+# The release target sections (`[github]`, `[gitlab]`, `[gitea]`, `[modrinth]` and
+# `[curseforge]`) are all optional, but at least one of them is needed. Without `[github]`
+# the changelog defaults to the commits since the last tag.
+[github]
+repo_owner = \"{repo_owner}\"
+repo_name = \"{repo_name}\"
+# Set to false to never create GitHub releases.
+# enabled = true
+# One of \"always\", \"never\" or \"auto\" (pre-release when the version has a semver
+# pre-release component). Follows the Modrinth version type when not set.
+# prerelease = \"auto\"
+# Tag name of releases, e.g. for repos hosting several projects.
+# tag_format = \"modpack/v{{version}}\"
+# Branch the release tag is created on, defaults to the default branch.
+# target_commitish = \"releases\"
+# What to do when a release with the same tag exists: \"fail\", \"update\" (replace the
+# body and assets) or \"skip\".
+# on_existing = \"fail\"
+# Leave releases as drafts to publish them by hand.
+# draft = false
+# Attach `mods.md` and `mods.csv` with the name, version, side and links of every mod.
+# mod_list = true
+# Paste the mod list into a collapsed section of the release body as well.
+# mod_list_in_release_body = false
+# Environment variable holding the token, `GITHUB_TOKEN` and then `GH_TOKEN` by default.
+# token_env = \"RELEASE_TOKEN\"
+# For GitHub Enterprise Server, defaults to github.com.
+# api_base_url = \"https://github.example.com/api/v3\"
+# upload_base_url = \"https://github.example.com/api/uploads\"
+# web_base_url = \"https://github.example.com\"
+
+[modrinth]
+project_id = \"{project_id}\"
+# Upload to staging.modrinth.com instead of modrinth.com.
+# staging = false
+# API to upload to instead of Modrinth's, e.g. a local Labrinth.
+# api_base_url = \"http://localhost:8000/v2\"
+# Environment variable holding the token, `MODRINTH_TOKEN` by default.
+# token_env = \"MODRINTH_TOKEN\"
+# Set to false to never upload versions to Modrinth.
+# enabled = true
+# One of \"release\", \"beta\" or \"alpha\". Guessed from the version's pre-release
+# suffix (e.g. `-beta.1`) when not set.
+# version_type = \"release\"
+# Loaders and Minecraft versions to tag the version with. Default to the `versions`
+# table in pack.toml.
+# loaders = [\"quilt\"]
+# game_versions = [\"1.20.1\", \"1.20.2\"]
+
+# Projects listed as dependencies of every uploaded version.
+# `dependency_type` is one of \"required\", \"optional\", \"incompatible\" or \"embedded\".
+# [[modrinth.dependencies]]
+# project_id = \"P7dR8mSH\"
+# dependency_type = \"embedded\"
+
+# Where the release version comes from: \"pack\" (pack.toml), \"git-tag\" (the latest
+# tag, without `tag_prefix`) or \"env\" (the `env_var` environment variable).
+# [version]
+# source = \"git-tag\"
+# tag_prefix = \"v\"
+# env_var = \"GITHUB_REF_NAME\"
+
+# Changelog used for releases. \"github\" links to the changes since the latest GitHub
+# release, \"git\" lists the commits since the previous tag and \"index\" lists the mods
+# added, removed and updated since the previous release. \"file\" uses the section of
+# `path` (CHANGELOG.md by default) with the version as its heading. \"github-prs\" lists the
+# pull requests merged since the latest GitHub release, in the section of their label.
+# [changelog]
+# source = \"git\"
+# exclude = [\"^chore:\", \"^Merge\"]
+# default_message = \"Initial release\"
+# path = \"CHANGELOG.md\"
+# allow_unreleased = false
+# Link the previous release at the end of GitHub and Modrinth changelogs, \"\" to leave out.
+# footer = \"Full diff: [{{previous_tag}}...{{tag}}]({{compare_url}})\"
+# modrinth_footer = \"Previous version: [{{previous_version}}]({{previous_version_url}})\"
+# [changelog.labels]
+# enhancement = \"Added\"
+# bug = \"Fixed\"
+# mods = \"Mods updated\"
+
+# Create releases on GitLab too, or instead of GitHub when `[github]` is removed.
+# Files are uploaded to the project's generic package registry and linked from the release.
+# [gitlab]
+# base_url = \"https://gitlab.com\"
+# project_id = \"my-group/my-pack\"
+# token_env = \"GITLAB_TOKEN\"
+# tag_format = \"v{{version}}\"
+# ref_name = \"main\"
+
+# Mirror releases to a Gitea or Forgejo instance, with the files attached.
+# [gitea]
+# base_url = \"https://codeberg.org\"
+# owner = \"my-org\"
+# repo = \"my-pack\"
+# token_env = \"GITEA_TOKEN\"
+# tag_format = \"v{{version}}\"
+# target_commitish = \"main\"
+
+# Also export with `packwiz curseforge export` and upload the zip to CurseForge.
+# `game_versions` are CurseForge game version ids, not version names.
+# [curseforge]
+# project_id = 123456
+# token_env = \"CURSEFORGE_TOKEN\"
+# game_versions = [9990]
+
+# Retries for failed requests, with exponential backoff, and request timeouts.
+# [network]
+# retries = 3
+# base_delay_ms = 1000
+# Seconds before a request is given up on. Uploads and downloads of files get
+# `transfer_timeout_secs` instead, raise it for large packs on slow connections.
+# timeout_secs = 30
+# transfer_timeout_secs = 120
+# connect_timeout_secs = 10
+# `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` are honored. Certificates of a private CA,
+# e.g. of a proxy, can be trusted with a PEM bundle.
+# ca_bundle = \"/etc/ssl/certs/company-ca.pem\"
+# Disables TLS certificate verification entirely. Never use this outside of test setups.
+# danger_accept_invalid_certs = false
+
+# Download this packwiz release instead of using the one on `PATH`, so that every machine
+# exports with the same version. A commit is built with `go install` instead.
+# [packwiz]
+# version = \"v0.16.1\"
+# system = false
+# Expected SHA-256 of the download, or of the built binary for a commit. Checked instead of
+# the release's checksums file.
+# sha256 = \"\"
+# Run `packwiz refresh` before exporting, so that a stale index is never released.
+# refresh_before_export = true
+
+# Also release a server pack without client-only mods, as `<pack>-server-<version>.mrpack`.
+# It's uploaded to GitHub and Modrinth and linked in the Discord embed.
+# [server_pack]
+# exclude_sides = [\"client\"]
+# Files to leave out as well, relative to the packwiz index.
+# exclude = [\"config/shaders/**\"]
+
+# Shell commands run in the project directory, with `PEONY_VERSION` and `PEONY_VERSION_NAME`
+# set. `PEONY_MRPACK_PATH` is set from `post_export` on, and `PEONY_GITHUB_URL` and
+# `PEONY_MODRINTH_URL` for `post_release` when those releases succeeded. A failing `pre_*`
+# or `post_export` hook aborts the release. Release hooks don't run in dry runs.
+# [hooks]
+# pre_export = [\"./scripts/generate-mod-list.sh\"]
+# post_release = [\"git -C website submodule update --remote\"]
+# \"warn\" or \"fail\", whether a failing `post_release` hook fails the command.
+# post_release_failure = \"warn\"
+
+# Overrides used with `--profile staging`. Tables are merged key by key, other values
+# (arrays included) replace the value of the base config.
+# [profile.staging.modrinth]
+# staging = true
+# [profile.staging.github]
+# repo_name = \"{repo_name}-testing\"
+# [[profile.staging.discord.webhooks]]
+# env_var = \"STAGING_WEBHOOK_URL\"
+
+# Only used when running with `--slack`, posts the changelog with buttons to the releases.
+# [slack]
+# webhook_env = \"SLACK_WEBHOOK_URL\"
+# title_emoji = \":package:\"
+
+# Posts releases to a Matrix room when running with `--matrix`, or always with `enabled`.
+# The account's access token is read from `MATRIX_ACCESS_TOKEN`.
+# [matrix]
+# homeserver_url = \"https://matrix.org\"
+# room_id = \"!abcdefghijklmnop:matrix.org\"
+# access_token_env = \"MATRIX_ACCESS_TOKEN\"
+# enabled = false
+
+# Posts a status once the version was released everywhere. Failing to post only warns.
+# The token, with the `write:statuses` scope, is read from `MASTODON_TOKEN`.
+# [mastodon]
+# instance_url = \"https://mastodon.social\"
+# One of \"public\", \"unlisted\", \"private\" (followers only) or \"direct\".
+# visibility = \"public\"
+# The changelog summary is cut to fit the character limit of the instance.
+# template = \"{{version}} is out!\\n\\n{{changelog_summary}}\\n\\n{{modrinth_url}}\"
+
+# Requests sent to any HTTP endpoint after a release, one table per endpoint. The body can use
+# {{version}}, {{version_name}}, {{version_type}}, {{changelog}}, {{file_name}}, {{sha512}},
+# {{github_url}}, {{gitlab_url}}, {{gitea_url}}, {{modrinth_url}}, {{curseforge_url}} and
+# {{server_pack_url}}, with `{{{{` and `}}}}` for literal braces. Values are escaped for JSON
+# strings unless `escape = \"none\"`.
+# [[notify.http]]
+# name = \"status page\"
+# url_env = \"STATUS_WEBHOOK_URL\"
+# method = \"POST\"
+# headers = {{ Authorization = \"Bearer ...\" }}
+# content_type = \"application/json\"
+# body = '{{{{ \"text\": \"{{version_name}} is out: {{modrinth_url}}\" }}}}'
+
+# Emails releases with an HTML and a plain text changelog. Failing to send only warns, and
+# `peony validate` tests the connection. The password is read from `SMTP_PASSWORD`.
+# [email]
+# host = \"smtp.example.com\"
+# One of \"starttls\" (port 587), \"ssl\" (port 465) or \"none\" (port 25).
+# security = \"starttls\"
+# username = \"releases@example.com\"
+# from = \"{repo_name} <releases@example.com>\"
+# to = [\"admins@example.com\"]
+# subject = \"{{version_name}} released\"
+
+# Only used when running with `--discord`. The webhook URL is read from `WEBHOOK_URL`.
+{discord_section}",
+        header = header,
+        repo_owner = options.repo_owner,
+        repo_name = options.repo_name,
+        project_id = options.modrinth_project_id,
+        discord_section = discord_section,
+    )
+}
+
+pub fn write_modpack_config(path: &Path, options: &InitOptions) -> Result<(), anyhow::Error> {
+    match fs::write(path, generate_modpack_config(options)) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!(
+            "Failed to write config file `{}`: {}",
+            path.display(),
+            err
+        )),
+    }
+}
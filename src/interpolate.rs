@@ -0,0 +1,236 @@
+//! `${VAR}` interpolation of config strings, so that one config can be shared between forks.
+
+use anyhow::anyhow;
+use std::env;
+
+/// Replaces `${VAR}` with the value of `VAR` and `${VAR:-default}` with `default` when `VAR`
+/// is unset or empty. `$${` is a literal `${`.
+pub fn interpolate(text: &str) -> Result<String, anyhow::Error> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => return Err(anyhow!("Unclosed `${{` in `{}`", text)),
+            };
+
+            result.push_str(&resolve_variable(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+fn resolve_variable(expression: &str) -> Result<String, anyhow::Error> {
+    let (name, default) = match expression.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (expression, None),
+    };
+
+    if name.is_empty() {
+        return Err(anyhow!("Missing variable name in `${{{}}}`", expression));
+    }
+
+    match (env::var(name), default) {
+        (Ok(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => Ok(default.to_string()),
+        (Err(err), None) => Err(anyhow!(
+            "Failed to get `{}` from environment: {}",
+            name,
+            err
+        )),
+    }
+}
+
+/// Keys whose strings are left as they are, as hook commands are run by a shell that expands
+/// the `PEONY_*` variables of the release.
+const UNINTERPOLATED_KEYS: [&str; 1] = ["hooks"];
+
+/// Interpolates every string in `value` but those under [`UNINTERPOLATED_KEYS`], `key` is the
+/// dotted path used in error messages.
+pub fn interpolate_value(value: &mut toml::Value, key: &str) -> Result<(), anyhow::Error> {
+    match value {
+        toml::Value::String(string) => {
+            *string = match interpolate(string) {
+                Ok(string) => string,
+                Err(err) => return Err(anyhow!("Failed to interpolate `{}`: {}", key, err)),
+            }
+        }
+        toml::Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", key, index))?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                if UNINTERPOLATED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                interpolate_value(value, &key)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_set_variables() {
+        env::set_var("PEONY_TEST_REPO_OWNER", "LilydevMC");
+
+        assert_eq!(
+            interpolate("${PEONY_TEST_REPO_OWNER}/peony").unwrap(),
+            "LilydevMC/peony"
+        );
+        assert_eq!(
+            interpolate("${PEONY_TEST_REPO_OWNER:-fork}").unwrap(),
+            "LilydevMC"
+        );
+    }
+
+    #[test]
+    fn unset_or_empty_variables_use_their_default() {
+        env::set_var("PEONY_TEST_EMPTY_VARIABLE", "");
+
+        assert_eq!(
+            interpolate("${PEONY_TEST_UNSET_VARIABLE:-AABBCCDD}").unwrap(),
+            "AABBCCDD"
+        );
+        assert_eq!(
+            interpolate("${PEONY_TEST_EMPTY_VARIABLE:-AABBCCDD}").unwrap(),
+            "AABBCCDD"
+        );
+        assert_eq!(interpolate("${PEONY_TEST_UNSET_VARIABLE:-}").unwrap(), "");
+    }
+
+    #[test]
+    fn unset_variable_without_default_fails_with_its_name() {
+        let err = interpolate("${PEONY_TEST_UNSET_VARIABLE}")
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            err.starts_with("Failed to get `PEONY_TEST_UNSET_VARIABLE` from environment"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn escaped_and_lone_dollar_signs_are_literal() {
+        assert_eq!(
+            interpolate("$${PEONY_TEST_UNSET_VARIABLE}").unwrap(),
+            "${PEONY_TEST_UNSET_VARIABLE}"
+        );
+        assert_eq!(interpolate("$5 and $HOME").unwrap(), "$5 and $HOME");
+        assert_eq!(interpolate("$$").unwrap(), "$$");
+    }
+
+    #[test]
+    fn malformed_variables_fail() {
+        assert_eq!(
+            interpolate("${PEONY_TEST_REPO_OWNER")
+                .unwrap_err()
+                .to_string(),
+            "Unclosed `${` in `${PEONY_TEST_REPO_OWNER`"
+        );
+        assert_eq!(
+            interpolate("${:-default}").unwrap_err().to_string(),
+            "Missing variable name in `${:-default}`"
+        );
+    }
+
+    #[test]
+    fn interpolates_nested_tables_and_arrays() {
+        env::set_var("PEONY_TEST_NESTED_ROLE", "123456789");
+        let mut value = toml::Value::Table(
+            toml::from_str(
+                r#"
+[discord]
+discord_ping_role = "${PEONY_TEST_NESTED_ROLE}"
+
+[[discord.webhooks]]
+env_var = "${PEONY_TEST_UNSET_VARIABLE:-WEBHOOK_URL}"
+"#,
+            )
+            .unwrap(),
+        );
+
+        interpolate_value(&mut value, "").unwrap();
+
+        assert_eq!(
+            value["discord"]["discord_ping_role"].as_str(),
+            Some("123456789")
+        );
+        assert_eq!(
+            value["discord"]["webhooks"][0]["env_var"].as_str(),
+            Some("WEBHOOK_URL")
+        );
+    }
+
+    #[test]
+    fn hook_commands_are_left_to_the_shell() {
+        let mut value = toml::Value::Table(
+            toml::from_str(
+                r#"
+[hooks]
+post_release = ["echo ${PEONY_VERSION}"]
+"#,
+            )
+            .unwrap(),
+        );
+
+        interpolate_value(&mut value, "").unwrap();
+
+        assert_eq!(
+            value["hooks"]["post_release"][0].as_str(),
+            Some("echo ${PEONY_VERSION}")
+        );
+    }
+
+    #[test]
+    fn errors_name_the_key() {
+        let mut value = toml::Value::Table(
+            toml::from_str(
+                r#"
+[[discord.webhooks]]
+env_var = "${PEONY_TEST_UNSET_VARIABLE}"
+"#,
+            )
+            .unwrap(),
+        );
+
+        let err = interpolate_value(&mut value, "").unwrap_err().to_string();
+        assert!(
+            err.starts_with("Failed to interpolate `discord.webhooks[0].env_var`"),
+            "{}",
+            err
+        );
+    }
+}
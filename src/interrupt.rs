@@ -0,0 +1,80 @@
+use anyhow::anyhow;
+use peony::github::{created_releases, delete_created_release, CreatedRelease};
+use std::future::Future;
+use std::io::{self, IsTerminal};
+use std::process;
+use tracing::warn;
+
+use crate::init::prompt_bool;
+
+/// Exit code of processes stopped by Ctrl-C.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Runs `release` until it finishes or Ctrl-C is pressed. It's dropped when interrupted, which
+/// cancels the requests in flight and cleans up its temporary directory. GitHub releases it
+/// already created are then deleted with `rollback`, or after asking when stdin is a terminal.
+/// A second Ctrl-C exits immediately.
+pub async fn run_interruptible<T>(
+    release: impl Future<Output = Result<T, anyhow::Error>>,
+    rollback: bool,
+) -> Result<T, anyhow::Error> {
+    let mut release = Box::pin(release);
+
+    tokio::select! {
+        res = &mut release => return res,
+        res = tokio::signal::ctrl_c() => {
+            if let Err(err) = res {
+                return Err(anyhow!("Failed to listen for Ctrl-C: {}", err));
+            }
+        }
+    }
+
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Interrupted again, exiting immediately");
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    });
+
+    warn!("Interrupted, cancelling the release...");
+    drop(release);
+
+    for created in created_releases() {
+        roll_back(&created, rollback).await;
+    }
+
+    Err(anyhow!("The release was interrupted"))
+}
+
+async fn roll_back(created: &CreatedRelease, rollback: bool) {
+    let tag_name = &created.release.tag_name;
+
+    let delete = if rollback {
+        true
+    } else if io::stdin().is_terminal() {
+        let question = format!(
+            "GitHub release `{}` was created by this run, delete it?",
+            tag_name
+        );
+
+        match tokio::task::spawn_blocking(move || prompt_bool(&question, false)).await {
+            Ok(Ok(answer)) => answer,
+            _ => false,
+        }
+    } else {
+        false
+    };
+
+    if !delete {
+        warn!(
+            "Left GitHub release `{}` as is, use `--rollback-on-abort` to delete releases of \
+             interrupted runs",
+            tag_name
+        );
+        return;
+    }
+
+    if let Err(err) = delete_created_release(created).await {
+        warn!("Failed to delete GitHub release `{}`: {}", tag_name, err);
+    }
+}
@@ -0,0 +1,48 @@
+//! Builds Minecraft modpacks and mods and distributes them to Modrinth, GitHub and other
+//! platforms.
+//!
+//! [`release_modpack`], [`release_mod`] and [`release_pack`] run the same releases as the
+//! `peony modpack`, `peony mod` and `peony pack` commands. Progress is reported through [`tracing`] events, so nothing is
+//! printed unless a subscriber is installed. Upload progress bars are only drawn once
+//! [`progress::enable`] is called.
+
+pub mod actions;
+pub mod changelog;
+pub mod checksum;
+pub mod config_check;
+pub mod credentials;
+pub mod curseforge;
+pub mod diff;
+pub mod discord;
+pub mod email;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+pub mod hooks;
+pub mod interpolate;
+pub mod mastodon;
+pub mod matrix;
+pub mod mc_mod;
+pub mod mc_pack;
+pub mod mod_list;
+pub mod models;
+pub mod modrinth;
+pub mod notify;
+pub mod outdated;
+pub mod pack;
+pub mod packwiz;
+pub mod progress;
+pub mod redact;
+pub mod release;
+pub mod schema;
+pub mod slack;
+#[cfg(test)]
+mod test_support;
+pub mod util;
+pub mod validate;
+pub mod version;
+
+pub use release::{
+    notify_modpack_release, release_mod, release_modpack, release_pack, ModReleaseOptions,
+    NotifyOptions, PackReleaseOptions, ReleaseOptions,
+};
@@ -0,0 +1,87 @@
+use peony::progress;
+use peony::redact::redact;
+use std::env;
+use std::fmt::Write;
+use std::io::{self, IsTerminal};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::{format, FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Prints events as plain lines prefixed with their spans, like `github: Creating GitHub
+/// release...`. Registered secrets are masked, since CI logs are often public.
+struct RedactingFormat;
+
+impl<S, N> FormatEvent<S, N> for RedactingFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        let mut line = String::new();
+
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                write!(line, "{}: ", span.name())?;
+            }
+        }
+
+        match *event.metadata().level() {
+            Level::ERROR => line.push_str("Error: "),
+            Level::WARN => line.push_str("Warning: "),
+            Level::INFO => (),
+            Level::DEBUG => line.push_str("[debug] "),
+            Level::TRACE => line.push_str("[trace] "),
+        }
+
+        ctx.field_format()
+            .format_fields(format::Writer::new(&mut line), event)?;
+
+        writeln!(writer, "{}", redact(&line))
+    }
+}
+
+/// Writes to stdout with the progress bars hidden, so that lines aren't drawn over them.
+struct SuspendingWriter;
+
+impl io::Write for SuspendingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        progress::suspend(|| io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+/// `RUST_LOG` takes precedence over `--verbose` and `--quiet`. Only peony's own events get
+/// more verbose with `-v`, `-vv` also shows those of the HTTP client.
+pub fn init_logging(verbose: u8, quiet: bool) {
+    let default_filter = match (quiet, verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "info,peony=debug",
+        (false, _) => "trace",
+    };
+
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => EnvFilter::new(default_filter),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .event_format(RedactingFormat)
+        .with_writer(|| SuspendingWriter)
+        .init();
+
+    // CI logs have no use for redrawn bars, and `NO_COLOR` asks for plain output
+    if !quiet && env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal() {
+        progress::enable();
+    }
+}
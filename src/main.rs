@@ -1,481 +1,916 @@
-use std::io::Read;
 use std::{env, fs};
 
 use anyhow::anyhow;
-use clap::{command, Parser, Subcommand};
-use glob::glob;
-use std::path::{Path, PathBuf};
-use std::process::Command;
-
-use crate::discord::send_discord_webhook;
-use crate::models::project_type::mc_mod::version::ModVersionInfo;
-use crate::models::project_type::mc_mod::{Jar, ModInfo, ModJars};
-use crate::{
-    github::generate_changelog,
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::warn;
+
+use peony::changelog::{generate_modpack_changelog, read_changelog_override, render_changelog};
+#[cfg(feature = "keyring")]
+use peony::credentials::{credential_env_var, remove_credential, store_credential};
+use peony::diff::{diff_markdown, diff_mrpacks};
+use peony::models::version::BumpLevel;
+use peony::outdated::{find_outdated_mods, outdated_table};
+
+use crate::interrupt::run_interruptible;
+use peony::{
+    actions,
     models::{
-        modrinth::ModrinthUrl,
-        project_type::{mc_mod::config::ModConfig, modpack::config::ModpackConfig},
+        diff::DiffFormat,
+        manifest::{GithubManifest, ModrinthManifest, ReleaseManifest, MANIFEST_VERSION},
+        modrinth::{version::VersionType, ModrinthUrl},
+        project_type::{
+            mc_mod::config::ModConfig, modpack::config::ModpackConfig, pack::config::PackConfig,
+        },
+        release::Platform,
+        ChangelogFormat, HookFailure,
     },
+    notify_modpack_release,
     pack::*,
+    redact::redact,
+    release_mod, release_modpack, release_pack,
+    schema::{config_schema, ConfigKind},
     util::*,
-    version::*,
+    validate, ModReleaseOptions, NotifyOptions, PackReleaseOptions, ReleaseOptions,
 };
-use crate::models::modrinth::version::VersionType;
 
-mod discord;
-mod github;
-mod mc_mod;
-mod models;
-mod modrinth;
-mod pack;
-mod util;
-mod version;
+mod init;
+mod interrupt;
+mod logging;
 
 #[derive(Debug, Parser)]
 #[command(name = "peony", author, version, about)]
 struct CliArgs {
     #[command(subcommand)]
     commands: Commands,
+    #[clap(
+        long,
+        short,
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "Log more detail, repeat for even more (`-vv`)"
+    )]
+    verbose: u8,
+    #[clap(
+        long,
+        short,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only log warnings and errors"
+    )]
+    quiet: bool,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
+    #[command(about = "Create a new `mrpack.toml` config for a Packwiz modpack")]
+    Init {
+        #[clap(long, help = "GitHub repository owner")]
+        github_owner: Option<String>,
+        #[clap(long, help = "GitHub repository name")]
+        github_repo: Option<String>,
+        #[clap(long, help = "Modrinth project ID")]
+        modrinth_project_id: Option<String>,
+        #[clap(long, help = "Whether or not to include a Discord section")]
+        discord: Option<bool>,
+        #[clap(long, short, help = "Overwrite an existing config file")]
+        force: bool,
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+    },
+    #[command(about = "Check the modpack config and environment before releasing")]
+    Validate {
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+    },
+    #[command(about = "Send the notifications of a modpack release that already happened")]
+    Notify {
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(long, help = "Version number of the release, as in `pack.toml`")]
+        version: String,
+        #[clap(long, help = "Log the notifications instead of sending them")]
+        dry_run: bool,
+    },
+    #[command(about = "Preview the changelog of the next modpack release")]
+    Changelog {
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(
+            long,
+            short,
+            help = "Write the changelog to this file instead of stdout"
+        )]
+        out: Option<PathBuf>,
+        #[clap(
+            long,
+            value_enum,
+            default_value = "github",
+            help = "Platform to render the changelog for"
+        )]
+        format: ChangelogFormat,
+    },
+    #[command(about = "Compare the mods and overrides of two .mrpack files")]
+    Diff {
+        #[clap(help = "The older .mrpack")]
+        old: PathBuf,
+        #[clap(help = "The newer .mrpack")]
+        new: PathBuf,
+        #[clap(long, value_enum, default_value = "markdown", help = "Output format")]
+        format: DiffFormat,
+    },
+    #[command(
+        hide = true,
+        about = "Print the JSON Schema of a config file, for editors to complete its keys"
+    )]
+    Schema {
+        #[clap(
+            long,
+            value_enum,
+            default_value = "modpack",
+            help = "Config file to describe"
+        )]
+        kind: ConfigKind,
+    },
+    #[command(about = "List the mods of the pack that have newer versions on Modrinth")]
+    Outdated {
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(long, help = "Print the outdated mods as JSON")]
+        json: bool,
+    },
     #[command(about = "Export and upload a Packwiz modpack")]
     Modpack {
         #[clap(long, short, help = "Whether or not to send Discord webhook")]
         discord: bool,
-        #[clap(long, short, help = "Custom version number")]
+        #[clap(long, help = "Whether or not to send Slack webhook")]
+        slack: bool,
+        #[clap(
+            long,
+            help = "Post to the Matrix room, even if `matrix.enabled` isn't set"
+        )]
+        matrix: bool,
+        #[clap(long, help = "Custom version number")]
         version: Option<String>,
-        #[clap(long, short = 'V', help = "Version type (used for Modrinth & GitHub releases)")]
-        version_type: Option<VersionType>
+        #[clap(
+            long,
+            value_enum,
+            conflicts_with = "version",
+            help = "Bump the semver version from pack.toml"
+        )]
+        bump: Option<BumpLevel>,
+        #[clap(
+            long,
+            requires = "bump",
+            help = "Also write the bumped version to the project's pack.toml"
+        )]
+        persist_bump: bool,
+        #[clap(
+            long,
+            short = 'V',
+            visible_alias = "channel",
+            help = "Version type (used for Modrinth & GitHub releases)"
+        )]
+        version_type: Option<VersionType>,
+        #[clap(
+            long,
+            help = "Print what would be released without creating releases or sending webhooks"
+        )]
+        dry_run: bool,
+        #[clap(long, help = "Don't create a GitHub release")]
+        skip_github: bool,
+        #[clap(long, help = "Don't create a GitLab release")]
+        skip_gitlab: bool,
+        #[clap(long, help = "Don't create a Gitea release")]
+        skip_gitea: bool,
+        #[clap(long, help = "Don't upload a version to Modrinth")]
+        skip_modrinth: bool,
+        #[clap(long, help = "Don't upload a file to CurseForge")]
+        skip_curseforge: bool,
+        #[clap(
+            long,
+            help = "Exit successfully even if releasing to some platforms failed"
+        )]
+        allow_partial: bool,
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "mrpack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(long, short, help = "Release even if the version already exists")]
+        force: bool,
+        #[clap(
+            long,
+            conflicts_with = "force",
+            help = "Only release to the platforms that don't have this version yet"
+        )]
+        resume: bool,
+        #[clap(long, help = "Keep the temporary directory for debugging")]
+        no_cleanup: bool,
+        #[clap(
+            long,
+            help = "Where to write the JSON manifest of the release",
+            default_value = "peony-release.json"
+        )]
+        manifest_out: PathBuf,
+        #[clap(
+            long,
+            help = "Write GitHub Actions step outputs and a step summary, detected from `GITHUB_ACTIONS` by default"
+        )]
+        gha: bool,
+        #[clap(
+            long,
+            conflicts_with = "changelog_file",
+            help = "Use this changelog instead of generating one"
+        )]
+        changelog: Option<String>,
+        #[clap(
+            long,
+            help = "Read the changelog from this file instead of generating one"
+        )]
+        changelog_file: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Open the changelog in $VISUAL or $EDITOR before releasing, skipped without a terminal"
+        )]
+        edit: bool,
+        #[clap(
+            long,
+            help = "Name of the exported .mrpack, overrides `output_filename`"
+        )]
+        output_file: Option<String>,
+        #[clap(
+            long,
+            help = "Use the newest .mrpack if the export directory contains several"
+        )]
+        latest: bool,
+        #[clap(
+            long,
+            help = "Export in the project directory instead of a temporary copy"
+        )]
+        in_place: bool,
+        #[clap(
+            long,
+            help = "Delete the GitHub release of this run without asking when interrupted"
+        )]
+        rollback_on_abort: bool,
     },
-    #[command(about = "Build and upload a Fabric/Quilt mod")]
+    #[command(about = "Build and upload a Fabric, Quilt, Forge or NeoForge mod")]
     Mod {
         #[clap(long, short, help = "Whether or not to send Discord webhook")]
         discord: bool,
-        #[clap(long, short, help = "Args to pass to Gradle", default_value = "build")]
-        gradle_args: String,
-        #[clap(long, short = 'V', help = "Version type (used for Modrinth & GitHub releases)")]
-        version_type: Option<VersionType>
+        #[clap(long, help = "Whether or not to send Slack webhook")]
+        slack: bool,
+        #[clap(
+            long,
+            help = "Post to the Matrix room, even if `matrix.enabled` isn't set"
+        )]
+        matrix: bool,
+        #[clap(
+            long = "gradle-arg",
+            short,
+            visible_alias = "gradle-args",
+            help = "Arg to pass to Gradle, can be repeated",
+            default_value = "build"
+        )]
+        gradle_args: Vec<String>,
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "peony_mod.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(
+            long,
+            short = 'V',
+            visible_alias = "channel",
+            help = "Version type (used for Modrinth & GitHub releases)"
+        )]
+        version_type: Option<VersionType>,
+        #[clap(
+            long,
+            help = "Print what would be released without creating releases or sending webhooks"
+        )]
+        dry_run: bool,
+        #[clap(long, help = "Don't create a GitHub release")]
+        skip_github: bool,
+        #[clap(long, help = "Don't upload a version to Modrinth")]
+        skip_modrinth: bool,
+        #[clap(long, help = "Don't upload a file to CurseForge")]
+        skip_curseforge: bool,
+        #[clap(
+            long,
+            help = "Exit successfully even if releasing to some platforms failed"
+        )]
+        allow_partial: bool,
+        #[clap(
+            long,
+            help = "Only release to the platforms that don't have this version yet"
+        )]
+        resume: bool,
+        #[clap(long, help = "Keep the temporary directory for debugging")]
+        no_cleanup: bool,
+        #[clap(
+            long,
+            conflicts_with = "changelog_file",
+            help = "Use this changelog instead of generating one"
+        )]
+        changelog: Option<String>,
+        #[clap(
+            long,
+            help = "Read the changelog from this file instead of generating one"
+        )]
+        changelog_file: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Open the changelog in $VISUAL or $EDITOR before releasing, skipped without a terminal"
+        )]
+        edit: bool,
+        #[clap(
+            long,
+            help = "Build in the project directory instead of a temporary copy, keeping Gradle's caches"
+        )]
+        in_place: bool,
+        #[clap(
+            long,
+            help = "Don't run the `verify_tasks` of the config before building"
+        )]
+        skip_verify: bool,
+        #[clap(
+            long,
+            help = "Delete the GitHub release of this run without asking when interrupted"
+        )]
+        rollback_on_abort: bool,
+    },
+    #[command(about = "Zip and upload a resource pack or data pack")]
+    Pack {
+        #[clap(long, short, help = "Whether or not to send Discord webhook")]
+        discord: bool,
+        #[clap(
+            long,
+            short,
+            help = "Path to config file",
+            default_value = "peony_pack.toml"
+        )]
+        config: PathBuf,
+        #[clap(long, help = "Merge this `[profile.<name>]` table into the config")]
+        profile: Option<String>,
+        #[clap(long, help = "Version to release instead of the config's `version`")]
+        version: Option<String>,
+        #[clap(
+            long,
+            short = 'V',
+            visible_alias = "channel",
+            help = "Version type (used for Modrinth releases)"
+        )]
+        version_type: Option<VersionType>,
+        #[clap(
+            long,
+            conflicts_with = "changelog_file",
+            help = "Use this changelog instead of generating one"
+        )]
+        changelog: Option<String>,
+        #[clap(
+            long,
+            help = "Read the changelog from this file instead of generating one"
+        )]
+        changelog_file: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Open the changelog in $VISUAL or $EDITOR before releasing, skipped without a terminal"
+        )]
+        edit: bool,
+        #[clap(long, help = "Only zip the pack, without uploading it to Modrinth")]
+        skip_modrinth: bool,
+    },
+    #[cfg(feature = "keyring")]
+    #[command(about = "Store tokens and webhook URLs in the OS keyring")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Debug, Subcommand)]
+enum AuthAction {
+    #[command(about = "Prompt for a token and store it in the keyring")]
+    Set {
+        #[clap(
+            help = "`github`, `gitlab`, `gitea`, `modrinth`, `curseforge`, `discord`, `slack`, `matrix`, `mastodon` or `smtp`, or the environment variable the token would be read from"
+        )]
+        name: String,
+    },
+    #[command(about = "Remove a token from the keyring")]
+    Remove {
+        #[clap(
+            help = "`github`, `gitlab`, `gitea`, `modrinth`, `curseforge`, `discord`, `slack`, `matrix`, `mastodon` or `smtp`, or the environment variable the token would be read from"
+        )]
+        name: String,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<(), anyhow::Error> {
+async fn main() -> ExitCode {
     let _ = dotenvy::dotenv();
 
-    let args = CliArgs::parse();
+    let (raw_args, deprecated_version_flag) = rewrite_deprecated_version_flag(env::args_os());
+    let args = CliArgs::parse_from(raw_args);
 
-    match args.commands {
-        Commands::Modpack { discord, version, version_type } => {
-            match which::which("packwiz") {
-                Ok(_) => (),
-                Err(err) => return Err(anyhow!("Failed to find packwiz executable: {}", err)),
-            }
-
-            if !Path::new("mrpack.toml").exists() {
-                return Err(anyhow!("Failed to find `mrpack.toml` file."));
-            }
+    logging::init_logging(args.verbose, args.quiet);
 
-            let config_file = match fs::read_to_string("mrpack.toml") {
-                Ok(content_string) => {
-                    let parsed_config: ModpackConfig = match toml::from_str(&content_string) {
-                        Ok(config) => config,
-                        Err(err) => return Err(anyhow!("Failed to parse config file: {}", err)),
-                    };
-                    parsed_config
-                }
-                Err(err) => return Err(anyhow!("Failed to read config file: {}", err)),
-            };
-
-            let mut pack_file = match get_pack_file() {
-                Ok(file) => file,
-                Err(err) => return Err(err),
-            };
+    if deprecated_version_flag {
+        warn!("`modpack -v <version>` is deprecated, `-v` means `--verbose`, use `--version`");
+    }
 
-            let tmp_info = match create_temp() {
-                Ok(info) => info,
-                Err(err) => return Err(err),
-            };
+    // Errors can contain request URLs with tokens in them, which would end up in CI logs
+    match run(args).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", redact(&format!("{:?}", err)));
+            ExitCode::FAILURE
+        }
+    }
+}
 
-            if let Some(ver) = version {
-                let mut new_file_contents = pack_file.clone();
-                new_file_contents.version = ver;
-                let file_contents_string = match toml::to_string(&new_file_contents) {
-                    Ok(file) => file,
-                    Err(err) => {
-                        return Err(anyhow!("Failed to parse new pack data to toml: {}", err))
-                    }
-                };
+/// `modpack -v <version>` predates the global `-v` for verbosity. As `modpack` takes no
+/// positional args, a `-v` followed by a value can only be the old flag, so it's rewritten to
+/// `--version`. Returns whether it was.
+fn rewrite_deprecated_version_flag(args: impl Iterator<Item = OsString>) -> (Vec<OsString>, bool) {
+    let mut args = args.collect::<Vec<OsString>>();
+    let modpack = match args.iter().position(|arg| arg == "modpack") {
+        Some(index) => index,
+        None => return (args, false),
+    };
+
+    let mut rewritten = false;
+    for index in modpack + 1..args.len() {
+        let takes_value = args
+            .get(index + 1)
+            .is_some_and(|next| !next.to_string_lossy().starts_with('-'));
+
+        if args[index] == "-v" && takes_value {
+            args[index] = OsString::from("--version");
+            rewritten = true;
+        }
+    }
 
-                pack_file = new_file_contents;
+    (args, rewritten)
+}
 
-                write_pack_file(&tmp_info.dir_path, file_contents_string)?
+async fn run(args: CliArgs) -> Result<(), anyhow::Error> {
+    match args.commands {
+        Commands::Init {
+            github_owner,
+            github_repo,
+            modrinth_project_id,
+            discord,
+            force,
+            config,
+        } => {
+            if config.exists() && !force {
+                return Err(anyhow!(
+                    "Config file `{}` already exists, use `--force` to overwrite it",
+                    config.display()
+                ));
             }
 
-            match Command::new("packwiz")
-                .arg("mr")
-                .arg("export")
-                .current_dir(&tmp_info.dir_path)
-                .output()
-            {
-                Ok(_) => (),
-                Err(err) => return Err(anyhow!("Failed to export with packwiz: {}", err)),
+            let project_dir = project_dir_from_config(&config);
+            let pack_name = init::pack_name_from_dir(&project_dir);
+
+            if let Some(name) = &pack_name {
+                println!("Found pack.toml for `{}`", name);
             }
 
-            let output_file_info = match get_output_file(&tmp_info) {
-                Ok(file_info) => file_info,
-                Err(err) => return Err(err),
+            let init_options = init::InitOptions {
+                pack_name,
+                repo_owner: match github_owner {
+                    Some(owner) => owner,
+                    None => init::prompt("GitHub repository owner", None)?,
+                },
+                repo_name: match github_repo {
+                    Some(repo) => repo,
+                    None => init::prompt("GitHub repository name", None)?,
+                },
+                modrinth_project_id: match modrinth_project_id {
+                    Some(id) => id,
+                    None => init::prompt("Modrinth project ID", None)?,
+                },
+                discord: match discord {
+                    Some(discord) => discord,
+                    None => init::prompt_bool("Send Discord webhooks?", false)?,
+                },
             };
 
-            let version_info =
-                match get_modpack_version_info(&config_file, &pack_file, &output_file_info) {
-                    Ok(info) => info,
-                    Err(err) => return Err(err),
-                };
+            init::write_modpack_config(&config, &init_options)?;
 
-            // Changelog
+            println!("Created config file at `{}`!", config.display());
+        }
+        Commands::Notify {
+            config,
+            profile,
+            version,
+            dry_run,
+        } => {
+            let config_file: ModpackConfig = read_config(&config, profile.as_deref())?;
 
-            let changelog_markdown = match generate_changelog(&config_file.github).await {
-                Ok(changelog) => changelog,
-                Err(err) => return Err(err),
-            };
+            let summary =
+                notify_modpack_release(config_file, NotifyOptions { version, dry_run }).await?;
 
-            // GitHub Release
+            summary.print();
 
-            match github::create_modpack_release(
-                &config_file,
-                &pack_file,
-                &output_file_info,
-                &version_info,
-                &changelog_markdown,
-                match version_type.clone() {
-                    Some(ver_type) => ver_type,
-                    None => VersionType::Release
-                }
-            )
-            .await
-            {
-                Ok(_) => (),
-                Err(err) => println!("Failed to create GitHub release: {}", err),
+            if !summary.webhook_failures().is_empty() {
+                return Err(anyhow!("Failed to send some notifications"));
             }
+        }
+        Commands::Validate { config, profile } => {
+            let checks = validate::validate_modpack(&config, profile.as_deref()).await;
 
-            // Modrinth Release
+            validate::print_checks(&checks);
 
-            let modrinth_token = match env::var("MODRINTH_TOKEN") {
-                Ok(token) => token,
-                Err(err) => return Err(anyhow!("Failed to get `MODRINTH_TOKEN`: {}", err)),
-            };
+            let failed_count = checks
+                .iter()
+                .filter(|check| check.required && check.result.is_err())
+                .count();
+
+            if failed_count > 0 {
+                return Err(anyhow!("{} required check(s) failed", failed_count));
+            }
 
-            let modrinth_url = ModrinthUrl::new(&config_file.modrinth.staging);
+            println!("All required checks passed!");
+        }
+        Commands::Changelog {
+            config,
+            profile,
+            out,
+            format,
+        } => {
+            let config_file: ModpackConfig = read_config(&config, profile.as_deref())?;
+            let project_dir = project_dir_from_config(&config);
+            let network = config_file.project.network.clone().unwrap_or_default();
 
-            match modrinth::create_modpack_release(
+            let pack_file = get_pack_file(&project_dir)?;
+            let modrinth_url = config_file.modrinth_url();
+
+            let changelog = generate_modpack_changelog(
                 &config_file,
+                &project_dir,
                 &pack_file,
-                &output_file_info,
-                &version_info,
-                &changelog_markdown,
-                modrinth_token.clone(),
                 &modrinth_url,
-                match version_type {
-                    Some(ver_type) => ver_type,
-                    None => VersionType::Release
-                }
+                None,
+                &network,
             )
-            .await
-            {
-                Ok(_) => (),
-                Err(err) => println!("{}", err),
-            }
-
-            // Send Discord webhook
+            .await?;
 
-            if discord {
-                let discord_config = match config_file.discord {
-                    Some(config) => config,
-                    None => return Err(anyhow!("Failed to get Discord config")),
-                };
+            let changelog_markdown =
+                render_changelog(&changelog, format, config_file.forge(), &pack_file);
 
-                match send_discord_webhook(
-                    &discord_config,
-                    &modrinth_url,
-                    &config_file.modrinth.project_id,
-                    &config_file.github,
-                    &version_info.version_name,
-                    &changelog_markdown,
-                )
-                .await
-                {
-                    Ok(_) => {
-                        println!("Sent Discord webhook!")
+            match out {
+                Some(path) => match fs::write(&path, changelog_markdown) {
+                    Ok(_) => println!("Wrote changelog to `{}`", path.display()),
+                    Err(err) => {
+                        return Err(anyhow!(
+                            "Failed to write changelog to `{}`: {}",
+                            path.display(),
+                            err
+                        ))
                     }
-                    Err(err) => return Err(err),
-                }
+                },
+                None => println!("\n{}", changelog_markdown),
             }
-
-            clean_up(&tmp_info.dir_path)?
         }
-        Commands::Mod {
-            discord,
-            gradle_args,
-            version_type
-        } => {
-            match which::which("java") {
-                Ok(_) => (),
-                Err(err) => return Err(anyhow!("Failed to find Java executable: {}", err)),
-            }
-
-            let gradlew_path: &Path = if env::consts::OS == "windows" {
-                Path::new(".\\gradlew.bat")
-            } else {
-                Path::new("./gradlew")
-            };
-
-            if !Path::new(gradlew_path).exists() {
-                return Err(anyhow!(
-                    "Failed to find gradle script at `{:?}`",
-                    gradlew_path
-                ));
-            }
-
-            if !Path::new("peony_mod.toml").exists() {
-                return Err(anyhow!("Failed to find `peony_mod.toml` file"));
-            }
-
-            let config_file = match fs::read_to_string("peony_mod.toml") {
-                Ok(content_string) => {
-                    let parsed_config: ModConfig = match toml::from_str(&content_string) {
-                        Ok(config) => config,
-                        Err(err) => return Err(anyhow!("Failed to parse config file: {}", err)),
-                    };
-                    parsed_config
-                }
-                Err(err) => return Err(anyhow!("Failed to read config file: {}", err)),
-            };
-
-            let tmp_info = match create_temp() {
-                Ok(info) => info,
-                Err(err) => return Err(anyhow!("Failed to create temporary directory: {}", err)),
-            };
-
-            // remove previously-compiled jars, if any
-            let _ = fs::remove_dir(&tmp_info.dir_path.join("build").join("libs"));
-
-            let mut gradle_command = Command::new(gradlew_path);
-
-            let gradle_command = gradle_command
-                .arg(gradle_args)
-                .current_dir(&tmp_info.dir_path);
-
-            let mut gradle_child = match gradle_command.spawn() {
-                Ok(child) => child,
-                Err(err) => return Err(anyhow!("Failed to build with Gradle: {}", err)),
-            };
-
-            gradle_child.wait().unwrap();
-
-            let jars = match glob(
-                match Path::new(&tmp_info.dir_path)
-                    .join("build")
-                    .join("libs")
-                    .join("*.jar")
-                    .to_str()
-                {
-                    Some(path) => path,
-                    None => return Err(anyhow!("Failed to parse glob to string")),
+        Commands::Diff { old, new, format } => {
+            let modrinth_url = ModrinthUrl::new(&None);
+            let diff = diff_mrpacks(&old, &new, &modrinth_url, &Default::default()).await?;
+
+            match format {
+                DiffFormat::Markdown => println!("{}", diff_markdown(&diff, &modrinth_url)),
+                DiffFormat::Json => match serde_json::to_string_pretty(&diff) {
+                    Ok(json) => println!("{}", json),
+                    Err(err) => return Err(anyhow!("Failed to serialize diff: {}", err)),
                 },
-            ) {
-                Ok(paths) => paths,
-                Err(err) => return Err(anyhow!("Failed to find files with mod glob: {}", err)),
-            };
-
-            let mut possible_jars: Vec<PathBuf> = vec![];
-            let mut possible_sources_jars: Vec<PathBuf> = vec![];
-
-            for jar in jars {
-                let jar_path = match jar {
-                    Ok(path) => path,
-                    Err(err) => {
-                        return Err(anyhow!("Failed to parse jar glob result as path: {}", err))
-                    }
-                };
+            }
+        }
+        Commands::Schema { kind } => println!("{}", config_schema(kind)?),
+        Commands::Outdated {
+            config,
+            profile,
+            json,
+        } => {
+            let config_file: ModpackConfig = read_config(&config, profile.as_deref())?;
+            let project_dir = project_dir_from_config(&config);
+            let network = config_file.project.network.clone().unwrap_or_default();
+            let pack_file = get_pack_file(&project_dir)?;
 
-                let file_name = match jar_path.file_name() {
-                    Some(name) => match name.to_os_string().into_string() {
-                        Ok(name) => name,
-                        // better error message needed, but fine for now
-                        Err(_) => {
-                            return Err(anyhow!(
-                                "Failed to parse file name from OsString to String"
-                            ))
-                        }
-                    },
-                    None => return Err(anyhow!("Failed to parse file name from jar path")),
-                };
+            let outdated = find_outdated_mods(
+                &project_dir,
+                &pack_file,
+                &config_file.modrinth_url(),
+                &network,
+            )
+            .await?;
 
-                if file_name.ends_with("-sources.jar") {
-                    possible_sources_jars.push(jar_path)
-                } else if file_name.ends_with(".jar") {
-                    possible_jars.push(jar_path)
+            if json {
+                match serde_json::to_string_pretty(&outdated) {
+                    Ok(json) => println!("{}", json),
+                    Err(err) => return Err(anyhow!("Failed to serialize outdated mods: {}", err)),
                 }
+            } else if !outdated.is_empty() {
+                println!("{}", outdated_table(&outdated));
             }
 
-            if possible_jars.len() != 1 {
-                clean_up(&tmp_info.dir_path)?;
-                return Err(anyhow!(
-                    "Found an invalid amount of mod jars: {}",
-                    possible_jars.len()
-                ));
+            // Lets a scheduled job tell that there's something to update
+            if !outdated.is_empty() {
+                return Err(anyhow!("{} mod(s) are outdated", outdated.len()));
             }
 
-            let jar_path = &possible_jars[0];
-            let sources_jar_path: Option<&PathBuf> = match possible_sources_jars.len() {
-                0 => None,
-                1 => Some(&possible_sources_jars[0]),
-                _ => {
-                    return Err(anyhow!(
-                        "Found an invalid amount of sources jars: {}",
-                        possible_sources_jars.len()
-                    ))
-                }
+            if !json {
+                println!("All mods are up to date!");
+            }
+        }
+        Commands::Modpack {
+            discord,
+            slack,
+            matrix,
+            version,
+            bump,
+            persist_bump,
+            version_type,
+            dry_run,
+            skip_github,
+            skip_gitlab,
+            skip_gitea,
+            skip_modrinth,
+            skip_curseforge,
+            allow_partial,
+            config,
+            profile,
+            force,
+            resume,
+            no_cleanup,
+            manifest_out,
+            gha,
+            changelog,
+            changelog_file,
+            edit,
+            output_file,
+            latest,
+            in_place,
+            rollback_on_abort,
+        } => {
+            let started_at = Utc::now();
+
+            let config_file: ModpackConfig = read_config(&config, profile.as_deref())?;
+            let project_dir = project_dir_from_config(&config);
+
+            let options = ReleaseOptions {
+                project_dir: project_dir.clone(),
+                discord,
+                slack,
+                matrix,
+                version,
+                bump,
+                version_type,
+                dry_run,
+                skip_github,
+                skip_gitlab,
+                skip_gitea,
+                skip_modrinth,
+                skip_curseforge,
+                force,
+                resume,
+                // packwiz output is always shown when verbose
+                verbose: args.verbose > 0,
+                no_cleanup,
+                changelog: read_changelog_override(changelog, changelog_file)?,
+                edit,
+                output_file,
+                latest,
+                in_place,
             };
 
-            let jar_file = fs::File::open(jar_path)?;
+            let summary = run_interruptible(
+                release_modpack(config_file.clone(), options),
+                rollback_on_abort,
+            )
+            .await?;
 
-            let mut archive = zip::ZipArchive::new(jar_file)?;
+            summary.print();
 
-            let mut loader_file = if file_exists_in_zip(&mut archive, "fabric.mod.json") {
-                match archive.by_name("fabric.mod.json") {
-                    Ok(file) => file,
-                    Err(err) => {
-                        return Err(anyhow!(
-                            "Failed to get `fabric.mod.json` file from jar: {}",
-                            err
-                        ))
+            // Failing to report to Actions shouldn't fail a release that already happened
+            if gha || actions::is_github_actions() {
+                if env::var("GITHUB_OUTPUT").is_ok() {
+                    if let Err(err) = actions::write_outputs(&summary) {
+                        warn!("Failed to write GitHub Actions outputs: {}", err);
                     }
                 }
-            } else if file_exists_in_zip(&mut archive, "quilt.mod.json") {
-                match archive.by_name("quilt.mod.json") {
-                    Ok(file) => file,
-                    Err(err) => {
-                        return Err(anyhow!(
-                            "Failed to get `quilt.mod.json` file from jar: {}",
-                            err
-                        ))
+                if env::var("GITHUB_STEP_SUMMARY").is_ok() {
+                    if let Err(err) = actions::write_step_summary(&summary) {
+                        warn!("Failed to write GitHub Actions step summary: {}", err);
                     }
                 }
-            } else {
-                return Err(anyhow!(
-                    "Failed to get `fabric.mod.json` or `quilt.mod.json` from jar"
-                ));
-            };
-
-            let mut loader_file_string = String::new();
-            loader_file.read_to_string(&mut loader_file_string)?;
-
-            let parsed_loader_file: serde_json::Value = serde_json::from_str(&loader_file_string)?;
+            }
 
-            let mod_info = ModInfo {
-                name: trim_quotes(parsed_loader_file["name"].to_string()),
-                id: trim_quotes(parsed_loader_file["id"].to_string()),
-                version: trim_quotes(parsed_loader_file["version"].to_string()),
-            };
+            summary.check(allow_partial)?;
+
+            if !dry_run {
+                let manifest = ReleaseManifest {
+                    manifest_version: MANIFEST_VERSION,
+                    version_number: summary.version_number.clone(),
+                    version_name: summary.version_name.clone(),
+                    file_name: summary.file_name.clone().unwrap_or_default(),
+                    sha512: summary.sha512.clone().unwrap_or_default(),
+                    github: summary.url(Platform::Github).zip(config_file.github()).map(
+                        |(url, github_config)| GithubManifest {
+                            url: url.clone(),
+                            tag_name: github_config.tag_name(&summary.version_number),
+                        },
+                    ),
+                    modrinth: summary.url(Platform::Modrinth).map(|url| ModrinthManifest {
+                        version_id: url
+                            .split_once("/version/")
+                            .map(|(_, version_id)| version_id.to_string()),
+                        url: url.clone(),
+                    }),
+                    started_at: started_at.to_rfc3339(),
+                    finished_at: Utc::now().to_rfc3339(),
+                    changelog: summary.changelog.render_github(),
+                };
 
-            let mod_jar_name = file_name_from_path(jar_path)?;
+                write_json_file(&manifest_out, &manifest)?;
+                println!("Wrote release manifest to `{}`", manifest_out.display());
+            }
 
-            let sources_jar_name = match sources_jar_path {
-                Some(path) => Some(file_name_from_path(path)?),
-                None => None,
-            };
+            // Only persisted once released, so that a failed release can be retried as is
+            if persist_bump && !dry_run {
+                set_pack_version(&project_dir, &summary.version_number)?;
+                println!("Wrote version {} to pack.toml", summary.version_number);
+            }
 
-            let mod_jar = Jar {
-                file_name: mod_jar_name,
-                file_path: jar_path.into(),
+            let fail_on_hooks = config_file
+                .project
+                .hooks
+                .as_ref()
+                .is_some_and(|hooks| hooks.post_release_failure() == HookFailure::Fail);
+            if !summary.hook_failures.is_empty() && fail_on_hooks {
+                return Err(anyhow!("Some post_release hooks failed"));
+            }
+        }
+        Commands::Mod {
+            discord,
+            slack,
+            matrix,
+            gradle_args,
+            version_type,
+            dry_run,
+            skip_github,
+            skip_modrinth,
+            skip_curseforge,
+            allow_partial,
+            resume,
+            config,
+            profile,
+            no_cleanup,
+            changelog,
+            changelog_file,
+            edit,
+            in_place,
+            skip_verify,
+            rollback_on_abort,
+        } => {
+            let config_file: ModConfig = read_config(&config, profile.as_deref())?;
+
+            let options = ModReleaseOptions {
+                project_dir: project_dir_from_config(&config),
+                discord,
+                slack,
+                matrix,
+                gradle_args,
+                version_type,
+                dry_run,
+                skip_github,
+                skip_modrinth,
+                skip_curseforge,
+                resume,
+                no_cleanup,
+                changelog: read_changelog_override(changelog, changelog_file)?,
+                edit,
+                in_place,
+                skip_verify,
             };
 
-            let sources_jar = sources_jar_name.map(|name| Jar {
-                file_name: name,
-                file_path: sources_jar_path.unwrap().into(),
-            });
+            let fail_on_hooks = config_file
+                .project
+                .hooks
+                .as_ref()
+                .is_some_and(|hooks| hooks.post_release_failure() == HookFailure::Fail);
 
-            let mod_jars = ModJars {
-                mod_jar,
-                sources_jar,
-            };
+            let summary =
+                run_interruptible(release_mod(config_file, options), rollback_on_abort).await?;
 
-            let version_info = ModVersionInfo::new(&config_file, &mod_jars, &mod_info)?;
-
-            // Generate changelog from previous GitHub Releases
-            let changelog_markdown = match generate_changelog(&config_file.github).await {
-                Ok(changelog) => changelog,
-                Err(err) => return Err(err),
-            };
+            summary.print();
 
-            // Create GitHub Release
+            summary.check(allow_partial)?;
 
-            match github::create_mod_release(
-                &config_file,
-                &mod_info,
-                &mod_jars,
-                &changelog_markdown,
-                &version_info.name,
-                match version_type.clone() {
-                    Some(ver_type) => ver_type,
-                    None => VersionType::Release
-                }
-            )
-            .await
-            {
-                Ok(_) => {}
-                Err(err) => return Err(err),
+            if !summary.hook_failures.is_empty() && fail_on_hooks {
+                return Err(anyhow!("Some post_release hooks failed"));
+            }
+        }
+        Commands::Pack {
+            discord,
+            config,
+            profile,
+            version,
+            version_type,
+            changelog,
+            changelog_file,
+            edit,
+            skip_modrinth,
+        } => {
+            let config_file: PackConfig = read_config(&config, profile.as_deref())?;
+
+            let options = PackReleaseOptions {
+                project_dir: project_dir_from_config(&config),
+                version,
+                version_type,
+                discord,
+                changelog: read_changelog_override(changelog, changelog_file)?,
+                edit,
+                skip_modrinth,
             };
 
-            // Create Modrinth Release
+            let summary = release_pack(config_file, options).await?;
 
-            let modrinth_url = ModrinthUrl::new(&config_file.modrinth.staging);
+            summary.print();
 
-            match modrinth::create_mod_release(
-                &config_file,
-                &version_info,
-                &changelog_markdown,
-                &modrinth_url,
-                &version_info.name,
-                match version_type {
-                    Some(ver_type) => ver_type,
-                    None => VersionType::Release
-                }
-            )
-            .await
-            {
-                Ok(_) => {}
-                Err(err) => return Err(err),
-            };
+            if !summary.webhook_failures().is_empty() {
+                return Err(anyhow!("Failed to send some notifications"));
+            }
+        }
+        #[cfg(feature = "keyring")]
+        Commands::Auth { action } => match action {
+            AuthAction::Set { name } => {
+                let env_var = credential_env_var(&name);
+                let secret = init::prompt_secret(&format!("Value of `{}`", env_var))?;
+
+                store_credential(&env_var, &secret)?;
+
+                println!(
+                    "Stored `{}` in the keyring, it's used whenever `{}` isn't set",
+                    env_var, env_var
+                );
+            }
+            AuthAction::Remove { name } => {
+                let env_var = credential_env_var(&name);
 
-            if discord {
-                let discord_config = match config_file.discord {
-                    Some(config) => config,
-                    None => return Err(anyhow!("Failed to get Discord config")),
-                };
+                remove_credential(&env_var)?;
 
-                match send_discord_webhook(
-                    &discord_config,
-                    &modrinth_url,
-                    &config_file.modrinth.project_id,
-                    &config_file.github,
-                    &version_info.name,
-                    &changelog_markdown,
-                )
-                .await
-                {
-                    Ok(_) => {
-                        println!("Sent Discord webhook!")
-                    }
-                    Err(err) => return Err(err),
-                }
+                println!("Removed `{}` from the keyring", env_var);
             }
-
-            clean_up(&tmp_info.dir_path)?
-        }
+        },
     }
     Ok(())
 }
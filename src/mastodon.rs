@@ -0,0 +1,184 @@
+use crate::discord::{fit_changelog, truncate_chars};
+use crate::models::mastodon::{CreateStatusRequest, StatusResponse};
+use crate::models::{
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    MastodonConfig, NetworkConfig,
+};
+use crate::util::{error_body, join_url, read_secret_env, send_with_retry};
+use crate::version::render_template;
+use anyhow::anyhow;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use tracing::{info, warn};
+
+/// Mastodon's own limit, used when the instance doesn't say.
+const DEFAULT_CHARACTER_LIMIT: usize = 500;
+
+/// Posts the release as a status. Failures are only logged and returned in the result, as
+/// the release already happened.
+pub async fn post_release_status(
+    mastodon_config: &MastodonConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> WebhookResult {
+    let error = match post_status(mastodon_config, release, network, dry_run).await {
+        Ok(_) => None,
+        Err(err) => {
+            warn!("{}", err);
+            Some(err.to_string())
+        }
+    };
+
+    WebhookResult {
+        notifier: Notifier::Mastodon,
+        target: mastodon_config.instance_url.clone(),
+        error,
+    }
+}
+
+async fn post_status(
+    mastodon_config: &MastodonConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<(), anyhow::Error> {
+    let character_limit = if dry_run {
+        DEFAULT_CHARACTER_LIMIT
+    } else {
+        get_character_limit(mastodon_config, network).await
+    };
+
+    let request = CreateStatusRequest {
+        status: render_status(mastodon_config, release, character_limit)?,
+        visibility: mastodon_config.visibility(),
+    };
+
+    if dry_run {
+        info!(
+            "[dry run] Would post to `{}`:\n{}",
+            mastodon_config.instance_url, request.status
+        );
+        return Ok(());
+    }
+
+    let token = read_secret_env(&mastodon_config.token_env())?;
+    // Makes Mastodon ignore a retried request it already posted
+    let idempotency_key = uuid::Uuid::new_v4().to_string();
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .post(join_url(&mastodon_config.instance_url, "api/v1/statuses"))
+            .bearer_auth(token.expose())
+            .header("Idempotency-Key", &idempotency_key)
+            .json(&request))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to post to Mastodon: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to post to Mastodon: {}",
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<StatusResponse>().await {
+        Ok(status) => {
+            info!("Posted to Mastodon: {}", status.url.unwrap_or(status.id));
+            Ok(())
+        }
+        Err(err) => Err(anyhow!("Failed to parse Mastodon response: {}", err)),
+    }
+}
+
+/// Reads `configuration.statuses.max_characters` of the instance, or `max_toot_chars` which
+/// Pleroma and Akkoma use.
+async fn get_character_limit(mastodon_config: &MastodonConfig, network: &NetworkConfig) -> usize {
+    let res = send_with_retry(network, |client| {
+        Ok(client.get(join_url(&mastodon_config.instance_url, "api/v1/instance")))
+    })
+    .await;
+
+    let instance = match res {
+        Ok(res) if res.status().is_success() => res.json::<serde_json::Value>().await.ok(),
+        _ => None,
+    };
+
+    let limit = instance.as_ref().and_then(|instance| {
+        instance
+            .pointer("/configuration/statuses/max_characters")
+            .or_else(|| instance.get("max_toot_chars"))
+            .and_then(|limit| limit.as_u64())
+    });
+
+    match limit {
+        Some(limit) => limit as usize,
+        None => {
+            warn!(
+                "Failed to get the character limit of `{}`, using {}",
+                mastodon_config.instance_url, DEFAULT_CHARACTER_LIMIT
+            );
+            DEFAULT_CHARACTER_LIMIT
+        }
+    }
+}
+
+/// The changelog summary gets whatever the rest of the template leaves of the limit.
+pub fn render_status(
+    mastodon_config: &MastodonConfig,
+    release: &ReleaseSummary,
+    character_limit: usize,
+) -> Result<String, anyhow::Error> {
+    let template = mastodon_config.template();
+    let modrinth_url = release.url(Platform::Modrinth).cloned().unwrap_or_default();
+
+    let render = |changelog_summary: &str| {
+        render_template(
+            "mastodon.template",
+            &template,
+            &[
+                ("version", release.version_name.as_str()),
+                ("modrinth_url", modrinth_url.as_str()),
+                ("changelog_summary", changelog_summary),
+            ],
+        )
+        .map(|status| status.trim().to_string())
+    };
+
+    let budget = character_limit.saturating_sub(render("")?.chars().count());
+    let changelog_summary = fit_changelog(
+        &plain_text(&release.changelog.render_github()),
+        budget,
+        None,
+    );
+
+    Ok(truncate_chars(
+        &render(&changelog_summary)?,
+        character_limit,
+    ))
+}
+
+/// Statuses are plain text, so only the text of the changelog is kept, a line per paragraph,
+/// heading and list item.
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::Start(Tag::Item) => text.push_str("- "),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item)
+                if !text.ends_with('\n') =>
+            {
+                text.push('\n')
+            }
+            _ => (),
+        }
+    }
+
+    text.trim().to_string()
+}
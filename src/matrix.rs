@@ -0,0 +1,186 @@
+use crate::discord::fit_changelog;
+use crate::models::matrix::{RoomMessage, SendEventResponse};
+use crate::models::{
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    MatrixConfig, NetworkConfig,
+};
+use crate::util::{error_body, read_secret_env, send_with_retry};
+use anyhow::anyhow;
+use pulldown_cmark::{html, Parser};
+use tracing::{info, warn};
+
+/// Homeservers reject events over 64 KiB, the changelog is cut well before that.
+const CHANGELOG_LIMIT: usize = 20_000;
+
+/// Posts the release to the Matrix room of the config, as HTML with a plain text fallback.
+pub async fn send_matrix_notification(
+    matrix_config: &MatrixConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<WebhookResult, anyhow::Error> {
+    let message = release_message(release);
+
+    if dry_run {
+        let message_preview = match serde_json::to_string_pretty(&message) {
+            Ok(json) => json,
+            Err(err) => return Err(anyhow!("Failed to serialize Matrix message: {}", err)),
+        };
+
+        info!(
+            "[dry run] Would send Matrix message to `{}`:\n{}",
+            matrix_config.room_id, message_preview
+        );
+        return Ok(WebhookResult {
+            notifier: Notifier::Matrix,
+            target: matrix_config.room_id.clone(),
+            error: None,
+        });
+    }
+
+    let error = match send_room_message(matrix_config, &message, network).await {
+        Ok(event_id) => {
+            info!(
+                "Sent Matrix message `{}` to `{}`!",
+                event_id, matrix_config.room_id
+            );
+            None
+        }
+        Err(err) => {
+            warn!("{}", err);
+            Some(err.to_string())
+        }
+    };
+
+    Ok(WebhookResult {
+        notifier: Notifier::Matrix,
+        target: matrix_config.room_id.clone(),
+        error,
+    })
+}
+
+pub fn release_message(release: &ReleaseSummary) -> RoomMessage {
+    let changelog = fit_changelog(&release.changelog.render_github(), CHANGELOG_LIMIT, None);
+
+    let links = [
+        Platform::Github,
+        Platform::Gitlab,
+        Platform::Gitea,
+        Platform::Modrinth,
+        Platform::Curseforge,
+    ]
+    .into_iter()
+    .filter_map(|platform| release.url(platform).map(|url| (platform.formatted(), url)))
+    .collect::<Vec<(String, &String)>>();
+
+    let mut body = format!(
+        "New release: {} ({})\n\n{}",
+        release.version_name, release.version_number, changelog
+    );
+    if !links.is_empty() {
+        body.push_str("\n\n");
+        for (name, url) in &links {
+            body.push_str(&format!("{}: {}\n", name, url));
+        }
+    }
+
+    let mut formatted_body = format!(
+        "<h3>{}</h3>\n<p>Version <code>{}</code></p>\n",
+        escape_html(&release.version_name),
+        escape_html(&release.version_number)
+    );
+    html::push_html(&mut formatted_body, Parser::new(&changelog));
+    if !links.is_empty() {
+        formatted_body.push_str(&format!(
+            "<p>{}</p>",
+            links
+                .iter()
+                .map(|(name, url)| format!("<a href=\"{}\">{}</a>", escape_html(url), name))
+                .collect::<Vec<String>>()
+                .join(" | ")
+        ));
+    }
+
+    RoomMessage {
+        msgtype: "m.text".to_string(),
+        body: body.trim_end().to_string(),
+        format: "org.matrix.custom.html".to_string(),
+        formatted_body,
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Sends with `PUT /rooms/{roomId}/send/m.room.message/{txnId}`. The transaction ID is the same
+/// for every retry, so that the homeserver ignores repeats of a message it already got.
+pub async fn send_room_message(
+    matrix_config: &MatrixConfig,
+    message: &RoomMessage,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    let token = read_secret_env(&matrix_config.access_token_env())?;
+    let transaction_id = uuid::Uuid::new_v4().to_string();
+
+    let mut url = match reqwest::Url::parse(&matrix_config.homeserver_url) {
+        Ok(url) => url,
+        Err(err) => {
+            return Err(anyhow!(
+                "Invalid Matrix homeserver url `{}`: {}",
+                matrix_config.homeserver_url,
+                err
+            ))
+        }
+    };
+
+    // Added as path segments, so that anything in the room ID that needs escaping is escaped
+    match url.path_segments_mut() {
+        Ok(mut segments) => {
+            segments.pop_if_empty().extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &matrix_config.room_id,
+                "send",
+                "m.room.message",
+                &transaction_id,
+            ]);
+        }
+        Err(_) => {
+            return Err(anyhow!(
+                "Invalid Matrix homeserver url `{}`",
+                matrix_config.homeserver_url
+            ))
+        }
+    }
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .put(url.clone())
+            .bearer_auth(token.expose())
+            .json(message))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to send Matrix message: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to send Matrix message to `{}`: {}",
+            matrix_config.room_id,
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<SendEventResponse>().await {
+        Ok(res) => Ok(res.event_id),
+        Err(err) => Err(anyhow!("Failed to parse Matrix response: {}", err)),
+    }
+}
@@ -1,26 +1,813 @@
-// use std::fs::File;
-// use anyhow::anyhow;
-// use zip::{ZipArchive, read::ZipFile};
-// use crate::util::file_exists_in_zip;
-//
-// pub fn get_loader_file(archive: &mut ZipArchive<File>) -> Result<ZipFile, anyhow::Error> {
-//     if file_exists_in_zip(&mut archive, "fabric.mod.json") {
-//         match archive.by_name("fabric.mod.json") {
-//             Ok(file) => Ok(file),
-//             Err(err) => return Err(anyhow!(
-//                 "Failed to get `fabric.mod.json` file from jar: {}", err
-//             ))
-//         }
-//     } else if file_exists_in_zip(&mut archive, "quilt.mod.json") {
-//         match archive.by_name("quilt.mod.json") {
-//             Ok(file) => Some(file),
-//             Err(err) => return Err(anyhow!(
-//                 "Failed to get `quilt.mod.json` file from jar: {}", err
-//             ))
-//         }
-//     } else {
-//         return Err(anyhow!(
-//             "Failed to get `fabric.mod.json` or `quilt.mod.json` from jar"
-//         ))
-//     }
-// }
+use anyhow::anyhow;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+use zip::ZipArchive;
+
+use crate::models::{
+    modrinth::{DependencyType, Loader},
+    project_type::mc_mod::{ModDependency, ModInfo},
+};
+use crate::util::file_exists_in_zip;
+
+/// How many lines of Gradle's stderr are kept for the error message of a failed build.
+const GRADLE_ERROR_LINES: usize = 50;
+
+/// `--gradle-args` used to take every arg as one string, so values containing spaces are
+/// still split up to keep existing scripts working.
+pub fn split_gradle_args(gradle_args: Vec<String>) -> Vec<String> {
+    let mut args = vec![];
+
+    for arg in gradle_args {
+        if arg.contains(' ') {
+            warn!(
+                "Passing multiple Gradle args in one value is deprecated, \
+                 repeat `--gradle-arg` for each of them instead"
+            );
+            args.extend(arg.split_whitespace().map(String::from));
+        } else {
+            args.push(arg);
+        }
+    }
+
+    args
+}
+
+/// Runs the Gradle wrapper of `project_dir` with `args`, using the JDK of `JAVA_HOME` when
+/// it's set.
+pub fn run_gradle_args(
+    gradlew_path: &Path,
+    project_dir: &Path,
+    args: &[String],
+) -> Result<(), anyhow::Error> {
+    let mut command = Command::new(gradlew_path);
+
+    if let Some(java_home) = java_home() {
+        command.arg(format!("-Dorg.gradle.java.home={}", java_home.display()));
+    }
+
+    run_gradle(command.args(args).current_dir(project_dir))
+}
+
+fn java_home() -> Option<PathBuf> {
+    env::var_os("JAVA_HOME")
+        .filter(|java_home| !java_home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$JAVA_HOME/bin/java` if `JAVA_HOME` is set, otherwise the `java` on the `PATH`.
+pub fn find_java() -> Result<PathBuf, anyhow::Error> {
+    let java_home = match java_home() {
+        Some(java_home) => java_home,
+        None => {
+            return match which::which("java") {
+                Ok(path) => Ok(path),
+                Err(err) => Err(anyhow!("Failed to find Java executable: {}", err)),
+            }
+        }
+    };
+
+    let java = java_home
+        .join("bin")
+        .join(format!("java{}", env::consts::EXE_SUFFIX));
+    match java.exists() {
+        true => Ok(java),
+        false => Err(anyhow!(
+            "Failed to find Java executable of `JAVA_HOME` at `{}`",
+            java.display()
+        )),
+    }
+}
+
+/// Fails before anything is built if Java is older than `required`.
+pub fn check_java_version(required: u32) -> Result<(), anyhow::Error> {
+    let java = find_java()?;
+
+    let output = match Command::new(&java).arg("-version").output() {
+        Ok(output) => output,
+        Err(err) => return Err(anyhow!("Failed to run `{}`: {}", java.display(), err)),
+    };
+
+    // `-version` prints to stderr
+    let version_output = String::from_utf8_lossy(&output.stderr);
+    let version = match parse_java_version(&version_output) {
+        Some(version) => version,
+        None => {
+            return Err(anyhow!(
+                "Failed to parse the version of `{}` from:\n{}",
+                java.display(),
+                version_output.trim()
+            ))
+        }
+    };
+
+    match version >= required {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "The mod needs Java {} but `{}` is Java {}, set `JAVA_HOME` to a newer JDK",
+            required,
+            java.display(),
+            version
+        )),
+    }
+}
+
+/// The major version of e.g. `openjdk version "21.0.2"`, or of `java version "1.8.0_382"`
+/// from before Java 9 dropped the `1.`.
+pub fn parse_java_version(version_output: &str) -> Option<u32> {
+    let version = version_output.split('"').nth(1)?;
+    let mut parts = version.split(['.', '_', '-', '+']);
+
+    match parts.next()? {
+        "1" => parts.next()?.parse().ok(),
+        major => major.parse().ok(),
+    }
+}
+
+/// Runs Gradle to completion, failing if it didn't exit successfully.
+/// Stderr is still reported line by line while it's being captured.
+pub fn run_gradle(gradle_command: &mut Command) -> Result<(), anyhow::Error> {
+    let mut gradle_child = match gradle_command.stderr(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(err) => return Err(anyhow!("Failed to build with Gradle: {}", err)),
+    };
+
+    let mut stderr_tail: VecDeque<String> = VecDeque::with_capacity(GRADLE_ERROR_LINES);
+
+    if let Some(stderr) = gradle_child.stderr.take() {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            info!("{}", line);
+
+            if stderr_tail.len() == GRADLE_ERROR_LINES {
+                stderr_tail.pop_front();
+            }
+            stderr_tail.push_back(line);
+        }
+    }
+
+    let status = match gradle_child.wait() {
+        Ok(status) => status,
+        Err(err) => return Err(anyhow!("Failed to wait for Gradle to finish: {}", err)),
+    };
+
+    if status.success() {
+        return Ok(());
+    }
+
+    let stderr_tail = Vec::from(stderr_tail).join("\n");
+
+    match status.code() {
+        Some(code) => Err(anyhow!(
+            "Gradle build failed with exit code {}:\n{}",
+            code,
+            stderr_tail
+        )),
+        None => Err(anyhow!(
+            "Gradle build was terminated by a signal:\n{}",
+            stderr_tail
+        )),
+    }
+}
+
+/// Metadata files of each loader, checked in this order. Forge and NeoForge list their mods
+/// in TOML, Fabric and Quilt in JSON.
+const METADATA_FILES: [(&str, Loader); 4] = [
+    ("fabric.mod.json", Loader::Fabric),
+    ("quilt.mod.json", Loader::Quilt),
+    ("META-INF/neoforge.mods.toml", Loader::Neoforge),
+    ("META-INF/mods.toml", Loader::Forge),
+];
+
+/// Reads the ID, name and version of the mod from the metadata of its loader, which is
+/// detected from the file that's in the jar. A version that's still a placeholder like
+/// `${version}` is replaced with `gradle_version`.
+pub fn read_mod_info(
+    jar_path: &Path,
+    gradle_version: Option<&str>,
+) -> Result<ModInfo, anyhow::Error> {
+    let mut mod_info = read_jar_metadata(jar_path)?;
+
+    if is_placeholder(&mod_info.version) {
+        mod_info.version = match gradle_version {
+            Some(version) => version.to_string(),
+            None => {
+                return Err(anyhow!(
+                    "`{}` has the placeholder version `{}`, and `gradle.properties` has no \
+                     `mod_version` to use instead",
+                    jar_path.display(),
+                    mod_info.version
+                ))
+            }
+        };
+    }
+
+    Ok(mod_info)
+}
+
+/// Left in the metadata when `processResources` doesn't expand it.
+fn is_placeholder(value: &str) -> bool {
+    value.contains("${")
+}
+
+/// `mod_version` of the project's `gradle.properties`, which is readable before the build.
+pub fn read_gradle_mod_version(project_dir: &Path) -> Result<Option<String>, anyhow::Error> {
+    let path = project_dir.join("gradle.properties");
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse_gradle_property(&contents, "mod_version")),
+        Err(err) => Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    }
+}
+
+/// `group:archives_base_name:version`, with the group of `maven_group` or `group` in
+/// `gradle.properties` like the Fabric and NeoForge templates have it.
+pub fn read_maven_coordinates(
+    project_dir: &Path,
+    version: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let path = project_dir.join("gradle.properties");
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    };
+
+    let group = parse_gradle_property(&contents, "maven_group")
+        .or_else(|| parse_gradle_property(&contents, "group"));
+    let artifact = parse_gradle_property(&contents, "archives_base_name");
+
+    Ok(match (group, artifact) {
+        (Some(group), Some(artifact)) => Some(format!("{}:{}:{}", group, artifact, version)),
+        _ => None,
+    })
+}
+
+/// `key=value` and `key: value` lines, comments start with `#` or `!`.
+pub fn parse_gradle_property(contents: &str, key: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with('!') {
+            return None;
+        }
+
+        let (name, value) = line.split_once(['=', ':'])?;
+        match name.trim() == key {
+            true => Some(value.trim().to_string()).filter(|value| !value.is_empty()),
+            false => None,
+        }
+    })
+}
+
+/// Mod IDs every mod depends on in some way, which aren't projects on Modrinth.
+const PLATFORM_MOD_IDS: [&str; 6] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+];
+
+fn mod_dependency(
+    own_id: &str,
+    mod_id: &str,
+    dependency_type: DependencyType,
+) -> Option<ModDependency> {
+    match mod_id == own_id || PLATFORM_MOD_IDS.contains(&mod_id) {
+        true => None,
+        false => Some(ModDependency {
+            mod_id: mod_id.to_string(),
+            dependency_type,
+        }),
+    }
+}
+
+fn read_jar_metadata(jar_path: &Path) -> Result<ModInfo, anyhow::Error> {
+    let jar_file = match File::open(jar_path) {
+        Ok(file) => file,
+        Err(err) => return Err(anyhow!("Failed to open `{}`: {}", jar_path.display(), err)),
+    };
+
+    let mut archive = match ZipArchive::new(jar_file) {
+        Ok(archive) => archive,
+        Err(err) => return Err(anyhow!("Failed to read `{}`: {}", jar_path.display(), err)),
+    };
+
+    let (file_name, loader) = match METADATA_FILES
+        .into_iter()
+        .find(|(file_name, _)| file_exists_in_zip(&mut archive, file_name))
+    {
+        Some(metadata_file) => metadata_file,
+        None => {
+            return Err(anyhow!(
+                "Failed to find {} in jar",
+                METADATA_FILES
+                    .iter()
+                    .map(|(file_name, _)| format!("`{}`", file_name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ))
+        }
+    };
+
+    let contents = read_zip_file(&mut archive, file_name)?;
+
+    match loader {
+        Loader::Fabric => parse_fabric_metadata(&contents),
+        Loader::Quilt => parse_quilt_metadata(&contents),
+        _ => {
+            let manifest = read_zip_file(&mut archive, "META-INF/MANIFEST.MF").ok();
+            parse_forge_metadata(file_name, &contents, manifest.as_deref(), loader)
+        }
+    }
+}
+
+fn read_zip_file(archive: &mut ZipArchive<File>, file_name: &str) -> Result<String, anyhow::Error> {
+    let mut contents = String::new();
+
+    match archive.by_name(file_name) {
+        Ok(mut file) => match file.read_to_string(&mut contents) {
+            Ok(_) => Ok(contents),
+            Err(err) => Err(anyhow!("Failed to read `{}` from jar: {}", file_name, err)),
+        },
+        Err(err) => Err(anyhow!("Failed to get `{}` from jar: {}", file_name, err)),
+    }
+}
+
+pub fn parse_fabric_metadata(contents: &str) -> Result<ModInfo, anyhow::Error> {
+    let metadata: serde_json::Value = match serde_json::from_str(contents) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(anyhow!("Failed to parse `fabric.mod.json`: {}", err)),
+    };
+
+    let field = |name: &str| metadata[name].as_str().map(String::from);
+    let id = match field("id") {
+        Some(id) => id,
+        None => return Err(anyhow!("`fabric.mod.json` has no `id`")),
+    };
+
+    let mut dependencies = vec![];
+    for (block, dependency_type) in [
+        ("depends", DependencyType::Required),
+        ("recommends", DependencyType::Optional),
+        ("breaks", DependencyType::Incompatible),
+    ] {
+        if let Some(mod_ids) = metadata[block].as_object() {
+            dependencies.extend(
+                mod_ids
+                    .keys()
+                    .filter_map(|mod_id| mod_dependency(&id, mod_id, dependency_type)),
+            );
+        }
+    }
+
+    Ok(ModInfo {
+        name: field("name").unwrap_or_else(|| id.clone()),
+        version: match field("version") {
+            Some(version) => version,
+            None => return Err(anyhow!("`fabric.mod.json` has no `version`")),
+        },
+        id,
+        loader: Loader::Fabric,
+        dependencies,
+    })
+}
+
+/// Everything is nested in `quilt_loader`, the name in its `metadata`.
+pub fn parse_quilt_metadata(contents: &str) -> Result<ModInfo, anyhow::Error> {
+    let metadata: serde_json::Value = match serde_json::from_str(contents) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(anyhow!("Failed to parse `quilt.mod.json`: {}", err)),
+    };
+
+    let loader = &metadata["quilt_loader"];
+    let id = match loader["id"].as_str() {
+        Some(id) => id.to_string(),
+        None => return Err(anyhow!("`quilt.mod.json` has no `quilt_loader.id`")),
+    };
+
+    // Either just the mod ID, or an object with an `optional` flag
+    let mut dependencies = vec![];
+    for (block, dependency_type) in [
+        ("depends", DependencyType::Required),
+        ("breaks", DependencyType::Incompatible),
+    ] {
+        for dependency in loader[block].as_array().into_iter().flatten() {
+            let (mod_id, optional) = match dependency {
+                serde_json::Value::String(mod_id) => (mod_id.as_str(), false),
+                dependency => match dependency["id"].as_str() {
+                    Some(mod_id) => (mod_id, dependency["optional"].as_bool().unwrap_or(false)),
+                    None => continue,
+                },
+            };
+            let dependency_type = match (dependency_type, optional) {
+                (DependencyType::Required, true) => DependencyType::Optional,
+                (dependency_type, _) => dependency_type,
+            };
+
+            // Quilt mods depend on `mod_id` as `namespace:mod_id` when it isn't unique
+            let mod_id = mod_id.rsplit(':').next().unwrap_or(mod_id);
+            dependencies.extend(mod_dependency(&id, mod_id, dependency_type));
+        }
+    }
+
+    Ok(ModInfo {
+        name: loader["metadata"]["name"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| id.clone()),
+        version: match loader["version"].as_str() {
+            Some(version) => version.to_string(),
+            None => return Err(anyhow!("`quilt.mod.json` has no `quilt_loader.version`")),
+        },
+        id,
+        loader: Loader::Quilt,
+        dependencies,
+    })
+}
+
+/// The first of the `[[mods]]`. A `${file.jarVersion}` version is replaced by Gradle with the
+/// `Implementation-Version` of the jar's manifest, so that's read instead.
+pub fn parse_forge_metadata(
+    file_name: &str,
+    contents: &str,
+    manifest: Option<&str>,
+    loader: Loader,
+) -> Result<ModInfo, anyhow::Error> {
+    let metadata: toml::Table = match toml::from_str(contents) {
+        Ok(metadata) => metadata,
+        Err(err) => return Err(anyhow!("Failed to parse `{}`: {}", file_name, err)),
+    };
+
+    let first_mod = match metadata
+        .get("mods")
+        .and_then(|mods| mods.as_array())
+        .and_then(|mods| mods.first())
+    {
+        Some(first_mod) => first_mod,
+        None => return Err(anyhow!("`{}` has no `[[mods]]`", file_name)),
+    };
+
+    let field = |name: &str| first_mod.get(name).and_then(|value| value.as_str());
+    let id = match field("modId") {
+        Some(id) => id.to_string(),
+        None => return Err(anyhow!("`{}` has no `modId`", file_name)),
+    };
+
+    // `[[dependencies.<modId>]]`, Forge marks them `mandatory` and NeoForge gives a `type`
+    let mut dependencies = vec![];
+    let declared = metadata
+        .get("dependencies")
+        .and_then(|dependencies| dependencies.get(&id))
+        .and_then(|dependencies| dependencies.as_array());
+    for dependency in declared.into_iter().flatten() {
+        let mod_id = match dependency.get("modId").and_then(|mod_id| mod_id.as_str()) {
+            Some(mod_id) => mod_id,
+            None => continue,
+        };
+        let dependency_type = match (
+            dependency
+                .get("type")
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_lowercase())
+                .as_deref(),
+            dependency
+                .get("mandatory")
+                .and_then(|value| value.as_bool()),
+        ) {
+            (Some("required"), _) | (None, Some(true)) => DependencyType::Required,
+            (Some("optional"), _) | (None, Some(false)) => DependencyType::Optional,
+            (Some("incompatible"), _) => DependencyType::Incompatible,
+            _ => continue,
+        };
+
+        dependencies.extend(mod_dependency(&id, mod_id, dependency_type));
+    }
+
+    let version = match field("version") {
+        Some("${file.jarVersion}") => manifest.and_then(|manifest| {
+            manifest.lines().find_map(|line| {
+                line.strip_prefix("Implementation-Version:")
+                    .map(|version| version.trim().to_string())
+            })
+        }),
+        Some(version) => Some(version.to_string()),
+        None => None,
+    };
+
+    Ok(ModInfo {
+        name: field("displayName").unwrap_or(&id).to_string(),
+        version: match version {
+            Some(version) => version,
+            None => {
+                return Err(anyhow!(
+                    "Failed to get the version of `{}` from `{}`",
+                    id,
+                    file_name
+                ))
+            }
+        },
+        id,
+        loader,
+        dependencies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_dir;
+
+    /// A `gradlew` that runs `script` instead of Gradle. It's run through `sh`, as executing
+    /// a file that was just written fails when another test forks meanwhile.
+    #[cfg(unix)]
+    fn fake_gradlew(dir: &Path, script: &str) -> Command {
+        let path = dir.join("gradlew");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+
+        let mut command = Command::new("sh");
+        command.arg(path);
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn successful_gradle_build() {
+        let dir = test_dir();
+        let mut gradlew = fake_gradlew(&dir.dir_path, "echo 'BUILD SUCCESSFUL' >&2");
+
+        run_gradle(&mut gradlew).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_gradle_build_reports_end_of_stderr() {
+        let dir = test_dir();
+        let mut gradlew = fake_gradlew(
+            &dir.dir_path,
+            "i=1; while [ $i -le 60 ]; do echo \"error line $i\" >&2; i=$((i + 1)); done; exit 1",
+        );
+
+        let err = run_gradle(&mut gradlew).unwrap_err().to_string();
+
+        assert!(
+            err.starts_with("Gradle build failed with exit code 1:\n"),
+            "{}",
+            err
+        );
+        assert_eq!(err.lines().count(), 1 + GRADLE_ERROR_LINES);
+        assert!(err.contains("\nerror line 11\n"), "{}", err);
+        assert!(!err.contains("error line 10\n"), "{}", err);
+        assert!(err.ends_with("error line 60"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn gradle_build_killed_by_signal() {
+        let dir = test_dir();
+        let mut gradlew = fake_gradlew(&dir.dir_path, "echo 'Killed' >&2; kill -9 $$");
+
+        let err = run_gradle(&mut gradlew).unwrap_err().to_string();
+
+        assert!(
+            err.starts_with("Gradle build was terminated by a signal"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn missing_gradle_fails_to_start() {
+        let dir = test_dir();
+
+        let err = run_gradle(&mut Command::new(dir.dir_path.join("gradlew")))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.starts_with("Failed to build with Gradle"), "{}", err);
+    }
+
+    const FABRIC_MOD_JSON: &str = include_str!("../test_projects/mod-metadata/fabric.mod.json");
+    const QUILT_MOD_JSON: &str = include_str!("../test_projects/mod-metadata/quilt.mod.json");
+    const MODS_TOML: &str = include_str!("../test_projects/mod-metadata/mods.toml");
+    const NEOFORGE_MODS_TOML: &str =
+        include_str!("../test_projects/mod-metadata/neoforge.mods.toml");
+    const MANIFEST: &str = include_str!("../test_projects/mod-metadata/MANIFEST.MF");
+
+    /// Sorted by mod ID, as JSON objects don't keep their order.
+    fn dependencies(mod_info: &ModInfo) -> Vec<(&str, DependencyType)> {
+        let mut dependencies = mod_info
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.mod_id.as_str(), dependency.dependency_type))
+            .collect::<Vec<(&str, DependencyType)>>();
+        dependencies.sort_by_key(|(mod_id, _)| *mod_id);
+        dependencies
+    }
+
+    fn write_jar(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (file_name, contents) in files {
+            zip.start_file(*file_name, zip::write::FileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn parses_fabric_metadata() {
+        let mod_info = parse_fabric_metadata(FABRIC_MOD_JSON).unwrap();
+
+        assert_eq!(mod_info.id, "peony_test");
+        assert_eq!(mod_info.name, "Peony Test");
+        assert_eq!(mod_info.version, "1.2.0+mc1.20.1");
+        assert_eq!(mod_info.loader, Loader::Fabric);
+        assert_eq!(
+            dependencies(&mod_info),
+            [
+                ("fabric-api", DependencyType::Required),
+                ("modmenu", DependencyType::Optional),
+                ("optifabric", DependencyType::Incompatible),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quilt_metadata() {
+        let mod_info = parse_quilt_metadata(QUILT_MOD_JSON).unwrap();
+
+        assert_eq!(mod_info.id, "peony_test");
+        assert_eq!(mod_info.name, "Peony Test");
+        assert_eq!(mod_info.version, "1.2.0+mc1.20.1");
+        assert_eq!(mod_info.loader, Loader::Quilt);
+        assert_eq!(
+            dependencies(&mod_info),
+            [
+                ("modmenu", DependencyType::Optional),
+                ("optifabric", DependencyType::Incompatible),
+                ("qsl", DependencyType::Required),
+                ("quilted_fabric_api", DependencyType::Required),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_forge_metadata_with_manifest_version() {
+        let mod_info = parse_forge_metadata(
+            "META-INF/mods.toml",
+            MODS_TOML,
+            Some(MANIFEST),
+            Loader::Forge,
+        )
+        .unwrap();
+
+        assert_eq!(mod_info.id, "peony_test");
+        assert_eq!(mod_info.name, "Peony Test");
+        assert_eq!(mod_info.version, "1.2.0+mc1.20.1");
+        assert_eq!(mod_info.loader, Loader::Forge);
+        assert_eq!(
+            dependencies(&mod_info),
+            [
+                ("architectury", DependencyType::Required),
+                ("jei", DependencyType::Optional),
+            ]
+        );
+    }
+
+    #[test]
+    fn forge_jar_version_needs_manifest() {
+        let err =
+            parse_forge_metadata("META-INF/mods.toml", MODS_TOML, None, Loader::Forge).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to get the version of `peony_test` from `META-INF/mods.toml`"
+        );
+    }
+
+    #[test]
+    fn parses_neoforge_metadata() {
+        let mod_info = parse_forge_metadata(
+            "META-INF/neoforge.mods.toml",
+            NEOFORGE_MODS_TOML,
+            None,
+            Loader::Neoforge,
+        )
+        .unwrap();
+
+        assert_eq!(mod_info.version, "1.2.0+mc1.20.4");
+        assert_eq!(mod_info.loader, Loader::Neoforge);
+        // `discouraged` has no Modrinth dependency type
+        assert_eq!(
+            dependencies(&mod_info),
+            [
+                ("curios", DependencyType::Optional),
+                ("oculus", DependencyType::Incompatible),
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_without_id_fails() {
+        let err = parse_fabric_metadata(r#"{"version": "1.0.0"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "`fabric.mod.json` has no `id`");
+
+        let err = parse_quilt_metadata(r#"{"quilt_loader": {"version": "1.0.0"}}"#).unwrap_err();
+        assert_eq!(err.to_string(), "`quilt.mod.json` has no `quilt_loader.id`");
+
+        let err = parse_forge_metadata(
+            "META-INF/mods.toml",
+            "modLoader = \"javafml\"",
+            None,
+            Loader::Forge,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "`META-INF/mods.toml` has no `[[mods]]`");
+    }
+
+    #[test]
+    fn detects_loader_from_jar() {
+        let dir = test_dir();
+        let cases = [
+            ("fabric.mod.json", FABRIC_MOD_JSON, Loader::Fabric),
+            ("quilt.mod.json", QUILT_MOD_JSON, Loader::Quilt),
+            ("META-INF/mods.toml", MODS_TOML, Loader::Forge),
+            (
+                "META-INF/neoforge.mods.toml",
+                NEOFORGE_MODS_TOML,
+                Loader::Neoforge,
+            ),
+        ];
+
+        for (file_name, contents, loader) in cases {
+            let jar_path = dir.dir_path.join("peony-test.jar");
+            write_jar(
+                &jar_path,
+                &[(file_name, contents), ("META-INF/MANIFEST.MF", MANIFEST)],
+            );
+
+            let mod_info = read_mod_info(&jar_path, None).unwrap();
+            assert_eq!(mod_info.loader, loader, "{}", file_name);
+            assert_eq!(mod_info.id, "peony_test", "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn neoforge_metadata_wins_over_forge() {
+        let dir = test_dir();
+        let jar_path = dir.dir_path.join("peony-test.jar");
+        write_jar(
+            &jar_path,
+            &[
+                ("META-INF/mods.toml", MODS_TOML),
+                ("META-INF/neoforge.mods.toml", NEOFORGE_MODS_TOML),
+            ],
+        );
+
+        assert_eq!(
+            read_mod_info(&jar_path, None).unwrap().loader,
+            Loader::Neoforge
+        );
+    }
+
+    #[test]
+    fn placeholder_version_is_replaced_with_gradle_version() {
+        let dir = test_dir();
+        let jar_path = dir.dir_path.join("peony-test.jar");
+        let metadata = FABRIC_MOD_JSON.replace("1.2.0+mc1.20.1", "${version}");
+        write_jar(&jar_path, &[("fabric.mod.json", &metadata)]);
+
+        assert_eq!(
+            read_mod_info(&jar_path, Some("1.3.0")).unwrap().version,
+            "1.3.0"
+        );
+        let err = read_mod_info(&jar_path, None).unwrap_err().to_string();
+        assert!(err.ends_with("has the placeholder version `${version}`, and `gradle.properties` has no `mod_version` to use instead"), "{}", err);
+    }
+
+    #[test]
+    fn jar_without_metadata_fails() {
+        let dir = test_dir();
+        let jar_path = dir.dir_path.join("peony-test.jar");
+        write_jar(&jar_path, &[("META-INF/MANIFEST.MF", MANIFEST)]);
+
+        let err = read_mod_info(&jar_path, None).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to find `fabric.mod.json`, `quilt.mod.json`, `META-INF/neoforge.mods.toml`, \
+             `META-INF/mods.toml` in jar"
+        );
+    }
+}
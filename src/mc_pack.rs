@@ -0,0 +1,142 @@
+//! Resource packs and data packs, zipped from a directory instead of being built.
+
+use anyhow::anyhow;
+use ignore::WalkBuilder;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use tracing::{info, warn};
+use zip::write::FileOptions;
+
+use crate::models::project_type::pack::{PackMcmeta, PackType};
+
+pub fn read_pack_mcmeta(source_dir: &Path) -> Result<PackMcmeta, anyhow::Error> {
+    let path = source_dir.join("pack.mcmeta");
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(mcmeta) => Ok(mcmeta),
+        Err(err) => Err(anyhow!("Failed to parse `{}`: {}", path.display(), err)),
+    }
+}
+
+/// Every Minecraft version of the config has to be supported by `pack.mcmeta`. Versions
+/// without a known format are only warned about.
+pub fn check_pack_format(
+    pack_type: PackType,
+    mcmeta: &PackMcmeta,
+    mc_versions: &[String],
+) -> Result<(), anyhow::Error> {
+    let mut unsupported = vec![];
+
+    for mc_version in mc_versions {
+        match pack_type.pack_format(mc_version) {
+            Some(format) if !mcmeta.pack.supports(format) => {
+                unsupported.push(format!("{} (format {})", mc_version, format))
+            }
+            Some(_) => (),
+            None => warn!(
+                "Unknown {} format for Minecraft {}, not checking it",
+                pack_type.formatted().to_lowercase(),
+                mc_version
+            ),
+        }
+    }
+
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "`pack_format` {} of pack.mcmeta doesn't support {}",
+            mcmeta.pack.pack_format,
+            unsupported.join(", ")
+        ))
+    }
+}
+
+/// Files are added sorted by path with a fixed timestamp, so that zipping the same files
+/// gives the same zip. Hidden and gitignored files are left out, and so is `output_path` when
+/// it's in `source_dir`. Returns the number of files.
+pub fn zip_pack(source_dir: &Path, output_path: &Path) -> Result<usize, anyhow::Error> {
+    let mut paths = vec![];
+
+    for entry in WalkBuilder::new(source_dir).require_git(false).build() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => return Err(anyhow!("Failed to list pack files: {}", err)),
+        };
+
+        if !entry.path().is_file() || entry.path() == output_path {
+            continue;
+        }
+
+        let relative_path = match entry.path().strip_prefix(source_dir) {
+            Ok(path) => path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(err) => {
+                return Err(anyhow!(
+                    "`{}` is outside of the pack: {}",
+                    entry.path().display(),
+                    err
+                ))
+            }
+        };
+
+        paths.push(relative_path);
+    }
+
+    paths.sort();
+
+    let file = match File::create(output_path) {
+        Ok(file) => file,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to create `{}`: {}",
+                output_path.display(),
+                err
+            ))
+        }
+    };
+
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+
+    let mut zip = zip::ZipWriter::new(file);
+
+    for path in &paths {
+        let result = zip
+            .start_file(path.as_str(), options)
+            .map_err(io::Error::from)
+            .and_then(|_| File::open(source_dir.join(path)))
+            .and_then(|mut source| io::copy(&mut source, &mut zip));
+
+        if let Err(err) = result {
+            return Err(anyhow!("Failed to add `{}` to the zip: {}", path, err));
+        }
+    }
+
+    match zip.finish().and_then(|mut file| Ok(file.flush()?)) {
+        Ok(_) => {
+            info!(
+                "Zipped {} files to `{}`",
+                paths.len(),
+                output_path.display()
+            );
+            Ok(paths.len())
+        }
+        Err(err) => Err(anyhow!(
+            "Failed to write `{}`: {}",
+            output_path.display(),
+            err
+        )),
+    }
+}
@@ -0,0 +1,252 @@
+//! `mods.md` and `mods.csv`, the list of mods with their versions that's attached to releases.
+
+use anyhow::anyhow;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+use crate::models::{
+    project_type::modpack::{
+        index::{ModList, ModListEntry, MrpackIndex, PackwizMetafile},
+        PackFile,
+    },
+    util::{OutputFileInfo, TempInfo},
+};
+use crate::pack::read_packwiz_index;
+
+pub const MARKDOWN_FILE_NAME: &str = "mods.md";
+pub const CSV_FILE_NAME: &str = "mods.csv";
+
+/// GitHub rejects release bodies longer than this.
+const MAX_RELEASE_BODY_LENGTH: usize = 125_000;
+
+/// Lists the mods of the packwiz index: metafiles, and jars that are exported as overrides.
+/// Metafiles that can't be parsed are listed by their path, so that the list stays complete.
+pub fn read_mod_list(
+    tmp_dir_info: &TempInfo,
+    pack_file: &PackFile,
+    mrpack_index: &MrpackIndex,
+) -> Result<Vec<ModListEntry>, anyhow::Error> {
+    let index = read_packwiz_index(&tmp_dir_info.dir_path, pack_file)?;
+    let index_dir = match tmp_dir_info.dir_path.join(&pack_file.index.file).parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => return Err(anyhow!("Failed to get packwiz index directory")),
+    };
+
+    let mods_folder = format!(
+        "{}/",
+        pack_file
+            .options
+            .as_ref()
+            .and_then(|options| options.mods_folder.as_deref())
+            .unwrap_or("mods")
+    );
+
+    let mut entries = vec![];
+
+    for file in &index.files {
+        let path = file.alias.as_ref().unwrap_or(&file.file);
+        if !path.starts_with(&mods_folder) {
+            continue;
+        }
+
+        if !file.is_metafile() {
+            if path.ends_with(".jar") {
+                entries.push(override_entry(path));
+            }
+            continue;
+        }
+
+        match read_metafile(&index_dir.join(&file.file)) {
+            Ok(metafile) => entries.push(metafile_entry(metafile, mrpack_index)),
+            Err(err) => {
+                warn!("{}, listing it by its path", err);
+                entries.push(override_entry(path));
+            }
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+
+    Ok(entries)
+}
+
+pub fn read_metafile(path: &Path) -> Result<PackwizMetafile, anyhow::Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    };
+
+    match toml::from_str(&contents) {
+        Ok(metafile) => Ok(metafile),
+        Err(err) => Err(anyhow!("Failed to parse `{}`: {}", path.display(), err)),
+    }
+}
+
+/// Links come from the update section, or a Modrinth download of the same file in the
+/// .mrpack when the mod was added by URL.
+fn metafile_entry(metafile: PackwizMetafile, mrpack_index: &MrpackIndex) -> ModListEntry {
+    let update = metafile.update.as_ref();
+
+    let modrinth_id = update
+        .and_then(|update| update.modrinth.as_ref())
+        .map(|modrinth| modrinth.mod_id.clone())
+        .or_else(|| {
+            mrpack_index
+                .files
+                .iter()
+                .filter(|file| file.file_name() == metafile.filename)
+                .find_map(|file| file.modrinth_project_id())
+        });
+
+    ModListEntry {
+        version: split_file_name(&metafile.filename).1.to_string(),
+        side: metafile.side.unwrap_or_else(|| "both".to_string()),
+        modrinth_url: modrinth_id.map(|id| format!("https://modrinth.com/mod/{}", id)),
+        curseforge_url: update
+            .and_then(|update| update.curseforge.as_ref())
+            .map(|curseforge| {
+                format!(
+                    "https://www.curseforge.com/projects/{}",
+                    curseforge.project_id
+                )
+            }),
+        name: metafile.name,
+    }
+}
+
+/// Jars in the pack itself have nothing but their file name to go by.
+fn override_entry(path: &str) -> ModListEntry {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let (name, version) = split_file_name(file_name.trim_end_matches(".pw.toml"));
+
+    ModListEntry {
+        name: name.to_string(),
+        version: version.to_string(),
+        side: "both".to_string(),
+        modrinth_url: None,
+        curseforge_url: None,
+    }
+}
+
+/// Splits e.g. `sodium-fabric-0.5.8+mc1.20.4.jar` into `sodium-fabric` and `0.5.8+mc1.20.4`
+/// at the first `-` or `_` followed by a digit. The version is empty when there's none.
+fn split_file_name(file_name: &str) -> (&str, &str) {
+    let stem = file_name.trim_end_matches(".jar");
+
+    let separator = stem.char_indices().find(|&(i, c)| {
+        let rest = &stem[i + c.len_utf8()..];
+        let rest = rest.strip_prefix('v').unwrap_or(rest);
+        matches!(c, '-' | '_') && rest.starts_with(|c: char| c.is_ascii_digit())
+    });
+
+    match separator {
+        Some((i, c)) => (&stem[..i], &stem[i + c.len_utf8()..]),
+        None => (stem, ""),
+    }
+}
+
+pub fn mod_list_markdown(entries: &[ModListEntry]) -> String {
+    let escape = |value: &str| value.replace('|', "\\|");
+
+    let mut markdown = "| Name | Version | Side | Links |\n| --- | --- | --- | --- |\n".to_string();
+
+    for entry in entries {
+        let links = [
+            entry
+                .modrinth_url
+                .as_ref()
+                .map(|url| format!("[Modrinth]({})", url)),
+            entry
+                .curseforge_url
+                .as_ref()
+                .map(|url| format!("[CurseForge]({})", url)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(", ");
+
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            escape(&entry.name),
+            escape(&entry.version),
+            entry.side,
+            links
+        ));
+    }
+
+    markdown
+}
+
+pub fn mod_list_csv(entries: &[ModListEntry]) -> String {
+    // Quoted as in RFC 4180 when needed, mod names can contain commas
+    let escape = |value: &str| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut csv = "name,version,side,modrinth_url,curseforge_url\n".to_string();
+
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            escape(&entry.name),
+            escape(&entry.version),
+            escape(&entry.side),
+            entry.modrinth_url.as_deref().unwrap_or_default(),
+            entry.curseforge_url.as_deref().unwrap_or_default()
+        ));
+    }
+
+    csv
+}
+
+/// Writes `mods.md` and `mods.csv` into the temporary directory.
+pub fn write_mod_list(
+    tmp_dir_info: &TempInfo,
+    entries: Vec<ModListEntry>,
+) -> Result<ModList, anyhow::Error> {
+    let mut files = vec![];
+
+    for (file_name, contents) in [
+        (MARKDOWN_FILE_NAME, mod_list_markdown(&entries)),
+        (CSV_FILE_NAME, mod_list_csv(&entries)),
+    ] {
+        let file_path = tmp_dir_info.dir_path.join(file_name);
+
+        if let Err(err) = fs::write(&file_path, contents) {
+            return Err(anyhow!("Failed to write `{}`: {}", file_name, err));
+        }
+
+        files.push(OutputFileInfo {
+            file_name: file_name.to_string(),
+            file_path,
+        });
+    }
+
+    Ok(ModList { entries, files })
+}
+
+/// Appends the mod list to a release body as a collapsed section, unless that would make the
+/// body too long for GitHub.
+pub fn append_to_release_body(body: &mut String, entries: &[ModListEntry]) {
+    let section = format!(
+        "\n\n<details>\n<summary>Mods ({})</summary>\n\n{}\n</details>",
+        entries.len(),
+        mod_list_markdown(entries)
+    );
+
+    if body.len() + section.len() > MAX_RELEASE_BODY_LENGTH {
+        warn!(
+            "The mod list is too long for the release body, it's only attached as `{}`",
+            MARKDOWN_FILE_NAME
+        );
+        return;
+    }
+
+    body.push_str(&section);
+}
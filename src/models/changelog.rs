@@ -0,0 +1,304 @@
+use crate::discord::fit_changelog;
+
+/// GitHub rejects release bodies longer than this.
+pub const GITHUB_CHANGELOG_LIMIT: usize = 125_000;
+/// Modrinth rejects version changelogs longer than this.
+pub const MODRINTH_CHANGELOG_LIMIT: usize = 65_536;
+
+/// A changelog split into its sections, so that every platform gets Markdown it can render.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changelog {
+    /// Everything before the first section, e.g. a list of commits.
+    pub intro: String,
+    pub sections: Vec<ChangelogSection>,
+    /// Compare page of the forge, linked at the end.
+    pub full_changelog_url: Option<String>,
+    /// Appended to the GitHub release body, e.g. a link comparing with the previous release.
+    pub github_footer: Option<String>,
+    /// Appended to the Modrinth changelog, e.g. a link to the previous version.
+    pub modrinth_footer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogSection {
+    pub kind: SectionKind,
+    pub title: String,
+    /// Level of the section's Markdown heading, `###` when it isn't parsed from one.
+    pub level: usize,
+    pub body: String,
+}
+
+/// Ordered as the sections of generated changelogs are.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SectionKind {
+    Added,
+    Changed,
+    Fixed,
+    Removed,
+    ModsUpdated,
+    Other,
+}
+
+impl SectionKind {
+    /// Guesses the kind from a heading, e.g. `Bug fixes` or `Updated mods`.
+    pub fn from_title(title: &str) -> Self {
+        let title = title.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| title.contains(word));
+
+        if has(&["update", "upgrade", "bump"]) {
+            Self::ModsUpdated
+        } else if has(&["fix"]) {
+            Self::Fixed
+        } else if has(&["remove", "delete"]) {
+            Self::Removed
+        } else if has(&["add", "new", "feature"]) {
+            Self::Added
+        } else if has(&["change", "improve", "tweak"]) {
+            Self::Changed
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl ChangelogSection {
+    pub fn new(title: &str, body: String) -> Self {
+        Self {
+            kind: SectionKind::from_title(title),
+            title: title.to_string(),
+            level: 3,
+            body,
+        }
+    }
+}
+
+impl Changelog {
+    pub fn from_intro(intro: String) -> Self {
+        Self {
+            intro,
+            ..Default::default()
+        }
+    }
+
+    /// Splits Markdown at its highest level of headings. A trailing `[Full Changelog](...)`
+    /// line becomes the full changelog link.
+    pub fn parse(markdown: &str) -> Self {
+        let mut lines = markdown.trim().lines().collect::<Vec<&str>>();
+
+        let mut full_changelog_url = None;
+        if let Some(url) = lines.last().and_then(|line| {
+            line.trim()
+                .strip_prefix("[Full Changelog](")
+                .and_then(|rest| rest.strip_suffix(')'))
+        }) {
+            full_changelog_url = Some(url.to_string());
+            lines.pop();
+        }
+
+        let headings = heading_lines(&lines);
+        let level = headings.iter().map(|(_, level, _)| *level).min();
+
+        let mut intro = vec![];
+        let mut sections: Vec<(ChangelogSection, Vec<&str>)> = vec![];
+
+        for (index, line) in lines.iter().enumerate() {
+            let heading = headings.iter().find(|(heading_index, heading_level, _)| {
+                *heading_index == index && Some(*heading_level) == level
+            });
+
+            match (heading, sections.last_mut()) {
+                (Some((_, level, title)), _) => {
+                    let mut section = ChangelogSection::new(title, String::new());
+                    section.level = *level;
+                    sections.push((section, vec![]));
+                }
+                (None, Some((_, body))) => body.push(line),
+                (None, None) => intro.push(*line),
+            }
+        }
+
+        Self {
+            intro: intro.join("\n").trim().to_string(),
+            sections: sections
+                .into_iter()
+                .map(|(mut section, body)| {
+                    section.body = body.join("\n").trim().to_string();
+                    section
+                })
+                .collect(),
+            full_changelog_url,
+            ..Default::default()
+        }
+    }
+
+    /// Full Markdown, as it was written.
+    pub fn render_github(&self) -> String {
+        let markdown = self
+            .render_markdown(|section| format!("{} {}", "#".repeat(section.level), section.title));
+
+        self.with_footer(
+            &markdown,
+            self.github_footer.as_deref(),
+            GITHUB_CHANGELOG_LIMIT,
+        )
+    }
+
+    /// Markdown without `<details>` blocks, which Modrinth shows with their tags.
+    pub fn render_modrinth(&self) -> String {
+        let markdown = self
+            .render_markdown(|section| format!("{} {}", "#".repeat(section.level), section.title));
+
+        self.with_footer(
+            &flatten_details(&markdown),
+            self.modrinth_footer.as_deref(),
+            MODRINTH_CHANGELOG_LIMIT,
+        )
+    }
+
+    /// The subset Discord embeds render: headings become bold text and tables become lists.
+    /// Cut to `limit`, linking `full_changelog_url` when it's cut.
+    pub fn render_discord(&self, limit: usize, full_changelog_url: Option<&str>) -> String {
+        let markdown = self.render_markdown(|section| format!("**{}**", section.title));
+
+        // Code blocks are left as they are
+        let mut in_code_block = false;
+        let lines = flatten_details(&markdown)
+            .lines()
+            .filter_map(|line| {
+                if line.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                    return Some(line.to_string());
+                }
+                match in_code_block {
+                    true => Some(line.to_string()),
+                    false => discord_line(line),
+                }
+            })
+            .collect::<Vec<String>>();
+
+        fit_changelog(
+            &lines.join("\n"),
+            limit,
+            full_changelog_url.or(self.full_changelog_url.as_deref()),
+        )
+    }
+
+    /// The footer is kept when the rest has to be cut to fit.
+    fn with_footer(&self, markdown: &str, footer: Option<&str>, limit: usize) -> String {
+        let footer = match footer {
+            Some(footer) => format!("\n\n{}", footer),
+            None => String::new(),
+        };
+
+        let markdown = fit_changelog(
+            markdown,
+            limit.saturating_sub(footer.chars().count()),
+            self.full_changelog_url.as_deref(),
+        );
+
+        format!("{}{}", markdown, footer).trim_start().to_string()
+    }
+
+    fn render_markdown(&self, heading: impl Fn(&ChangelogSection) -> String) -> String {
+        let mut parts = vec![];
+
+        if !self.intro.is_empty() {
+            parts.push(self.intro.clone());
+        }
+        for section in &self.sections {
+            match section.body.is_empty() {
+                true => parts.push(heading(section)),
+                false => parts.push(format!("{}\n{}", heading(section), section.body)),
+            }
+        }
+        if let Some(url) = &self.full_changelog_url {
+            parts.push(format!("[Full Changelog]({})", url));
+        }
+
+        parts.join("\n\n")
+    }
+}
+
+/// Index, level and title of every heading outside of code blocks.
+fn heading_lines(lines: &[&str]) -> Vec<(usize, usize, String)> {
+    let mut headings = vec![];
+    let mut in_code_block = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if level > 0 && line[level..].starts_with(' ') {
+            headings.push((index, level, line[level..].trim().to_string()));
+        }
+    }
+
+    headings
+}
+
+/// `<summary>` becomes bold text, the `<details>` tags around it are dropped.
+fn flatten_details(markdown: &str) -> String {
+    markdown
+        .lines()
+        .filter_map(|line| {
+            let trimmed = line.trim();
+
+            if trimmed == "<details>" || trimmed == "</details>" {
+                return None;
+            }
+            match trimmed
+                .strip_prefix("<summary>")
+                .and_then(|rest| rest.strip_suffix("</summary>"))
+            {
+                Some(summary) => Some(format!("**{}**", summary)),
+                None => Some(line.to_string()),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Headings left in section bodies become bold, table rows list items.
+fn discord_line(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level > 0 && trimmed[level..].starts_with(' ') {
+        return Some(format!("**{}**", trimmed[level..].trim()));
+    }
+
+    if trimmed.starts_with('|') && trimmed.ends_with('|') {
+        // Escaped pipes are part of a cell
+        let row = trimmed.replace("\\|", "\u{0}");
+        let cells = row
+            .trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim().replace('\u{0}', "|"))
+            .collect::<Vec<String>>();
+
+        // The `| --- |` row under the header
+        if cells
+            .iter()
+            .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':')))
+        {
+            return None;
+        }
+
+        return Some(format!(
+            "- {}",
+            cells
+                .into_iter()
+                .filter(|cell| !cell.is_empty())
+                .collect::<Vec<String>>()
+                .join(" · ")
+        ));
+    }
+
+    Some(line.to_string())
+}
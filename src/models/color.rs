@@ -0,0 +1,213 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, NumberValidation, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// An RGB color, written in configs as an integer like `0x1F8FE2` or as a string like
+/// `"#1F8FE2"`, `"1F8FE2"` or `"0x1F8FE2"` copied from a color picker.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Color(pub u32);
+
+impl Color {
+    pub const MAX: u32 = 0xFFFFFF;
+
+    pub fn from_u64(value: u64) -> Result<Self, String> {
+        match u32::try_from(value) {
+            Ok(value) if value <= Self::MAX => Ok(Self(value)),
+            _ => Err(format!(
+                "color {:#X} is out of range, it must be from 0x000000 to 0xFFFFFF",
+                value
+            )),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim();
+        let hex = trimmed
+            .strip_prefix('#')
+            .or_else(|| trimmed.strip_prefix("0x"))
+            .or_else(|| trimmed.strip_prefix("0X"))
+            .unwrap_or(trimmed);
+
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "invalid color `{}`, expected `#RRGGBB`, `RRGGBB` or `0xRRGGBB`",
+                text
+            ));
+        }
+
+        match u32::from_str_radix(hex, 16) {
+            Ok(value) => Ok(Self(value)),
+            Err(err) => Err(format!("invalid color `{}`: {}", text, err)),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "#{:06X}", self.0)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a color as an integer or a `#RRGGBB` string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Color, E> {
+        Color::from_u64(value).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Color, E> {
+        match u64::try_from(value) {
+            Ok(value) => self.visit_u64(value),
+            Err(_) => Err(E::custom(format!(
+                "color {} is out of range, it must be from 0x000000 to 0xFFFFFF",
+                value
+            ))),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Color, E> {
+        Color::parse(value).map_err(E::custom)
+    }
+}
+
+impl JsonSchema for Color {
+    fn schema_name() -> String {
+        "Color".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let integer = SchemaObject {
+            instance_type: Some(InstanceType::Integer.into()),
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(0.0),
+                maximum: Some(f64::from(Self::MAX)),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        let string = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some("^(#|0[xX])?[0-9a-fA-F]{6}$".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        let mut schema = SchemaObject::default();
+        schema.metadata().description =
+            Some("An RGB color, e.g. `0x1F8FE2` or `\"#1F8FE2\"`.".to_string());
+        schema.subschemas().any_of = Some(vec![integer.into(), string.into()]);
+        schema.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Config {
+        color: Color,
+    }
+
+    fn parse_color(value: &str) -> Result<Color, String> {
+        toml::from_str::<Config>(&format!("color = {}", value))
+            .map(|config| config.color)
+            .map_err(|err| err.message().to_string())
+    }
+
+    #[test]
+    fn accepts_every_spelling() {
+        for value in [
+            "2068450",
+            "0x1F8FE2",
+            "0x1f8fe2",
+            "\"#1F8FE2\"",
+            "\"#1f8fe2\"",
+            "\"1F8FE2\"",
+            "\"0x1F8FE2\"",
+            "\"0X1F8FE2\"",
+            "\" #1F8FE2 \"",
+        ] {
+            assert_eq!(parse_color(value), Ok(Color(0x1F8FE2)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn accepts_the_whole_range() {
+        assert_eq!(parse_color("0"), Ok(Color(0)));
+        assert_eq!(parse_color("0xFFFFFF"), Ok(Color(Color::MAX)));
+        assert_eq!(parse_color("\"#000000\""), Ok(Color(0)));
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        assert_eq!(
+            parse_color("\"#GGGGGG\""),
+            Err("invalid color `#GGGGGG`, expected `#RRGGBB`, `RRGGBB` or `0xRRGGBB`".to_string())
+        );
+        assert!(parse_color("\"#1F8FE\"").is_err());
+        assert!(parse_color("\"#1F8FE2FF\"").is_err());
+        assert!(parse_color("\"\"").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_integers() {
+        assert_eq!(
+            parse_color("0x1000000"),
+            Err(
+                "color 0x1000000 is out of range, it must be from 0x000000 to 0xFFFFFF".to_string()
+            )
+        );
+        assert_eq!(
+            parse_color("-1"),
+            Err("color -1 is out of range, it must be from 0x000000 to 0xFFFFFF".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_other_types() {
+        let err = parse_color("1.5").unwrap_err();
+
+        assert!(
+            err.contains("a color as an integer or a `#RRGGBB` string"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn serializes_as_hex_string() {
+        let config = Config {
+            color: Color(0x1F8FE2),
+        };
+
+        assert_eq!(toml::to_string(&config).unwrap(), "color = \"#1F8FE2\"\n");
+        assert_eq!(
+            serde_json::to_value(Color(0xd55de3)).unwrap(),
+            serde_json::json!("#D55DE3")
+        );
+    }
+}
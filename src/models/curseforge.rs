@@ -0,0 +1,72 @@
+use crate::models::modrinth::{version::VersionType, DependencyType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const CURSEFORGE_UPLOAD_API: &str = "https://minecraft.curseforge.com/api";
+pub const CURSEFORGE_URL: &str = "https://www.curseforge.com";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadFileRequest {
+    pub changelog: String,
+    pub changelog_type: String,
+    pub display_name: String,
+    pub game_versions: Vec<u32>,
+    pub release_type: VersionType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relations: Option<UploadRelations>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadFileResponse {
+    pub id: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadRelations {
+    pub projects: Vec<ProjectRelation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ProjectRelation {
+    pub slug: String,
+    #[serde(rename = "type")]
+    pub relation_type: RelationType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RelationType {
+    EmbeddedLibrary,
+    Incompatible,
+    OptionalDependency,
+    RequiredDependency,
+    Tool,
+}
+
+impl From<DependencyType> for RelationType {
+    fn from(dependency_type: DependencyType) -> Self {
+        match dependency_type {
+            DependencyType::Required => Self::RequiredDependency,
+            DependencyType::Optional => Self::OptionalDependency,
+            DependencyType::Incompatible => Self::Incompatible,
+            DependencyType::Embedded => Self::EmbeddedLibrary,
+        }
+    }
+}
+
+/// Minecraft versions and loaders alike, told apart by their type.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameVersion {
+    pub id: u32,
+    #[serde(rename = "gameVersionTypeID")]
+    pub game_version_type_id: u32,
+    pub name: String,
+}
+
+/// `minecraft-1-21` for the Minecraft versions of 1.21, `modloader` for loaders.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameVersionType {
+    pub id: u32,
+    pub slug: String,
+}
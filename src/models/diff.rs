@@ -0,0 +1,80 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Markdown,
+    Json,
+}
+
+/// What changed between two .mrpack files, as printed by `peony diff`.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PackDiff {
+    /// Minecraft and mod loader versions.
+    pub dependencies: Vec<DependencyChange>,
+    pub added: Vec<ModChange>,
+    pub removed: Vec<ModChange>,
+    pub updated: Vec<ModChange>,
+    pub overrides: OverrideChanges,
+}
+
+impl PackDiff {
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.updated.is_empty()
+            && self.overrides.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A file of `modrinth.index.json`. Only the new side is set for added files, and only the
+/// old side for removed ones.
+#[derive(Debug, Serialize, Clone)]
+pub struct ModChange {
+    pub project_id: Option<String>,
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    /// Version numbers from Modrinth, `None` when the file isn't a Modrinth version.
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    #[serde(skip)]
+    pub old_sha1: Option<String>,
+    #[serde(skip)]
+    pub new_sha1: Option<String>,
+}
+
+impl ModChange {
+    pub fn file_name(&self) -> &str {
+        let path = self
+            .new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default();
+
+        match path.rsplit_once('/') {
+            Some((_, name)) => name,
+            None => path,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct OverrideChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl OverrideChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
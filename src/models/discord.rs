@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecuteWebhookRequest {
+    pub content: String,
+    pub embeds: Vec<Embed>,
+    /// Creates a post with this name when the webhook belongs to a forum channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_name: Option<String>,
+    pub allowed_mentions: AllowedMentions,
+    /// Files sent as `files[n]` parts alongside the payload.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Index of the `files[n]` part.
+    pub id: usize,
+    pub filename: String,
+}
+
+/// A file attached to the webhook message.
+#[derive(Debug, Clone)]
+pub struct WebhookFile {
+    pub file_name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AllowedMentions {
+    pub parse: Vec<String>,
+    pub roles: Vec<String>,
+}
+
+// Based on Discord's embed object here:
+// https://discord.com/developers/docs/resources/channel#embed-object
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Embed {
+    pub title: String,
+    pub description: String,
+    pub color: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+    pub footer: EmbedFooter,
+    /// ISO 8601 timestamp, shown by Discord in the user's timezone.
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedImage {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedFooter {
+    pub text: String,
+}
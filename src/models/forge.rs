@@ -0,0 +1,59 @@
+use crate::models::{release::Platform, GiteaConfig, GithubConfig, GitlabConfig};
+
+/// Where the pack's source and releases live, used to build links into the repository.
+#[derive(Debug, Copy, Clone)]
+pub enum Forge<'a> {
+    Github(&'a GithubConfig),
+    Gitlab(&'a GitlabConfig),
+    Gitea(&'a GiteaConfig),
+}
+
+impl Forge<'_> {
+    pub fn platform(&self) -> Platform {
+        match self {
+            Self::Github(_) => Platform::Github,
+            Self::Gitlab(_) => Platform::Gitlab,
+            Self::Gitea(_) => Platform::Gitea,
+        }
+    }
+
+    pub fn web_url(&self) -> String {
+        match self {
+            Self::Github(config) => config.web_url(),
+            Self::Gitlab(config) => config.web_url(),
+            Self::Gitea(config) => config.web_url(),
+        }
+    }
+
+    pub fn tag_name(&self, version: &str) -> String {
+        match self {
+            Self::Github(config) => config.tag_name(version),
+            Self::Gitlab(config) => config.tag_name(version),
+            Self::Gitea(config) => config.tag_name(version),
+        }
+    }
+
+    pub fn release_url(&self, tag_name: &str) -> String {
+        match self {
+            Self::Github(_) | Self::Gitea(_) => {
+                format!("{}/releases/tag/{}", self.web_url(), tag_name)
+            }
+            Self::Gitlab(_) => format!("{}/-/releases/{}", self.web_url(), tag_name),
+        }
+    }
+
+    pub fn commit_url(&self, hash: &str) -> String {
+        match self {
+            Self::Github(_) | Self::Gitea(_) => format!("{}/commit/{}", self.web_url(), hash),
+            Self::Gitlab(_) => format!("{}/-/commit/{}", self.web_url(), hash),
+        }
+    }
+
+    pub fn compare_url(&self, from: &str, to: &str) -> String {
+        match self {
+            Self::Github(_) => format!("{}/compare/{}..{}", self.web_url(), from, to),
+            Self::Gitea(_) => format!("{}/compare/{}...{}", self.web_url(), from, to),
+            Self::Gitlab(_) => format!("{}/-/compare/{}...{}", self.web_url(), from, to),
+        }
+    }
+}
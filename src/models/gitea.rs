@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGiteaReleaseRequest {
+    pub tag_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_commitish: Option<String>,
+    pub name: String,
+    pub body: String,
+    pub prerelease: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteaReleaseResponse {
+    pub id: i64,
+    pub tag_name: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteaAttachment {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+}
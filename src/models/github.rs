@@ -3,12 +3,30 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateReleaseRequest {
     pub tag_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_commitish: Option<String>,
     pub name: Option<String>,
     pub body: Option<String>,
-    pub prerelease: bool
+    pub prerelease: bool,
+    pub draft: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct UpdateReleaseRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub draft: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAssetRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReleaseResponse {
     pub url: String,
     pub html_url: String,
@@ -35,7 +53,7 @@ pub struct ReleaseResponse {
     pub reactions: Option<GithubReactions>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GithubAuthor {
     pub name: Option<String>,
     pub email: Option<String>,
@@ -61,7 +79,7 @@ pub struct GithubAuthor {
     pub starred_at: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GithubAsset {
     pub url: String,
     pub browser_download_url: String,
@@ -78,7 +96,7 @@ pub struct GithubAsset {
     pub uploader: Option<GithubAuthor>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GithubReactions {
     pub url: String,
     pub total_count: i32,
@@ -93,3 +111,40 @@ pub struct GithubReactions {
     pub eyes: i32,
     pub rocket: i32,
 }
+
+// https://docs.github.com/en/rest/search/search#search-issues-and-pull-requests
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchIssuesResponse {
+    pub total_count: u32,
+    pub incomplete_results: bool,
+    pub items: Vec<SearchIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchIssue {
+    pub number: u32,
+    pub title: String,
+    pub html_url: String,
+    pub labels: Vec<IssueLabel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IssueLabel {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommitResponse {
+    pub sha: String,
+    pub commit: CommitDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommitDetails {
+    pub committer: Option<CommitActor>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommitActor {
+    pub date: Option<String>,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGitlabReleaseRequest {
+    pub name: String,
+    pub tag_name: String,
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+    pub description: String,
+    pub assets: GitlabReleaseAssets,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabReleaseAssets {
+    pub links: Vec<GitlabReleaseLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabReleaseLink {
+    pub name: String,
+    pub url: String,
+    pub link_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabReleaseResponse {
+    pub tag_name: String,
+    #[serde(rename = "_links")]
+    pub links: GitlabReleaseResponseLinks,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabReleaseResponseLinks {
+    #[serde(rename = "self")]
+    pub self_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabProject {
+    pub default_branch: String,
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever a field is changed or removed, new optional fields keep the version.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Written after a release for later CI steps, see `--manifest-out`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReleaseManifest {
+    pub manifest_version: u32,
+    pub version_number: String,
+    pub version_name: String,
+    pub file_name: String,
+    pub sha512: String,
+    pub github: Option<GithubManifest>,
+    pub modrinth: Option<ModrinthManifest>,
+    /// RFC 3339 timestamps of when the release started and finished.
+    pub started_at: String,
+    pub finished_at: String,
+    pub changelog: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GithubManifest {
+    pub url: String,
+    pub tag_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModrinthManifest {
+    /// Not known if the created version couldn't be read from Modrinth's response.
+    pub version_id: Option<String>,
+    pub url: String,
+}
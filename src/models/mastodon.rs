@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::MastodonVisibility;
+
+// Based on Mastodon's post a new status endpoint here:
+// https://docs.joinmastodon.org/methods/statuses/#create
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateStatusRequest {
+    pub status: String,
+    pub visibility: MastodonVisibility,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub id: String,
+    pub url: Option<String>,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+// Based on the m.room.message event of the client-server API here:
+// https://spec.matrix.org/latest/client-server-api/#mroommessage
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoomMessage {
+    /// Always `m.text`.
+    pub msgtype: String,
+    /// Plain text, for clients that can't render `formatted_body`.
+    pub body: String,
+    /// Always `org.matrix.custom.html`.
+    pub format: String,
+    pub formatted_body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendEventResponse {
+    pub event_id: String,
+}
@@ -1,33 +1,1212 @@
+use crate::models::{
+    color::Color,
+    modrinth::{version::VersionType, Loader, ModrinthUrl},
+    project_type::{
+        mc_mod::config::modrinth::{ModrinthDependency, DEFAULT_MOD_IDS},
+        modpack::PackFile,
+    },
+};
+use crate::redact::Secret;
+use crate::util::{join_url, read_secret_env, read_token};
+use crate::version::render_template;
+use anyhow::anyhow;
+use clap::ValueEnum;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
 
+pub mod changelog;
+pub mod color;
+pub mod curseforge;
+pub mod diff;
+pub mod discord;
+pub mod forge;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+pub mod manifest;
+pub mod mastodon;
+pub mod matrix;
 pub mod modrinth;
+pub mod outdated;
 pub mod project_type;
+pub mod release;
+pub mod slack;
 pub mod util;
 pub mod version;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct GithubConfig {
     pub repo_owner: String,
     pub repo_name: String,
+    pub enabled: Option<bool>,
+    pub prerelease: Option<PrereleaseMode>,
+    /// Tag name of releases, `{version}` is replaced with the version. Defaults to `{version}`.
+    pub tag_format: Option<String>,
+    /// Branch the release tag is created on, defaults to the repository's default branch.
+    pub target_commitish: Option<String>,
+    /// Leave releases as drafts to publish them manually.
+    pub draft: Option<bool>,
+    /// Name of the environment variable holding the GitHub token, `GITHUB_TOKEN` and then
+    /// `GH_TOKEN` are tried when not set.
+    #[serde(alias = "token_env_var")]
+    pub token_env: Option<String>,
+    /// For GitHub Enterprise Server, e.g. `https://github.example.com/api/v3`.
+    pub api_base_url: Option<String>,
+    /// For GitHub Enterprise Server, e.g. `https://github.example.com/api/uploads`.
+    pub upload_base_url: Option<String>,
+    /// Used for links to the repository, e.g. `https://github.example.com`.
+    pub web_base_url: Option<String>,
+    /// What to do when a release with the same tag already exists.
+    pub on_existing: Option<OnExisting>,
+    /// Attach `mods.md` and `mods.csv` listing the mods of the pack, true by default. Only
+    /// used for modpacks.
+    pub mod_list: Option<bool>,
+    /// Paste the mod list into a collapsed section of the release body, false by default.
+    pub mod_list_in_release_body: Option<bool>,
 }
 
-// Eventually should be moved to models::project_type::modpack::config
-// unnecessary for now, but should be moved before project types other than modpacks
-// and mods are implemented.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl GithubConfig {
+    pub fn on_existing(&self) -> OnExisting {
+        self.on_existing.unwrap_or(OnExisting::Fail)
+    }
+
+    pub fn mod_list(&self) -> bool {
+        self.mod_list.unwrap_or(true)
+    }
+
+    pub fn mod_list_in_release_body(&self) -> bool {
+        self.mod_list_in_release_body.unwrap_or(false)
+    }
+
+    pub fn api_url(&self) -> String {
+        join_url(
+            self.api_base_url
+                .as_deref()
+                .unwrap_or("https://api.github.com"),
+            &format!("repos/{}/{}", self.repo_owner, self.repo_name),
+        )
+    }
+
+    pub fn upload_url(&self) -> String {
+        join_url(
+            self.upload_base_url
+                .as_deref()
+                .unwrap_or("https://uploads.github.com"),
+            &format!("repos/{}/{}", self.repo_owner, self.repo_name),
+        )
+    }
+
+    pub fn search_url(&self) -> String {
+        join_url(
+            self.api_base_url
+                .as_deref()
+                .unwrap_or("https://api.github.com"),
+            "search/issues",
+        )
+    }
+
+    pub fn web_url(&self) -> String {
+        join_url(
+            self.web_base_url.as_deref().unwrap_or("https://github.com"),
+            &format!("{}/{}", self.repo_owner, self.repo_name),
+        )
+    }
+
+    pub fn token_env_vars(&self) -> Vec<String> {
+        match &self.token_env {
+            Some(env_var) => vec![env_var.clone()],
+            None => vec!["GITHUB_TOKEN".to_string(), "GH_TOKEN".to_string()],
+        }
+    }
+
+    pub fn token(&self) -> Result<Secret, anyhow::Error> {
+        read_token(&self.token_env_vars())
+    }
+
+    pub fn is_draft(&self) -> bool {
+        self.draft.unwrap_or(false)
+    }
+
+    pub fn tag_name(&self, version: &str) -> String {
+        match &self.tag_format {
+            Some(format) => format.replace("{version}", version),
+            None => version.to_string(),
+        }
+    }
+
+    /// Follows the rules of `git check-ref-format`, as GitHub can't create tags git rejects.
+    pub fn check_tag_name(&self, version: &str) -> Result<(), anyhow::Error> {
+        let tag_name = self.tag_name(version);
+
+        let is_valid = !tag_name.is_empty()
+            && !tag_name.starts_with('/')
+            && !tag_name.ends_with('/')
+            && !tag_name.ends_with('.')
+            && !tag_name.ends_with(".lock")
+            && !tag_name.contains("..")
+            && !tag_name.contains("//")
+            && !tag_name.contains("@{")
+            && tag_name != "@"
+            && !tag_name.split('/').any(|part| part.starts_with('.'))
+            && !tag_name.chars().any(|c| {
+                c.is_ascii_control() || matches!(c, ' ' | '~' | '^' | ':' | '?' | '*' | '[' | '\\')
+            });
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(anyhow!("`{}` isn't a valid git tag name", tag_name))
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Falls back to the version type when `prerelease` isn't configured, so that the
+    /// GitHub release stays consistent with the Modrinth version.
+    pub fn is_prerelease(&self, version: &str, version_type: VersionType) -> bool {
+        match self.prerelease {
+            Some(mode) => mode.is_prerelease(version),
+            None => !matches!(version_type, VersionType::Release),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnExisting {
+    Fail,
+    /// Replace the body and upload the assets again, so that re-runs are idempotent.
+    Update,
+    Skip,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PrereleaseMode {
+    Always,
+    Never,
+    Auto,
+}
+
+impl PrereleaseMode {
+    pub fn is_prerelease(&self, version: &str) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // Build metadata (`1.0.0+build5`) isn't a pre-release, and non-semver
+            // versions are never treated as one.
+            Self::Auto => match semver::Version::parse(version) {
+                Ok(version) => !version.pre.is_empty(),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct GitlabConfig {
+    /// Defaults to `https://gitlab.com`, set it for self-hosted instances.
+    pub base_url: Option<String>,
+    /// Full path of the project, e.g. `lilydev/my-pack`.
+    pub project_id: String,
+    /// Name of the environment variable holding the GitLab access token.
+    pub token_env: Option<String>,
+    pub enabled: Option<bool>,
+    /// Tag name of releases, `{version}` is replaced with the version. Defaults to `{version}`.
+    pub tag_format: Option<String>,
+    /// Branch the release tag is created from, defaults to the project's default branch.
+    pub ref_name: Option<String>,
+}
+
+impl GitlabConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "GITLAB_TOKEN".to_string())
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .clone()
+            .unwrap_or_else(|| "https://gitlab.com".to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    pub fn tag_name(&self, version: &str) -> String {
+        match &self.tag_format {
+            Some(format) => format.replace("{version}", version),
+            None => version.to_string(),
+        }
+    }
+
+    /// The project path has to be URL-encoded to be used as an ID in the API.
+    pub fn api_url(&self) -> String {
+        format!(
+            "{}/api/v4/projects/{}",
+            self.base_url(),
+            self.project_id.replace('/', "%2F")
+        )
+    }
+
+    pub fn web_url(&self) -> String {
+        format!("{}/{}", self.base_url(), self.project_id)
+    }
+}
+
+/// Also used for Forgejo, which implements the same API.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct GiteaConfig {
+    /// URL of the instance, e.g. `https://codeberg.org`.
+    pub base_url: String,
+    pub owner: String,
+    pub repo: String,
+    /// Name of the environment variable holding the Gitea access token.
+    pub token_env: Option<String>,
+    pub enabled: Option<bool>,
+    /// Tag name of releases, `{version}` is replaced with the version. Defaults to `{version}`.
+    pub tag_format: Option<String>,
+    /// Branch the release tag is created on, defaults to the repository's default branch.
+    pub target_commitish: Option<String>,
+}
+
+impl GiteaConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "GITEA_TOKEN".to_string())
+    }
+
+    pub fn tag_name(&self, version: &str) -> String {
+        match &self.tag_format {
+            Some(format) => format.replace("{version}", version),
+            None => version.to_string(),
+        }
+    }
+
+    pub fn api_url(&self) -> String {
+        format!(
+            "{}/api/v1/repos/{}/{}",
+            self.base_url.trim_end_matches('/'),
+            self.owner,
+            self.repo
+        )
+    }
+
+    pub fn web_url(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.base_url.trim_end_matches('/'),
+            self.owner,
+            self.repo
+        )
+    }
+}
+
+// Shared by every project type, `mod_ids` is only read by mods
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ModrinthConfig {
     pub project_id: String,
     pub staging: Option<bool>,
+    /// API to use instead of Modrinth's, e.g. a local Labrinth. `staging` only picks the site
+    /// that's linked when it's set.
+    pub api_base_url: Option<String>,
+    /// Name of the environment variable holding the Modrinth token.
+    #[serde(alias = "token_env_var")]
+    pub token_env: Option<String>,
+    pub enabled: Option<bool>,
+    pub version_type: Option<VersionType>,
+    #[serde(alias = "dependency")]
+    pub dependencies: Option<Vec<ModrinthDependency>>,
+    /// Defaults to the loaders in pack.toml's `versions` table.
+    pub loaders: Option<Vec<Loader>>,
+    /// Defaults to the Minecraft version in pack.toml.
+    pub game_versions: Option<Vec<String>>,
+    /// Modrinth project IDs of the mod IDs a mod jar's metadata depends on, added to the
+    /// defaults of [`DEFAULT_MOD_IDS`].
+    pub mod_ids: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl ModrinthConfig {
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "MODRINTH_TOKEN".to_string())
+    }
+
+    pub fn token(&self) -> Result<Secret, anyhow::Error> {
+        read_token(&[self.token_env()])
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn url(&self) -> ModrinthUrl {
+        let mut url = ModrinthUrl::new(&self.staging);
+        if let Some(api_base_url) = &self.api_base_url {
+            url.labrinth = api_base_url.trim_end_matches('/').to_string();
+        }
+        url
+    }
+
+    pub fn mod_ids(&self) -> HashMap<String, String> {
+        let mut mod_ids = DEFAULT_MOD_IDS
+            .iter()
+            .map(|(mod_id, project_id)| (mod_id.to_string(), project_id.to_string()))
+            .collect::<HashMap<String, String>>();
+        mod_ids.extend(self.mod_ids.clone().unwrap_or_default());
+
+        mod_ids
+    }
+
+    pub fn game_versions_for(&self, pack_file: &PackFile) -> Vec<String> {
+        match &self.game_versions {
+            Some(versions) => versions.clone(),
+            None => vec![pack_file.versions.minecraft.clone()],
+        }
+    }
+
+    pub fn loaders_for(&self, pack_file: &PackFile) -> Vec<Loader> {
+        match &self.loaders {
+            Some(loaders) => loaders.clone(),
+            None => pack_file.versions.loaders(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogSource {
+    /// A link comparing the latest GitHub release with HEAD.
+    Github,
+    /// A list of the commits made since the previous tag.
+    Git,
+    /// Mods added, removed and updated since the previous release's .mrpack.
+    Index,
+    /// The section of a Keep a Changelog style file matching the version.
+    File,
+    /// The pull requests merged since the latest GitHub release, grouped by their labels.
+    #[serde(rename = "github-prs")]
+    GithubPrs,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionSource {
+    /// The version in pack.toml.
+    Pack,
+    /// The latest tag reachable from HEAD.
+    GitTag,
+    /// An environment variable, e.g. `GITHUB_REF_NAME`.
+    Env,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct VersionConfig {
+    pub source: Option<VersionSource>,
+    /// Stripped from git tags, defaults to `v`.
+    pub tag_prefix: Option<String>,
+    /// Environment variable read by the `env` source.
+    pub env_var: Option<String>,
+}
+
+impl VersionConfig {
+    pub fn source(&self) -> VersionSource {
+        self.source.unwrap_or(VersionSource::Pack)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ChangelogFormat {
+    Github,
+    Modrinth,
+    Discord,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ChangelogConfig {
+    pub source: Option<ChangelogSource>,
+    /// Regex patterns, commits with a matching subject are left out of the changelog.
+    pub exclude: Option<Vec<String>>,
+    /// Used when there are no commits to list, and for the first release instead of
+    /// the full history.
+    pub default_message: Option<String>,
+    /// Changelog file used by the `file` source, relative to the project directory.
+    pub path: Option<String>,
+    /// Use the `Unreleased` section when the file has none for the version.
+    pub allow_unreleased: Option<bool>,
+    /// Section of the `github-prs` source that pull requests with a label are listed in,
+    /// e.g. `bug = "Fixed"`.
+    pub labels: Option<BTreeMap<String, String>>,
+    /// Appended to GitHub release bodies, with `{previous_tag}`, `{tag}` and `{compare_url}`
+    /// placeholders. Left out when empty, or when there's no previous release.
+    pub footer: Option<String>,
+    /// Appended to Modrinth changelogs, with `{previous_version}` and
+    /// `{previous_version_url}` placeholders. Left out like `footer`.
+    pub modrinth_footer: Option<String>,
+}
+
+pub const CHANGELOG_FOOTER_PLACEHOLDERS: [&str; 3] = ["previous_tag", "tag", "compare_url"];
+pub const MODRINTH_FOOTER_PLACEHOLDERS: [&str; 2] = ["previous_version", "previous_version_url"];
+
+impl ChangelogConfig {
+    pub fn source(&self) -> ChangelogSource {
+        self.source.unwrap_or(ChangelogSource::Github)
+    }
+
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        match &self.labels {
+            Some(labels) => labels.clone(),
+            None => [
+                ("enhancement", "Added"),
+                ("bug", "Fixed"),
+                ("bugfix", "Fixed"),
+                ("mods", "Mods updated"),
+            ]
+            .into_iter()
+            .map(|(label, section)| (label.to_string(), section.to_string()))
+            .collect(),
+        }
+    }
+
+    pub fn footer(&self) -> Option<String> {
+        match &self.footer {
+            Some(footer) if footer.is_empty() => None,
+            Some(footer) => Some(footer.clone()),
+            None => Some("Full diff: [{previous_tag}...{tag}]({compare_url})".to_string()),
+        }
+    }
+
+    pub fn modrinth_footer(&self) -> Option<String> {
+        match &self.modrinth_footer {
+            Some(footer) if footer.is_empty() => None,
+            Some(footer) => Some(footer.clone()),
+            None => {
+                Some("Previous version: [{previous_version}]({previous_version_url})".to_string())
+            }
+        }
+    }
+
+    /// Checks the footer templates with placeholder values, so that typos fail before the
+    /// release.
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        if let Some(footer) = self.footer() {
+            let placeholders = CHANGELOG_FOOTER_PLACEHOLDERS.map(|key| (key, ""));
+            render_template("changelog.footer", &footer, &placeholders)?;
+        }
+        if let Some(footer) = self.modrinth_footer() {
+            let placeholders = MODRINTH_FOOTER_PLACEHOLDERS.map(|key| (key, ""));
+            render_template("changelog.modrinth_footer", &footer, &placeholders)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CurseforgeConfig {
+    pub project_id: u32,
+    /// Name of the environment variable holding the CurseForge API token.
+    pub token_env: Option<String>,
+    /// CurseForge game version ids, as listed by `/api/game/versions` of the upload API.
+    pub game_versions: Vec<u32>,
+    pub enabled: Option<bool>,
+}
+
+impl CurseforgeConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "CURSEFORGE_TOKEN".to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DiscordConfig {
     pub github_emoji_id: String,
     pub modrinth_emoji_id: String,
+    pub curseforge_emoji_id: Option<String>,
+    pub gitlab_emoji_id: Option<String>,
+    pub gitea_emoji_id: Option<String>,
     pub discord_ping_role: String,
     pub title_emoji: String,
     pub embed_image_url: Option<String>,
+    #[serde(alias = "embed_thumbnail_url")]
     pub thumbnail_image_url: Option<String>,
-    pub embed_color: Option<u32>,
+    pub embed_color: Option<Color>,
+    /// Defaults to a single webhook read from `WEBHOOK_URL`.
+    pub webhooks: Option<Vec<DiscordWebhook>>,
+    /// Name of the forum post to create, `{version}` is replaced with the version name.
+    pub thread_name: Option<String>,
+    /// Existing thread to post into.
+    pub thread_id: Option<u64>,
+    /// Allow the message to ping `@everyone` and `@here`.
+    pub everyone: Option<bool>,
+    /// Show the version details as embed fields instead of in the description.
+    pub show_fields: Option<bool>,
+    /// Show the Maven coordinates of mods in a code block, for developers depending on them.
+    pub show_maven: Option<bool>,
+    /// Markdown of the embed description, with the placeholders of
+    /// [`DISCORD_DESCRIPTION_PLACEHOLDERS`]. Lines with the URL of a platform the release
+    /// isn't on are left out, as are lines of only empty placeholders.
+    pub description_template: Option<String>,
+    /// Attach the full changelog as `CHANGELOG-<version>.md` when it's cut to fit the embed.
+    pub attach_changelog: Option<bool>,
+    /// Text of the embed footer, with the placeholders of [`DISCORD_FOOTER_PLACEHOLDERS`].
+    /// Defaults to the project type and the version type.
+    pub footer_text: Option<String>,
+    /// Put the release time in UTC in the default footer, as Discord shows the embed's
+    /// timestamp in the viewer's time zone next to it otherwise.
+    pub legacy_footer_time: Option<bool>,
+}
+
+pub const DISCORD_FOOTER_PLACEHOLDERS: [&str; 4] =
+    ["project_type", "version_type", "version", "release_time"];
+
+pub const DISCORD_DESCRIPTION_PLACEHOLDERS: [&str; 22] = [
+    "version",
+    "version_number",
+    "version_type",
+    "mc_version",
+    "loaders",
+    "pack_name",
+    "release_info",
+    "github_emoji",
+    "github_url",
+    "gitlab_emoji",
+    "gitlab_url",
+    "gitea_emoji",
+    "gitea_url",
+    "modrinth_emoji",
+    "modrinth_url",
+    "curseforge_emoji",
+    "curseforge_url",
+    "maven",
+    "downloads",
+    "server_pack",
+    "server_pack_url",
+    "changelog",
+];
+
+pub const DEFAULT_DISCORD_DESCRIPTION_TEMPLATE: &str = "**New release!**
+
+{release_info}
+
+{github_emoji} [GitHub]({github_url})
+{gitlab_emoji} [GitLab]({gitlab_url})
+{gitea_emoji} [Gitea]({gitea_url})
+{modrinth_emoji} [Modrinth]({modrinth_url})
+{curseforge_emoji} [CurseForge]({curseforge_url})
+
+{maven}
+
+{downloads}
+
+{server_pack}
+
+{changelog}";
+
+impl DiscordConfig {
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        if self.thread_name.is_some() && self.thread_id.is_some() {
+            return Err(anyhow!(
+                "Only one of `discord.thread_name` and `discord.thread_id` can be set"
+            ));
+        }
+
+        let placeholders = DISCORD_DESCRIPTION_PLACEHOLDERS.map(|key| (key, ""));
+        render_template(
+            "discord.description_template",
+            &self.description_template(),
+            &placeholders,
+        )?;
+        if let Some(footer_text) = &self.footer_text {
+            let placeholders = DISCORD_FOOTER_PLACEHOLDERS.map(|key| (key, ""));
+            render_template("discord.footer_text", footer_text, &placeholders)?;
+        }
+
+        // Discord renders a broken embed for invalid image urls instead of rejecting them
+        for (key, url) in [
+            ("embed_image_url", &self.embed_image_url),
+            ("thumbnail_image_url", &self.thumbnail_image_url),
+        ] {
+            if let Some(url) = url {
+                match reqwest::Url::parse(url) {
+                    Ok(parsed) if parsed.scheme() == "https" || parsed.scheme() == "http" => (),
+                    _ => {
+                        return Err(anyhow!(
+                            "`discord.{}` must be an http(s) url, got `{}`",
+                            key,
+                            url
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn description_template(&self) -> String {
+        self.description_template
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DISCORD_DESCRIPTION_TEMPLATE.to_string())
+    }
+
+    pub fn thread_name(&self, version_name: &str) -> Option<String> {
+        self.thread_name
+            .as_ref()
+            .map(|name| name.replace("{version}", version_name))
+    }
+
+    pub fn webhooks(&self) -> Vec<DiscordWebhook> {
+        match &self.webhooks {
+            Some(webhooks) => webhooks.clone(),
+            None => vec![DiscordWebhook {
+                env_var: "WEBHOOK_URL".to_string(),
+                discord_ping_role: None,
+                embed_color: None,
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SlackConfig {
+    /// Name of the environment variable holding the incoming webhook URL, defaults to
+    /// `SLACK_WEBHOOK_URL`.
+    pub webhook_env: Option<String>,
+    /// Put in front of the version name in the header.
+    pub title_emoji: Option<String>,
+}
+
+impl SlackConfig {
+    pub fn webhook_env(&self) -> String {
+        self.webhook_env
+            .clone()
+            .unwrap_or_else(|| "SLACK_WEBHOOK_URL".to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct NotifyConfig {
+    /// Requests to any HTTP endpoint, sent after every release.
+    pub http: Option<Vec<HttpNotifierConfig>>,
+}
+
+impl NotifyConfig {
+    pub fn http(&self) -> &[HttpNotifierConfig] {
+        self.http.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct HttpNotifierConfig {
+    /// Shown in the summary and logs instead of the URL, which can contain a secret.
+    pub name: Option<String>,
+    pub url: Option<String>,
+    /// Name of the environment variable holding the URL, instead of `url`.
+    pub url_env: Option<String>,
+    /// `POST` by default.
+    pub method: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Rendered with the placeholders in [`HTTP_NOTIFIER_PLACEHOLDERS`], `{{` and `}}` are
+    /// literal braces. No body is sent when it isn't set.
+    pub body: Option<String>,
+    /// `application/json` by default.
+    pub content_type: Option<String>,
+    /// How placeholder values are escaped, `json` by default so that they can be put into
+    /// JSON strings.
+    pub escape: Option<TemplateEscape>,
+}
+
+pub const HTTP_NOTIFIER_PLACEHOLDERS: [&str; 12] = [
+    "version",
+    "version_name",
+    "version_type",
+    "changelog",
+    "file_name",
+    "sha512",
+    "github_url",
+    "gitlab_url",
+    "gitea_url",
+    "modrinth_url",
+    "curseforge_url",
+    "server_pack_url",
+];
+
+impl HttpNotifierConfig {
+    /// `notify.http[<index>]` when there's no name.
+    pub fn name(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("notify.http[{}]", index))
+    }
+
+    pub fn method(&self) -> Result<reqwest::Method, anyhow::Error> {
+        let method = self.method.as_deref().unwrap_or("POST").to_uppercase();
+
+        match reqwest::Method::from_bytes(method.as_bytes()) {
+            Ok(method) => Ok(method),
+            Err(_) => Err(anyhow!("Invalid HTTP method `{}`", method)),
+        }
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_deref().unwrap_or("application/json")
+    }
+
+    pub fn escape(&self) -> TemplateEscape {
+        self.escape.unwrap_or(TemplateEscape::Json)
+    }
+
+    pub fn url(&self) -> Result<Secret, anyhow::Error> {
+        match (&self.url, &self.url_env) {
+            (Some(url), None) => Ok(Secret::new(url.clone())),
+            (None, Some(env_var)) => read_secret_env(env_var),
+            _ => Err(anyhow!("Exactly one of `url` and `url_env` must be set")),
+        }
+    }
+
+    pub fn check(&self, index: usize) -> Result<(), anyhow::Error> {
+        let context = |err: anyhow::Error| anyhow!("`{}`: {}", self.name(index), err);
+
+        if self.url.is_some() == self.url_env.is_some() {
+            return Err(context(anyhow!(
+                "Exactly one of `url` and `url_env` must be set"
+            )));
+        }
+        self.method().map_err(context)?;
+
+        for name in self.headers.iter().flat_map(|headers| headers.keys()) {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(context(anyhow!("Invalid header name `{}`", name)));
+            }
+        }
+
+        if let Some(body) = &self.body {
+            let placeholders = HTTP_NOTIFIER_PLACEHOLDERS.map(|key| (key, ""));
+            render_template("notify.http body", body, &placeholders).map_err(context)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEscape {
+    Json,
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct MastodonConfig {
+    /// e.g. `https://mastodon.social`.
+    pub instance_url: String,
+    /// Name of the environment variable holding the access token, which needs the
+    /// `write:statuses` scope. Defaults to `MASTODON_TOKEN`.
+    pub token_env: Option<String>,
+    pub visibility: Option<MastodonVisibility>,
+    /// Text of the post, with `{version}`, `{modrinth_url}` and `{changelog_summary}`
+    /// placeholders.
+    pub template: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+pub const MASTODON_PLACEHOLDERS: [&str; 3] = ["version", "modrinth_url", "changelog_summary"];
+
+impl MastodonConfig {
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "MASTODON_TOKEN".to_string())
+    }
+
+    pub fn visibility(&self) -> MastodonVisibility {
+        self.visibility.unwrap_or(MastodonVisibility::Public)
+    }
+
+    pub fn template(&self) -> String {
+        self.template.clone().unwrap_or_else(|| {
+            "{version} is out!\n\n{changelog_summary}\n\n{modrinth_url}".to_string()
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Checks the template with placeholder values, so that typos fail before the release.
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        let placeholders = MASTODON_PLACEHOLDERS.map(|key| (key, ""));
+        render_template("mastodon.template", &self.template(), &placeholders).map(|_| ())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MastodonVisibility {
+    Public,
+    Unlisted,
+    /// Followers only.
+    Private,
+    Direct,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct EmailConfig {
+    /// e.g. `smtp.example.com`.
+    pub host: String,
+    /// Defaults to 587 with `starttls`, 465 with `ssl` and 25 with `none`.
+    pub port: Option<u16>,
+    pub security: Option<SmtpSecurity>,
+    /// Sent without logging in when not set.
+    pub username: Option<String>,
+    /// Name of the environment variable holding the password, defaults to `SMTP_PASSWORD`.
+    pub password_env: Option<String>,
+    /// e.g. `Peony <releases@example.com>`.
+    pub from: String,
+    pub to: Vec<String>,
+    /// Subject of the email, with `{version}`, `{version_name}` and `{version_type}`
+    /// placeholders.
+    pub subject: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+pub const EMAIL_SUBJECT_PLACEHOLDERS: [&str; 3] = ["version", "version_name", "version_type"];
+
+impl EmailConfig {
+    pub fn security(&self) -> SmtpSecurity {
+        self.security.unwrap_or(SmtpSecurity::Starttls)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.security() {
+            SmtpSecurity::Starttls => 587,
+            SmtpSecurity::Ssl => 465,
+            SmtpSecurity::None => 25,
+        })
+    }
+
+    pub fn password_env(&self) -> String {
+        self.password_env
+            .clone()
+            .unwrap_or_else(|| "SMTP_PASSWORD".to_string())
+    }
+
+    pub fn subject(&self) -> String {
+        self.subject
+            .clone()
+            .unwrap_or_else(|| "{version_name} released".to_string())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Checks the addresses and the subject template, so that typos fail before the release.
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        if self.to.is_empty() {
+            return Err(anyhow!("`email.to` must contain at least one address"));
+        }
+
+        for address in std::iter::once(&self.from).chain(&self.to) {
+            if let Err(err) = address.parse::<lettre::message::Mailbox>() {
+                return Err(anyhow!("Invalid email address `{}`: {}", address, err));
+            }
+        }
+
+        let placeholders = EMAIL_SUBJECT_PLACEHOLDERS.map(|key| (key, ""));
+        render_template("email.subject", &self.subject(), &placeholders).map(|_| ())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrades a plain connection with `STARTTLS`.
+    Starttls,
+    /// TLS from the start, also called SMTPS.
+    Ssl,
+    /// Unencrypted, only for local relays.
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct MatrixConfig {
+    /// e.g. `https://matrix.org`.
+    pub homeserver_url: String,
+    /// Internal ID of the room, e.g. `!abcdefg:matrix.org`, not its alias.
+    pub room_id: String,
+    /// Name of the environment variable holding the access token of the account that posts,
+    /// defaults to `MATRIX_ACCESS_TOKEN`.
+    pub access_token_env: Option<String>,
+    /// Post every release without passing `--matrix`, false by default.
+    pub enabled: Option<bool>,
+}
+
+impl MatrixConfig {
+    pub fn access_token_env(&self) -> String {
+        self.access_token_env
+            .clone()
+            .unwrap_or_else(|| "MATRIX_ACCESS_TOKEN".to_string())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        match reqwest::Url::parse(&self.homeserver_url) {
+            Ok(url) if url.scheme() == "https" || url.scheme() == "http" => (),
+            _ => {
+                return Err(anyhow!(
+                    "`matrix.homeserver_url` must be an http(s) url, got `{}`",
+                    self.homeserver_url
+                ))
+            }
+        }
+
+        if !self.room_id.starts_with('!') {
+            return Err(anyhow!(
+                "`matrix.room_id` must be a room ID starting with `!`, got `{}`",
+                self.room_id
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DiscordWebhook {
+    /// Name of the environment variable holding the webhook URL.
+    pub env_var: String,
+    /// Overrides the ping role of the `[discord]` section for this webhook.
+    pub discord_ping_role: Option<String>,
+    pub embed_color: Option<Color>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct NetworkConfig {
+    pub retries: Option<u32>,
+    pub base_delay_ms: Option<u64>,
+    /// Time limit of API requests.
+    pub timeout_secs: Option<u64>,
+    /// Time limit of requests uploading or downloading files.
+    pub transfer_timeout_secs: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+    /// PEM file with extra root certificates, e.g. of a proxy with a private CA.
+    pub ca_bundle: Option<PathBuf>,
+    /// Skips TLS certificate verification. Only meant for air-gapped test setups.
+    pub danger_accept_invalid_certs: Option<bool>,
+}
+
+impl NetworkConfig {
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(3)
+    }
+
+    pub fn base_delay(&self) -> Duration {
+        Duration::from_millis(self.base_delay_ms.unwrap_or(1000))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(30))
+    }
+
+    pub fn transfer_timeout(&self) -> Duration {
+        Duration::from_secs(self.transfer_timeout_secs.unwrap_or(120))
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs.unwrap_or(10))
+    }
+
+    pub fn danger_accept_invalid_certs(&self) -> bool {
+        self.danger_accept_invalid_certs.unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct PackwizConfig {
+    /// Release tag to download packwiz from, or a commit to build it from with Go.
+    pub version: Option<String>,
+    /// Use the packwiz found on `PATH` even if a version is set.
+    pub system: Option<bool>,
+    /// Repository whose releases packwiz is downloaded from, `packwiz/packwiz` by default.
+    pub repo: Option<String>,
+    /// Expected SHA-256 of the downloaded file, or of the built binary when `version` is a
+    /// commit. Checked instead of the release's checksums file when it's set.
+    pub sha256: Option<String>,
+    /// Run `packwiz refresh` before exporting, defaults to true.
+    pub refresh_before_export: Option<bool>,
+}
+
+impl PackwizConfig {
+    /// The pinned version, unless the system packwiz should be used.
+    pub fn pinned_version(&self) -> Option<&String> {
+        if self.system.unwrap_or(false) {
+            None
+        } else {
+            self.version.as_ref()
+        }
+    }
+
+    pub fn repo(&self) -> String {
+        self.repo
+            .clone()
+            .unwrap_or_else(|| "packwiz/packwiz".to_string())
+    }
+
+    pub fn refresh_before_export(&self) -> bool {
+        self.refresh_before_export.unwrap_or(true)
+    }
+}
+
+/// A second .mrpack without the client-only files, uploaded next to the regular one.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ServerPackConfig {
+    /// Metafiles with one of these `side`s are left out, `["client"]` by default.
+    pub exclude_sides: Option<Vec<String>>,
+    /// Glob patterns for more files to leave out, relative to the packwiz index.
+    pub exclude: Option<Vec<String>>,
+}
+
+impl ServerPackConfig {
+    pub fn exclude_sides(&self) -> Vec<String> {
+        self.exclude_sides
+            .clone()
+            .unwrap_or_else(|| vec!["client".to_string()])
+    }
+}
+
+/// Shell commands run in the project directory. A failing `pre_*` hook aborts the release.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct HooksConfig {
+    pub pre_export: Option<Vec<String>>,
+    pub post_export: Option<Vec<String>>,
+    pub pre_release: Option<Vec<String>>,
+    pub post_release: Option<Vec<String>>,
+    /// Whether a failing `post_release` hook fails the command, `warn` by default. The release
+    /// itself already happened either way.
+    pub post_release_failure: Option<HookFailure>,
+}
+
+impl HooksConfig {
+    pub fn pre_export(&self) -> &[String] {
+        self.pre_export.as_deref().unwrap_or_default()
+    }
+
+    pub fn post_export(&self) -> &[String] {
+        self.post_export.as_deref().unwrap_or_default()
+    }
+
+    pub fn pre_release(&self) -> &[String] {
+        self.pre_release.as_deref().unwrap_or_default()
+    }
+
+    pub fn post_release(&self) -> &[String] {
+        self.post_release.as_deref().unwrap_or_default()
+    }
+
+    pub fn post_release_failure(&self) -> HookFailure {
+        self.post_release_failure.unwrap_or(HookFailure::Warn)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HookFailure {
+    Warn,
+    Fail,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{config_with, test_pack_file};
+
+    const MODRINTH_CONFIG: &str = r#"project_id = "AABBCCDD""#;
+
+    #[test]
+    fn modrinth_tags_default_to_pack_toml() {
+        let pack_file = test_pack_file();
+        let config = config_with::<ModrinthConfig>(MODRINTH_CONFIG, "");
+
+        assert_eq!(config.loaders_for(&pack_file), vec![Loader::Quilt]);
+        assert_eq!(config.game_versions_for(&pack_file), vec!["1.20.2"]);
+    }
+
+    #[test]
+    fn modrinth_tags_from_config_win() {
+        let pack_file = test_pack_file();
+        let config = config_with::<ModrinthConfig>(
+            MODRINTH_CONFIG,
+            "loaders = [\"fabric\", \"quilt\"]\ngame_versions = [\"1.20.1\", \"1.20.2\"]",
+        );
+
+        assert_eq!(
+            config.loaders_for(&pack_file),
+            vec![Loader::Fabric, Loader::Quilt]
+        );
+        assert_eq!(
+            config.game_versions_for(&pack_file),
+            vec!["1.20.1", "1.20.2"]
+        );
+    }
+
+    #[test]
+    fn auto_prerelease_from_semver_pre_release() {
+        assert!(PrereleaseMode::Auto.is_prerelease("1.0.0-rc.1"));
+        assert!(PrereleaseMode::Auto.is_prerelease("1.5.0-beta.2"));
+        assert!(!PrereleaseMode::Auto.is_prerelease("1.0.0"));
+    }
+
+    #[test]
+    fn auto_prerelease_ignores_build_metadata() {
+        assert!(!PrereleaseMode::Auto.is_prerelease("1.0.0+build5"));
+        assert!(PrereleaseMode::Auto.is_prerelease("1.0.0-alpha+build5"));
+    }
+
+    #[test]
+    fn auto_prerelease_of_non_semver_versions_is_never() {
+        assert!(!PrereleaseMode::Auto.is_prerelease("1.0-beta"));
+        assert!(!PrereleaseMode::Auto.is_prerelease("v2"));
+        assert!(!PrereleaseMode::Auto.is_prerelease("Release 3"));
+    }
+
+    #[test]
+    fn always_and_never_ignore_the_version() {
+        assert!(PrereleaseMode::Always.is_prerelease("1.0.0"));
+        assert!(!PrereleaseMode::Never.is_prerelease("1.0.0-rc.1"));
+    }
+
+    #[test]
+    fn prerelease_defaults_to_version_type() {
+        let config: GithubConfig =
+            toml::from_str("repo_owner = \"LilydevMC\"\nrepo_name = \"peony-test\"").unwrap();
+
+        assert!(config.is_prerelease("1.0.0", VersionType::Beta));
+        assert!(!config.is_prerelease("1.0.0-rc.1", VersionType::Release));
+    }
 }
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub mod project;
@@ -33,7 +34,7 @@ impl ModrinthUrl {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Loader {
     Quilt,
@@ -41,6 +42,9 @@ pub enum Loader {
     Neoforge,
     Forge,
     Liteloader,
+    /// Resource packs.
+    Minecraft,
+    Datapack,
 }
 
 impl Loader {
@@ -51,6 +55,8 @@ impl Loader {
             Self::Neoforge => "NeoForge",
             Self::Forge => "Forge",
             Self::Liteloader => "LiteLoader",
+            Self::Minecraft => "Minecraft",
+            Self::Datapack => "Data Pack",
         }
         .to_string()
     }
@@ -80,7 +86,7 @@ pub struct GalleryObject {
     pub ordering: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum DependencyType {
     Required,
@@ -24,6 +24,18 @@ impl ProjectType {
         }
         .to_string()
     }
+
+    pub fn url_segment(&self) -> String {
+        match self {
+            Self::Mod => "mod",
+            Self::Plugin => "plugin",
+            Self::Datapack => "datapack",
+            Self::Shader => "shader",
+            Self::Resourcepack => "resourcepack",
+            Self::Modpack => "modpack",
+        }
+        .to_string()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,11 +1,11 @@
-use clap::ValueEnum;
 use crate::models::{
     modrinth::{DependencyType, Loader},
     project_type::mc_mod::config::modrinth::ModrinthDependency,
 };
+use clap::ValueEnum;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-
 // Based on the `Create Version` schema here:
 // https://docs.modrinth.com/api-spec#tag/versions/operation/createVersion
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +24,55 @@ pub struct VersionRequest {
     pub primary_file: String,
 }
 
+// Based on the `Version` schema here:
+// https://docs.modrinth.com/api-spec#tag/versions/operation/getProjectVersions
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionResponse {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    pub changelog: Option<String>,
+    pub version_type: VersionType,
+    pub date_published: String,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub files: Vec<VersionFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionFile {
+    pub hashes: VersionFileHashes,
+    pub url: String,
+    pub filename: String,
+    pub primary: bool,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionFileHashes {
+    pub sha1: String,
+    pub sha512: String,
+}
+
+// Based on the `Get versions from hashes` schema here:
+// https://docs.modrinth.com/api-spec#tag/version-files/operation/versionsFromHashes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionFilesRequest {
+    pub hashes: Vec<String>,
+    pub algorithm: String,
+}
+
+// Based on the `Latest versions of multiple projects from hashes` schema here:
+// https://docs.modrinth.com/api-spec#tag/version-files/operation/getLatestVersionsFromHashes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionFilesUpdateRequest {
+    pub hashes: Vec<String>,
+    pub algorithm: String,
+    pub loaders: Vec<Loader>,
+    pub game_versions: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VersionDependency {
     pub version_id: Option<String>,
@@ -32,7 +81,7 @@ pub struct VersionDependency {
     pub dependency_type: DependencyType,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, ValueEnum)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, ValueEnum, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum VersionType {
     Release,
@@ -40,6 +89,38 @@ pub enum VersionType {
     Alpha,
 }
 
+impl VersionType {
+    pub fn formatted(&self) -> String {
+        match self {
+            Self::Release => "Release",
+            Self::Beta => "Beta",
+            Self::Alpha => "Alpha",
+        }
+        .to_string()
+    }
+
+    /// Guesses the version type from a pre-release suffix like `-beta.1` or `-alpha`, which
+    /// may follow the Minecraft version as in `1.20.1-2.0.0-beta.1`, defaulting to a release.
+    pub fn from_version(version: &str) -> Self {
+        // Build metadata like `+beta` isn't a pre-release
+        let version = match version.split_once('+') {
+            Some((version, _)) => version,
+            None => version,
+        }
+        .to_lowercase();
+
+        for part in version.split('-').skip(1) {
+            if part.starts_with("alpha") {
+                return Self::Alpha;
+            } else if part.starts_with("beta") {
+                return Self::Beta;
+            }
+        }
+
+        Self::Release
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum VersionStatus {
@@ -59,3 +140,94 @@ impl From<ModrinthDependency> for VersionDependency {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn version_request_matches_create_version_schema() {
+        let dependency = ModrinthDependency {
+            version_id: None,
+            project_id: Some("P7dR8mSH".to_string()),
+            dependency_type: DependencyType::Embedded,
+        };
+        let request = VersionRequest {
+            name: "Pack 1.0.0".to_string(),
+            version_number: "1.0.0".to_string(),
+            changelog: Some("Changes".to_string()),
+            dependencies: vec![dependency.into()],
+            game_versions: vec!["1.20.1".to_string()],
+            version_type: VersionType::Release,
+            loaders: vec![Loader::Quilt],
+            featured: false,
+            requested_status: VersionStatus::Listed,
+            project_id: "AABBCCDD".to_string(),
+            file_parts: vec!["file".to_string()],
+            primary_file: "file".to_string(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "name": "Pack 1.0.0",
+                "version_number": "1.0.0",
+                "changelog": "Changes",
+                "dependencies": [{
+                    "version_id": null,
+                    "project_id": "P7dR8mSH",
+                    "file_name": null,
+                    "dependency_type": "embedded",
+                }],
+                "game_versions": ["1.20.1"],
+                "version_type": "release",
+                "loaders": ["quilt"],
+                "featured": false,
+                "requested_status": "listed",
+                "project_id": "AABBCCDD",
+                "file_parts": ["file"],
+                "primary_file": "file",
+            })
+        );
+    }
+
+    #[test]
+    fn version_type_from_pre_release_suffix() {
+        assert_eq!(VersionType::from_version("1.0.0"), VersionType::Release);
+        assert_eq!(VersionType::from_version("1.0.0-beta.2"), VersionType::Beta);
+        assert_eq!(VersionType::from_version("1.0.0-ALPHA"), VersionType::Alpha);
+        assert_eq!(
+            VersionType::from_version("1.0.0-rc.1"),
+            VersionType::Release
+        );
+    }
+
+    #[test]
+    fn version_type_after_minecraft_version() {
+        assert_eq!(
+            VersionType::from_version("1.20.1-2.0.0-beta.1"),
+            VersionType::Beta
+        );
+        assert_eq!(
+            VersionType::from_version("1.20.1-2.0.0-alpha"),
+            VersionType::Alpha
+        );
+        assert_eq!(
+            VersionType::from_version("1.20.1-2.0.0"),
+            VersionType::Release
+        );
+    }
+
+    #[test]
+    fn version_type_ignores_build_metadata() {
+        assert_eq!(
+            VersionType::from_version("1.0.0+beta"),
+            VersionType::Release
+        );
+        assert_eq!(
+            VersionType::from_version("1.0.0-beta+build.5"),
+            VersionType::Beta
+        );
+    }
+}
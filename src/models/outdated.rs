@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+/// A mod of the pack with a newer Modrinth version for its Minecraft version and loader.
+#[derive(Debug, Serialize, Clone)]
+pub struct OutdatedMod {
+    pub name: String,
+    pub project_id: String,
+    pub current_version: String,
+    pub latest_version: String,
+    /// When the latest version was published, as Modrinth returns it.
+    pub latest_published: String,
+    /// Days since the latest version was published.
+    pub age_days: Option<i64>,
+}
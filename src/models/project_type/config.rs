@@ -0,0 +1,236 @@
+use crate::config_check::{check_not_empty, ConfigProblem};
+use crate::models::modrinth::ModrinthUrl;
+use crate::models::util::default_copy_excludes;
+use crate::models::{
+    ChangelogConfig, DiscordConfig, EmailConfig, GithubConfig, HooksConfig, MastodonConfig,
+    MatrixConfig, ModrinthConfig, NetworkConfig, NotifyConfig, SlackConfig,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Sections that mean the same for modpacks and mods, flattened into both of their configs so
+/// that the files keep their layout.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ProjectConfig {
+    pub github: Option<GithubConfig>,
+    pub modrinth: Option<ModrinthConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub slack: Option<SlackConfig>,
+    pub matrix: Option<MatrixConfig>,
+    pub mastodon: Option<MastodonConfig>,
+    pub notify: Option<NotifyConfig>,
+    pub email: Option<EmailConfig>,
+    pub network: Option<NetworkConfig>,
+    pub hooks: Option<HooksConfig>,
+    pub changelog: Option<ChangelogConfig>,
+    /// Patterns in gitignore syntax for files that aren't copied to the temporary directory
+    /// the project is exported or built in, replacing the defaults.
+    pub copy_excludes: Option<Vec<String>>,
+    /// Skip the temporary directory and work in the project directory itself, false by
+    /// default.
+    pub in_place: Option<bool>,
+}
+
+impl ProjectConfig {
+    pub fn github(&self) -> Option<&GithubConfig> {
+        self.github.as_ref().filter(|github| github.is_enabled())
+    }
+
+    pub fn modrinth(&self) -> Option<&ModrinthConfig> {
+        self.modrinth
+            .as_ref()
+            .filter(|modrinth| modrinth.is_enabled())
+    }
+
+    pub fn modrinth_url(&self) -> ModrinthUrl {
+        match &self.modrinth {
+            Some(modrinth) => modrinth.url(),
+            None => ModrinthUrl::new(&None),
+        }
+    }
+
+    pub fn copy_excludes(&self) -> Vec<String> {
+        self.copy_excludes
+            .clone()
+            .unwrap_or_else(default_copy_excludes)
+    }
+
+    pub fn in_place(&self) -> bool {
+        self.in_place.unwrap_or(false)
+    }
+
+    /// Problems of the `[github]` and `[modrinth]` sections, disabled ones included.
+    pub fn platform_problems(&self, problems: &mut Vec<ConfigProblem>) {
+        if let Some(github) = &self.github {
+            check_not_empty(problems, "github.repo_owner", &github.repo_owner);
+            check_not_empty(problems, "github.repo_name", &github.repo_name);
+        }
+        if let Some(modrinth) = &self.modrinth {
+            check_not_empty(problems, "modrinth.project_id", &modrinth.project_id);
+            for (index, dependency) in modrinth.dependencies.iter().flatten().enumerate() {
+                dependency.check(problems, &format!("modrinth.dependencies[{}]", index));
+            }
+        }
+    }
+
+    /// Checks the sections that can be checked without a network connection.
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        if let Some(changelog_config) = &self.changelog {
+            changelog_config.check()?;
+        }
+        if let Some(discord_config) = &self.discord {
+            discord_config.check()?;
+        }
+        if let Some(matrix_config) = &self.matrix {
+            matrix_config.check()?;
+        }
+        if let Some(mastodon_config) = &self.mastodon {
+            mastodon_config.check()?;
+        }
+        if let Some(notify_config) = &self.notify {
+            for (index, http_config) in notify_config.http().iter().enumerate() {
+                http_config.check(index)?;
+            }
+        }
+        if let Some(email_config) = &self.email {
+            email_config.check()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config_check::CheckConfig;
+    use crate::models::project_type::{mc_mod::config::ModConfig, modpack::config::ModpackConfig};
+    use crate::test_support::read_test_config;
+    use crate::util::read_config;
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn example_config(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+    }
+
+    /// Writes `config` back out and reads it again, which has to give the same config.
+    fn round_trip<T: Serialize + DeserializeOwned + CheckConfig>(config: &T) {
+        let read_back: T = read_test_config(&toml::to_string(config).unwrap(), None).unwrap();
+        assert_eq!(
+            serde_json::to_value(&read_back).unwrap(),
+            serde_json::to_value(config).unwrap()
+        );
+    }
+
+    fn read_with_aliases<T: DeserializeOwned + CheckConfig>(
+        path: &Path,
+        aliases: &[(&str, &str)],
+    ) -> T {
+        let contents = aliases.iter().fold(
+            fs::read_to_string(path).unwrap(),
+            |contents, (key, alias)| contents.replacen(key, alias, 1),
+        );
+
+        read_test_config(&contents, None).unwrap()
+    }
+
+    #[test]
+    fn example_modpack_config_round_trips() {
+        let config: ModpackConfig =
+            read_config(&example_config("test_projects/modpack/mrpack.toml"), None).unwrap();
+
+        assert_eq!(
+            config.project.modrinth.as_ref().unwrap().project_id,
+            "2jZzXrpV"
+        );
+        assert_eq!(
+            config.project.github.as_ref().unwrap().repo_name,
+            "mrpack-dist-test"
+        );
+        let discord = config.project.discord.as_ref().unwrap();
+        assert_eq!(discord.title_emoji, "💖");
+        assert_eq!(discord.embed_color.map(|color| color.0), Some(0xd55de3));
+
+        round_trip(&config);
+    }
+
+    #[test]
+    fn example_mod_config_round_trips() {
+        let config: ModConfig = read_config(
+            &example_config("test_projects/mod-fabric/peony_mod.toml"),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.project.modrinth.as_ref().unwrap().project_id,
+            "MaJsLWB4"
+        );
+        assert_eq!(config.mc_version_alias, "1.20.x");
+        assert_eq!(
+            config
+                .project
+                .modrinth
+                .as_ref()
+                .unwrap()
+                .dependencies
+                .as_ref()
+                .map(Vec::len),
+            Some(2)
+        );
+        let discord = config.project.discord.as_ref().unwrap();
+        assert!(discord.thumbnail_image_url.is_some());
+        assert_eq!(discord.embed_color.map(|color| color.0), Some(0xffffff));
+
+        round_trip(&config);
+    }
+
+    #[test]
+    fn example_configs_read_with_aliases() {
+        let path = example_config("test_projects/mod-fabric/peony_mod.toml");
+        let config: ModConfig = read_config(&path, None).unwrap();
+        let aliased: ModConfig = read_with_aliases(
+            &path,
+            &[
+                ("thumbnail_image_url", "embed_thumbnail_url"),
+                (
+                    "staging = false",
+                    "staging = false\ntoken_env_var = \"PEONY_MODRINTH_TOKEN\"",
+                ),
+            ],
+        );
+
+        assert_eq!(
+            aliased.project.discord.unwrap().thumbnail_image_url,
+            config.project.discord.unwrap().thumbnail_image_url
+        );
+        assert_eq!(
+            aliased.project.modrinth.unwrap().token_env.as_deref(),
+            Some("PEONY_MODRINTH_TOKEN")
+        );
+
+        let path = example_config("test_projects/modpack/mrpack.toml");
+        let aliased: ModpackConfig = read_with_aliases(
+            &path,
+            &[
+                ("embed_image_url", "embed_thumbnail_url"),
+                (
+                    "repo_name = \"mrpack-dist-test\"",
+                    "repo_name = \"mrpack-dist-test\"\ntoken_env_var = \"PEONY_GITHUB_TOKEN\"",
+                ),
+            ],
+        );
+
+        assert!(aliased
+            .project
+            .discord
+            .unwrap()
+            .thumbnail_image_url
+            .is_some());
+        assert_eq!(
+            aliased.project.github.unwrap().token_env.as_deref(),
+            Some("PEONY_GITHUB_TOKEN")
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use crate::models::curseforge::ProjectRelation;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CurseforgeConfig {
+    pub project_id: u32,
+    /// Name of the environment variable holding the CurseForge API token.
+    pub token_env: Option<String>,
+    /// CurseForge game version ids, looked up from the Minecraft versions and loaders of each
+    /// jar when not set.
+    pub game_versions: Option<Vec<u32>>,
+    pub enabled: Option<bool>,
+    #[serde(rename = "relation")]
+    pub relations: Option<Vec<ProjectRelation>>,
+    /// CurseForge slugs of the mod IDs the jar's metadata depends on, added to the defaults of
+    /// [`DEFAULT_SLUGS`].
+    pub slugs: Option<HashMap<String, String>>,
+}
+
+/// Mod IDs of popular library mods and their CurseForge projects, the same mods as
+/// [`super::modrinth::DEFAULT_MOD_IDS`].
+pub const DEFAULT_SLUGS: [(&str, &str); 6] = [
+    ("fabric-api", "fabric-api"),
+    ("fabric", "fabric-api"),
+    ("fabric-language-kotlin", "fabric-language-kotlin"),
+    ("cloth-config", "cloth-config"),
+    ("cloth-config2", "cloth-config"),
+    ("modmenu", "modmenu"),
+];
+
+impl CurseforgeConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn token_env(&self) -> String {
+        self.token_env
+            .clone()
+            .unwrap_or_else(|| "CURSEFORGE_TOKEN".to_string())
+    }
+
+    pub fn slugs(&self) -> HashMap<String, String> {
+        let mut slugs = DEFAULT_SLUGS
+            .iter()
+            .map(|(mod_id, slug)| (mod_id.to_string(), slug.to_string()))
+            .collect::<HashMap<String, String>>();
+        slugs.extend(self.slugs.clone().unwrap_or_default());
+
+        slugs
+    }
+}
@@ -0,0 +1,25 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct MavenConfig {
+    /// Gradle task that publishes the mod, `publish` by default.
+    pub publish_task: Option<String>,
+    /// Environment variables the task needs, checked before building. Defaults to
+    /// `MAVEN_USERNAME` and `MAVEN_PASSWORD`.
+    pub env: Option<Vec<String>>,
+}
+
+impl MavenConfig {
+    pub fn publish_task(&self) -> String {
+        self.publish_task
+            .clone()
+            .unwrap_or_else(|| "publish".to_string())
+    }
+
+    pub fn env(&self) -> Vec<String> {
+        self.env
+            .clone()
+            .unwrap_or_else(|| vec!["MAVEN_USERNAME".to_string(), "MAVEN_PASSWORD".to_string()])
+    }
+}
@@ -1,20 +1,136 @@
+use crate::config_check::{check_not_empty, CheckConfig, ConfigProblem};
 use crate::models::{
-    modrinth::Loader, project_type::mc_mod::config::modrinth::ModrinthConfig, DiscordConfig,
-    GithubConfig,
+    modrinth::{Loader, ModrinthUrl},
+    project_type::{
+        config::ProjectConfig,
+        mc_mod::config::{curseforge::CurseforgeConfig, maven::MavenConfig},
+    },
+    GithubConfig, ModrinthConfig,
 };
+use anyhow::anyhow;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub mod curseforge;
+pub mod maven;
 pub mod modrinth;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ModConfig {
     pub config_format_version: i32,
     pub version_name_format: String,
-    pub loaders: Vec<Loader>,
+    /// Loaders of the Modrinth version, detected from the metadata in the jar when not set.
+    pub loaders: Option<Vec<Loader>>,
+    /// Jars of Gradle subprojects, e.g. the `fabric` and `neoforge` projects of an
+    /// Architectury mod. The jar of the root project is released when there are none.
+    pub artifacts: Option<Vec<ModArtifactConfig>>,
+    /// How `artifacts` are uploaded to Modrinth, `separate` by default.
+    pub artifacts_mode: Option<ArtifactsMode>,
+    /// Upload the `-sources` jar, failing if there isn't one. It's uploaded whenever it was
+    /// built when this isn't set.
+    pub include_sources: Option<bool>,
+    /// Upload the `-javadoc` jar, failing if there isn't one. False by default.
+    pub include_javadoc: Option<bool>,
+    /// Oldest Java major version the mod builds with, checked before Gradle runs.
+    pub java_version: Option<u32>,
+    /// Gradle tasks that have to pass before the mod is built, e.g. `check`.
+    pub verify_tasks: Option<Vec<String>>,
     pub mc_versions: Vec<String>,
     pub mc_version_alias: String,
     pub version_alias: Option<String>,
-    pub github: GithubConfig,
-    pub modrinth: ModrinthConfig,
-    pub discord: Option<DiscordConfig>,
+    pub curseforge: Option<CurseforgeConfig>,
+    /// Publishes the mod to a Maven repository once it's released everywhere else.
+    pub maven: Option<MavenConfig>,
+    #[serde(flatten)]
+    pub project: ProjectConfig,
+}
+
+impl ModConfig {
+    pub fn github(&self) -> Option<&GithubConfig> {
+        self.project.github()
+    }
+
+    pub fn modrinth(&self) -> Option<&ModrinthConfig> {
+        self.project.modrinth()
+    }
+
+    pub fn modrinth_url(&self) -> ModrinthUrl {
+        self.project.modrinth_url()
+    }
+
+    /// A config has to release somewhere, disabled sections still count.
+    pub fn check_release_targets(&self) -> Result<(), anyhow::Error> {
+        if self.project.github.is_none()
+            && self.project.modrinth.is_none()
+            && self.curseforge.is_none()
+        {
+            return Err(anyhow!(
+                "At least one release target must be configured, add a `[github]`, \
+                 `[modrinth]` or `[curseforge]` section"
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn loaders(&self, detected: Loader) -> Vec<Loader> {
+        self.loaders.clone().unwrap_or_else(|| vec![detected])
+    }
+
+    pub fn verify_tasks(&self) -> Vec<String> {
+        self.verify_tasks.clone().unwrap_or_default()
+    }
+
+    pub fn include_javadoc(&self) -> bool {
+        self.include_javadoc.unwrap_or(false)
+    }
+
+    pub fn artifacts_mode(&self) -> ArtifactsMode {
+        self.artifacts_mode.unwrap_or(ArtifactsMode::Separate)
+    }
+}
+
+impl CheckConfig for ModConfig {
+    fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = vec![];
+
+        self.project.platform_problems(&mut problems);
+        if let Some(curseforge) = self.curseforge.as_ref().filter(|cf| cf.project_id == 0) {
+            problems.push(ConfigProblem::new(
+                "curseforge.project_id",
+                &format!("`curseforge.project_id` can't be {}", curseforge.project_id),
+            ));
+        }
+        for (index, artifact) in self.artifacts.iter().flatten().enumerate() {
+            check_not_empty(
+                &mut problems,
+                &format!("artifacts[{}].dir", index),
+                &artifact.dir,
+            );
+        }
+
+        problems
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ModArtifactConfig {
+    /// Directory of the subproject, relative to the project.
+    pub dir: String,
+    /// Glob for the jar, relative to `dir`. The jar is looked for in `build/libs` like the
+    /// root project's when it isn't set.
+    pub jar: Option<String>,
+    /// Defaults to the loader detected from the jar.
+    pub loaders: Option<Vec<Loader>>,
+    /// Defaults to `mc_versions`.
+    pub game_versions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactsMode {
+    /// A Modrinth version for every artifact.
+    Separate,
+    /// One Modrinth version with every artifact's jar.
+    Combined,
 }
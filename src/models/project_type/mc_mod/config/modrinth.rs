@@ -1,22 +1,40 @@
+use crate::config_check::ConfigProblem;
 use crate::models::modrinth::version::VersionDependency;
 use crate::models::modrinth::DependencyType;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ModrinthConfig {
-    pub project_id: String,
-    pub staging: Option<bool>,
-    #[serde(rename = "dependency")]
-    pub dependencies: Option<Vec<ModrinthDependency>>,
-}
+/// Mod IDs of popular library mods and their Modrinth projects.
+pub const DEFAULT_MOD_IDS: [(&str, &str); 6] = [
+    ("fabric-api", "P7dR8mSH"),
+    ("fabric", "P7dR8mSH"),
+    ("fabric-language-kotlin", "Ha28R6CL"),
+    ("cloth-config", "9s6osm5g"),
+    ("cloth-config2", "9s6osm5g"),
+    ("modmenu", "mOgUt4GM"),
+];
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ModrinthDependency {
     pub version_id: Option<String>,
     pub project_id: Option<String>,
     pub dependency_type: DependencyType,
 }
 
+impl ModrinthDependency {
+    /// Modrinth rejects dependencies that point at neither a project nor a version.
+    pub fn check(&self, problems: &mut Vec<ConfigProblem>, key: &str) {
+        let is_set = |id: &Option<String>| id.as_ref().is_some_and(|id| !id.trim().is_empty());
+
+        if !is_set(&self.project_id) && !is_set(&self.version_id) {
+            problems.push(ConfigProblem::new(
+                key,
+                &format!("`{}` needs a `project_id` or a `version_id`", key),
+            ));
+        }
+    }
+}
+
 impl From<VersionDependency> for ModrinthDependency {
     fn from(dep: VersionDependency) -> Self {
         Self {
@@ -1,3 +1,4 @@
+use crate::models::modrinth::{DependencyType, Loader};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -9,12 +10,23 @@ pub struct ModInfo {
     pub id: String,
     pub name: String,
     pub version: String,
+    /// Detected from the metadata file in the jar.
+    pub loader: Loader,
+    /// Mods the metadata depends on, without Minecraft, Java and the loader itself.
+    pub dependencies: Vec<ModDependency>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModDependency {
+    pub mod_id: String,
+    pub dependency_type: DependencyType,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModJars {
     pub mod_jar: Jar,
     pub sources_jar: Option<Jar>,
+    pub javadoc_jar: Option<Jar>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
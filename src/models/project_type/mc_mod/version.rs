@@ -1,22 +1,29 @@
 use crate::models::modrinth::Loader;
 use crate::models::project_type::mc_mod::config::ModConfig;
-use crate::models::project_type::mc_mod::{ModInfo, ModJars};
-use crate::util::read_file;
+use crate::models::project_type::mc_mod::{Jar, ModDependency, ModInfo, ModJars};
+use crate::util::file_size;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModVersionInfo {
     pub name: String,
     pub version: String,
     pub loaders: Vec<Loader>,
+    pub game_versions: Vec<String>,
+    /// From the metadata of this jar, mapped to Modrinth projects when uploading.
+    pub dependencies: Vec<ModDependency>,
     pub mod_file: ModFile,
     pub sources_file: Option<ModFile>,
+    pub javadoc_file: Option<ModFile>,
 }
 
+/// Jars are streamed from disk when they're uploaded, and never read into memory.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModFile {
     pub name: String,
-    pub contents: Vec<u8>,
+    pub path: PathBuf,
+    pub size: u64,
 }
 
 impl ModVersionInfo {
@@ -24,25 +31,32 @@ impl ModVersionInfo {
         config: &ModConfig,
         mod_jars: &ModJars,
         mod_info: &ModInfo,
+        dependencies: Vec<ModDependency>,
+        loaders: Vec<Loader>,
+        game_versions: Vec<String>,
     ) -> Result<Self, anyhow::Error> {
-        let mod_jar_contents = read_file(&mod_jars.mod_jar.file_path)?;
-        let sources_jar_contents = match &mod_jars.sources_jar {
-            Some(jar) => Some(read_file(&jar.file_path)?),
-            None => None,
+        let mod_file = |jar: &Jar| -> Result<ModFile, anyhow::Error> {
+            Ok(ModFile {
+                name: jar.file_name.clone(),
+                path: jar.file_path.clone(),
+                size: file_size(&jar.file_path)?,
+            })
         };
 
-        let mod_jar_info = ModFile {
-            name: mod_jars.mod_jar.file_name.clone(),
-            contents: mod_jar_contents,
-        };
-
-        let sources_jar_info = sources_jar_contents.map(|contents| ModFile {
-            name: mod_jars.sources_jar.clone().unwrap().file_name,
-            contents,
-        });
-
-        let loaders = config.loaders.clone();
+        Ok(Self {
+            name: Self::version_name(config, mod_info, &loaders),
+            version: mod_info.version.clone(),
+            loaders,
+            game_versions,
+            dependencies,
+            mod_file: mod_file(&mod_jars.mod_jar)?,
+            sources_file: mod_jars.sources_jar.as_ref().map(mod_file).transpose()?,
+            javadoc_file: mod_jars.javadoc_jar.as_ref().map(mod_file).transpose()?,
+        })
+    }
 
+    /// `version_name_format` with the loaders joined by `/`.
+    pub fn version_name(config: &ModConfig, mod_info: &ModInfo, loaders: &[Loader]) -> String {
         let loaders_formatted: String = loaders
             .iter()
             .map(|l| l.formatted())
@@ -54,19 +68,11 @@ impl ModVersionInfo {
             None => &mod_info.version,
         };
 
-        let version_name = config
+        config
             .version_name_format
             .replace("%project_name%", &mod_info.name)
             .replace("%project_version%", project_version)
             .replace("%mc_version%", &config.mc_version_alias)
-            .replace("%loader%", &loaders_formatted);
-
-        Ok(Self {
-            name: version_name,
-            version: mod_info.version.clone(),
-            loaders,
-            mod_file: mod_jar_info,
-            sources_file: sources_jar_info,
-        })
+            .replace("%loader%", &loaders_formatted)
     }
 }
@@ -1,2 +1,4 @@
+pub mod config;
 pub mod mc_mod;
 pub mod modpack;
+pub mod pack;
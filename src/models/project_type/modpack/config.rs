@@ -1,11 +1,198 @@
-use crate::models::{DiscordConfig, GithubConfig, ModrinthConfig};
+use crate::config_check::{check_not_empty, CheckConfig, ConfigProblem};
+use crate::models::{
+    forge::Forge, CurseforgeConfig, GiteaConfig, GithubConfig, GitlabConfig, ModrinthConfig,
+    PackwizConfig, ServerPackConfig, VersionConfig,
+};
+use crate::models::{
+    modrinth::{Loader, ModrinthUrl},
+    project_type::{config::ProjectConfig, modpack::PackFile},
+};
+use anyhow::anyhow;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ModpackConfig {
     pub config_format_version: i32,
     pub version_name_format: String,
-    pub github: GithubConfig,
-    pub modrinth: ModrinthConfig,
-    pub discord: Option<DiscordConfig>,
+    /// Name of the exported file, with `{name}`, `{version}` and `{mc_version}` placeholders.
+    /// The name packwiz picks is kept when it isn't set.
+    pub output_filename: Option<String>,
+    pub gitlab: Option<GitlabConfig>,
+    pub gitea: Option<GiteaConfig>,
+    pub curseforge: Option<CurseforgeConfig>,
+    pub packwiz: Option<PackwizConfig>,
+    pub server_pack: Option<ServerPackConfig>,
+    pub version: Option<VersionConfig>,
+    /// Glob patterns for extra files to upload to Modrinth alongside the .mrpack,
+    /// relative to the project directory.
+    pub additional_files: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub project: ProjectConfig,
+}
+
+impl ModpackConfig {
+    pub fn github(&self) -> Option<&GithubConfig> {
+        self.project.github()
+    }
+
+    pub fn gitlab(&self) -> Option<&GitlabConfig> {
+        self.gitlab.as_ref().filter(|gitlab| gitlab.is_enabled())
+    }
+
+    pub fn gitea(&self) -> Option<&GiteaConfig> {
+        self.gitea.as_ref().filter(|gitea| gitea.is_enabled())
+    }
+
+    pub fn modrinth(&self) -> Option<&ModrinthConfig> {
+        self.project.modrinth()
+    }
+
+    pub fn modrinth_url(&self) -> ModrinthUrl {
+        self.project.modrinth_url()
+    }
+
+    /// The Modrinth overrides are used for every platform, as they're what the pack supports.
+    pub fn game_versions_for(&self, pack_file: &PackFile) -> Vec<String> {
+        match &self.project.modrinth {
+            Some(modrinth) => modrinth.game_versions_for(pack_file),
+            None => vec![pack_file.versions.minecraft.clone()],
+        }
+    }
+
+    pub fn loaders_for(&self, pack_file: &PackFile) -> Vec<Loader> {
+        match &self.project.modrinth {
+            Some(modrinth) => modrinth.loaders_for(pack_file),
+            None => pack_file.versions.loaders(),
+        }
+    }
+
+    /// A config has to release somewhere, disabled sections still count.
+    pub fn check_release_targets(&self) -> Result<(), anyhow::Error> {
+        if self.project.github.is_none()
+            && self.gitlab.is_none()
+            && self.gitea.is_none()
+            && self.project.modrinth.is_none()
+            && self.curseforge.is_none()
+        {
+            return Err(anyhow!(
+                "At least one release target must be configured, add a `[github]`, `[gitlab]`, \
+                 `[gitea]`, `[modrinth]` or `[curseforge]` section"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The forge that changelog and release links point to, GitHub is preferred when
+    /// releases are mirrored to several.
+    pub fn forge(&self) -> Option<Forge<'_>> {
+        if let Some(github) = self.github() {
+            return Some(Forge::Github(github));
+        }
+        if let Some(gitlab) = self.gitlab() {
+            return Some(Forge::Gitlab(gitlab));
+        }
+        self.gitea().map(Forge::Gitea)
+    }
+}
+
+impl CheckConfig for ModpackConfig {
+    fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = vec![];
+
+        self.project.platform_problems(&mut problems);
+        if let Some(gitlab) = &self.gitlab {
+            check_not_empty(&mut problems, "gitlab.project_id", &gitlab.project_id);
+        }
+        if let Some(gitea) = &self.gitea {
+            check_not_empty(&mut problems, "gitea.owner", &gitea.owner);
+            check_not_empty(&mut problems, "gitea.repo", &gitea.repo);
+        }
+        if let Some(curseforge) = self.curseforge.as_ref().filter(|cf| cf.project_id == 0) {
+            problems.push(ConfigProblem::new(
+                "curseforge.project_id",
+                &format!("`curseforge.project_id` can't be {}", curseforge.project_id),
+            ));
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::modrinth::DependencyType;
+    use crate::test_support::{read_test_config, MODPACK_CONFIG};
+
+    fn read_with_modrinth(extra: &str) -> Result<ModpackConfig, anyhow::Error> {
+        read_test_config(
+            &format!(
+                "{}\n[modrinth]\nproject_id = \"AABBCCDD\"\n{}",
+                MODPACK_CONFIG, extra
+            ),
+            None,
+        )
+    }
+
+    #[test]
+    fn reads_dependencies() {
+        let config = read_with_modrinth(
+            r#"
+[[modrinth.dependencies]]
+project_id = "P7dR8mSH"
+dependency_type = "embedded"
+
+[[modrinth.dependencies]]
+version_id = "IIJJKKLL"
+dependency_type = "incompatible"
+"#,
+        )
+        .unwrap();
+
+        let dependencies = config.project.modrinth.unwrap().dependencies.unwrap();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[0].project_id.as_deref(), Some("P7dR8mSH"));
+        assert_eq!(dependencies[0].dependency_type, DependencyType::Embedded);
+        assert_eq!(dependencies[1].version_id.as_deref(), Some("IIJJKKLL"));
+    }
+
+    #[test]
+    fn rejects_dependency_without_project_or_version() {
+        let err = read_with_modrinth(
+            r#"
+[[modrinth.dependencies]]
+project_id = "P7dR8mSH"
+dependency_type = "required"
+
+[[modrinth.dependencies]]
+dependency_type = "required"
+"#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(
+            err.contains("`modrinth.dependencies[1]` needs a `project_id` or a `version_id`"),
+            "{}",
+            err
+        );
+        assert!(!err.contains("dependencies[0]"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_invalid_dependency_type() {
+        let err = read_with_modrinth(
+            r#"
+[[modrinth.dependencies]]
+project_id = "P7dR8mSH"
+dependency_type = "bundled"
+"#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("unknown variant `bundled`"), "{}", err);
+    }
 }
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::util::OutputFileInfo;
+
+// Based on the modrinth.index.json format of .mrpack files here:
+// https://docs.modrinth.com/docs/modpacks/format_definition/
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndex {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    pub files: Vec<MrpackIndexFile>,
+    pub dependencies: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndexFile {
+    pub path: String,
+    pub downloads: Vec<String>,
+    pub file_size: u64,
+    /// `sha1` and `sha512` of the file.
+    #[serde(default)]
+    pub hashes: HashMap<String, String>,
+}
+
+impl MrpackIndexFile {
+    pub fn file_name(&self) -> String {
+        match self.path.rsplit_once('/') {
+            Some((_, name)) => name.to_string(),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Project id taken from a Modrinth CDN download url, e.g.
+    /// `https://cdn.modrinth.com/data/AANobbMI/versions/.../sodium.jar`.
+    pub fn modrinth_project_id(&self) -> Option<String> {
+        self.downloads.iter().find_map(|url| {
+            url.strip_prefix("https://cdn.modrinth.com/data/")
+                .and_then(|rest| rest.split('/').next())
+                .map(String::from)
+        })
+    }
+
+    /// Files are matched across releases by their Modrinth project, falling back to the path.
+    pub fn key(&self) -> String {
+        match self.modrinth_project_id() {
+            Some(project_id) => project_id,
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Overrides are compared by the CRC-32 stored in the archive, so nothing is extracted.
+#[derive(Debug, Clone)]
+pub struct MrpackContents {
+    pub index: MrpackIndex,
+    /// Files in `overrides`, `client-overrides` and `server-overrides`, with their CRC-32.
+    pub overrides: BTreeMap<String, u32>,
+}
+
+/// packwiz's index of every file in the pack, based on the format described here:
+/// <https://packwiz.infra.link/reference/pack-format/index-toml/>
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackwizIndex {
+    pub hash_format: String,
+    #[serde(default)]
+    pub files: Vec<PackwizIndexFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackwizIndexFile {
+    /// Path relative to the index.
+    pub file: String,
+    pub hash: String,
+    /// Path to install the file to instead of `file`.
+    pub alias: Option<String>,
+    /// Metafiles describe a download, every other file is exported as an override.
+    pub metafile: Option<bool>,
+    pub preserve: Option<bool>,
+}
+
+impl PackwizIndexFile {
+    pub fn is_metafile(&self) -> bool {
+        self.metafile.unwrap_or(false)
+    }
+
+    /// Path of the file in the `overrides` directory of an exported .mrpack.
+    pub fn override_path(&self) -> String {
+        format!("overrides/{}", self.alias.as_ref().unwrap_or(&self.file))
+    }
+}
+
+/// The parts of a packwiz metafile (`*.pw.toml`) that describe which mod it is, based on:
+/// <https://packwiz.infra.link/reference/pack-format/mod-toml/>
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackwizMetafile {
+    pub name: String,
+    pub filename: String,
+    /// `client`, `server` or `both`, which is the default.
+    pub side: Option<String>,
+    pub download: Option<PackwizMetafileDownload>,
+    pub update: Option<PackwizMetafileUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackwizMetafileDownload {
+    pub hash_format: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackwizMetafileUpdate {
+    pub modrinth: Option<PackwizModrinthUpdate>,
+    pub curseforge: Option<PackwizCurseforgeUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackwizModrinthUpdate {
+    pub mod_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackwizCurseforgeUpdate {
+    pub project_id: u64,
+}
+
+/// A mod of the pack, as listed in the `mods.md` and `mods.csv` release assets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModListEntry {
+    pub name: String,
+    /// Taken from the file name, as metafiles don't have a version number.
+    pub version: String,
+    pub side: String,
+    pub modrinth_url: Option<String>,
+    pub curseforge_url: Option<String>,
+}
+
+/// The mods of the pack, with `mods.md` and `mods.csv` written from them.
+#[derive(Clone)]
+pub struct ModList {
+    pub entries: Vec<ModListEntry>,
+    pub files: Vec<OutputFileInfo>,
+}
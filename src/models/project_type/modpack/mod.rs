@@ -1,14 +1,17 @@
+use crate::models::modrinth::Loader;
 use serde::{Deserialize, Serialize};
 
 pub mod config;
+pub mod index;
 
-// Based on packwiz's pack.toml format here:
-// https://packwiz.infra.link/reference/pack-format/pack-toml/
+/// A packwiz `pack.toml`, based on the format described here:
+/// <https://packwiz.infra.link/reference/pack-format/pack-toml/>
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PackFile {
     pub name: String,
     pub author: Option<String>,
     pub description: Option<String>,
+    /// Version of the pack, replaced by the released version once it's resolved.
     pub version: String,
     #[serde(rename = "pack-format")]
     pub pack_format: String,
@@ -31,9 +34,34 @@ pub struct PackFileVersions {
     pub quilt: Option<String>,
     pub fabric: Option<String>,
     pub forge: Option<String>,
+    pub neoforge: Option<String>,
     pub liteloader: Option<String>,
 }
 
+impl PackFileVersions {
+    pub fn loaders(&self) -> Vec<Loader> {
+        let mut loaders = vec![];
+
+        if self.quilt.is_some() {
+            loaders.push(Loader::Quilt)
+        }
+        if self.fabric.is_some() {
+            loaders.push(Loader::Fabric)
+        }
+        if self.forge.is_some() {
+            loaders.push(Loader::Forge)
+        }
+        if self.neoforge.is_some() {
+            loaders.push(Loader::Neoforge)
+        }
+        if self.liteloader.is_some() {
+            loaders.push(Loader::Liteloader)
+        }
+
+        loaders
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PackFileOptions {
     #[serde(rename = "acceptable-game-versions")]
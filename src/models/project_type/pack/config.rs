@@ -0,0 +1,57 @@
+use crate::config_check::{check_not_empty, CheckConfig, ConfigProblem};
+use crate::models::{
+    project_type::pack::PackType, ChangelogConfig, DiscordConfig, ModrinthConfig, NetworkConfig,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct PackConfig {
+    pub config_format_version: i32,
+    pub project_type: PackType,
+    pub name: String,
+    /// Version of the pack, `--version` takes precedence.
+    pub version: Option<String>,
+    /// Version name with `{name}`, `{version}` and `{mc_version}` placeholders, defaults to
+    /// `{name} {version}`.
+    pub version_name_format: Option<String>,
+    pub mc_versions: Vec<String>,
+    /// Directory with the `pack.mcmeta`, relative to the config.
+    pub source_dir: String,
+    /// Name of the zip, with the same placeholders as `version_name_format`. Defaults to
+    /// `{name}-{version}.zip`.
+    pub file_name_format: Option<String>,
+    pub modrinth: ModrinthConfig,
+    pub discord: Option<DiscordConfig>,
+    pub network: Option<NetworkConfig>,
+    pub changelog: Option<ChangelogConfig>,
+}
+
+impl PackConfig {
+    pub fn version_name_format(&self) -> String {
+        self.version_name_format
+            .clone()
+            .unwrap_or_else(|| "{name} {version}".to_string())
+    }
+
+    pub fn file_name_format(&self) -> String {
+        self.file_name_format
+            .clone()
+            .unwrap_or_else(|| "{name}-{version}.zip".to_string())
+    }
+}
+
+impl CheckConfig for PackConfig {
+    fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = vec![];
+
+        check_not_empty(&mut problems, "name", &self.name);
+        check_not_empty(
+            &mut problems,
+            "modrinth.project_id",
+            &self.modrinth.project_id,
+        );
+
+        problems
+    }
+}
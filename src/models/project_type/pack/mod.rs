@@ -0,0 +1,144 @@
+use crate::models::modrinth::Loader;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub mod config;
+
+/// Packs that are zipped from a directory with a `pack.mcmeta`, rather than built.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PackType {
+    Resourcepack,
+    Datapack,
+}
+
+/// First and last Minecraft version of every `pack_format`, based on the tables here:
+/// <https://minecraft.wiki/w/Pack_format>
+const RESOURCE_PACK_FORMATS: [(&str, &str, u32); 18] = [
+    ("1.13", "1.14.4", 4),
+    ("1.15", "1.16.1", 5),
+    ("1.16.2", "1.16.5", 6),
+    ("1.17", "1.17.1", 7),
+    ("1.18", "1.18.2", 8),
+    ("1.19", "1.19.2", 9),
+    ("1.19.3", "1.19.3", 12),
+    ("1.19.4", "1.19.4", 13),
+    ("1.20", "1.20.1", 15),
+    ("1.20.2", "1.20.2", 18),
+    ("1.20.3", "1.20.4", 22),
+    ("1.20.5", "1.20.6", 32),
+    ("1.21", "1.21.1", 34),
+    ("1.21.2", "1.21.3", 42),
+    ("1.21.4", "1.21.4", 46),
+    ("1.21.5", "1.21.5", 55),
+    ("1.21.6", "1.21.6", 63),
+    ("1.21.7", "1.21.8", 64),
+];
+
+const DATA_PACK_FORMATS: [(&str, &str, u32); 18] = [
+    ("1.13", "1.14.4", 4),
+    ("1.15", "1.16.1", 5),
+    ("1.16.2", "1.16.5", 6),
+    ("1.17", "1.17.1", 7),
+    ("1.18", "1.18.1", 8),
+    ("1.18.2", "1.18.2", 9),
+    ("1.19", "1.19.3", 10),
+    ("1.19.4", "1.19.4", 12),
+    ("1.20", "1.20.1", 15),
+    ("1.20.2", "1.20.2", 18),
+    ("1.20.3", "1.20.4", 26),
+    ("1.20.5", "1.20.6", 41),
+    ("1.21", "1.21.1", 48),
+    ("1.21.2", "1.21.3", 57),
+    ("1.21.4", "1.21.4", 61),
+    ("1.21.5", "1.21.5", 71),
+    ("1.21.6", "1.21.6", 80),
+    ("1.21.7", "1.21.8", 81),
+];
+
+impl PackType {
+    pub fn formatted(&self) -> String {
+        match self {
+            Self::Resourcepack => "Resource Pack",
+            Self::Datapack => "Data Pack",
+        }
+        .to_string()
+    }
+
+    /// Modrinth has no loaders for these, versions are tagged with these instead.
+    pub fn loader(&self) -> Loader {
+        match self {
+            Self::Resourcepack => Loader::Minecraft,
+            Self::Datapack => Loader::Datapack,
+        }
+    }
+
+    /// `None` for snapshots and versions newer than the table.
+    pub fn pack_format(&self, mc_version: &str) -> Option<u32> {
+        let formats: &[(&str, &str, u32)] = match self {
+            Self::Resourcepack => &RESOURCE_PACK_FORMATS,
+            Self::Datapack => &DATA_PACK_FORMATS,
+        };
+        let version = parse_mc_version(mc_version)?;
+
+        formats.iter().find_map(|(first, last, format)| {
+            let first = parse_mc_version(first)?;
+            let last = parse_mc_version(last)?;
+            (first <= version && version <= last).then_some(*format)
+        })
+    }
+}
+
+/// `1.20` and `1.20.0` are the same version.
+fn parse_mc_version(version: &str) -> Option<Vec<u32>> {
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+
+    while parts.len() < 3 {
+        parts.push(0);
+    }
+
+    Some(parts)
+}
+
+/// The parts of `pack.mcmeta` that say which versions the pack is for, based on:
+/// <https://minecraft.wiki/w/Pack.mcmeta>
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackMcmeta {
+    pub pack: PackMcmetaPack,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackMcmetaPack {
+    pub pack_format: u32,
+    pub description: serde_json::Value,
+    pub supported_formats: Option<SupportedFormats>,
+}
+
+/// A single format, `[min, max]` or `{ min_inclusive, max_inclusive }`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SupportedFormats {
+    Single(u32),
+    Range([u32; 2]),
+    Object {
+        min_inclusive: u32,
+        max_inclusive: u32,
+    },
+}
+
+impl PackMcmetaPack {
+    pub fn supports(&self, format: u32) -> bool {
+        match &self.supported_formats {
+            Some(SupportedFormats::Single(supported)) => *supported == format,
+            Some(SupportedFormats::Range([min, max]))
+            | Some(SupportedFormats::Object {
+                min_inclusive: min,
+                max_inclusive: max,
+            }) => (*min..=*max).contains(&format),
+            None => self.pack_format == format,
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::anyhow;
+
+use crate::models::changelog::Changelog;
+use crate::models::modrinth::{version::VersionType, Loader};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Platform {
+    Github,
+    Gitlab,
+    Gitea,
+    Modrinth,
+    Curseforge,
+}
+
+impl Platform {
+    pub fn formatted(&self) -> String {
+        match self {
+            Self::Github => "GitHub",
+            Self::Gitlab => "GitLab",
+            Self::Gitea => "Gitea",
+            Self::Modrinth => "Modrinth",
+            Self::Curseforge => "CurseForge",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ReleaseStatus {
+    Succeeded {
+        url: String,
+    },
+    Failed(String),
+    Skipped,
+    /// The version was released by an earlier run, only used with `--resume`.
+    AlreadyPresent {
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct PlatformResult {
+    pub platform: Platform,
+    pub status: ReleaseStatus,
+}
+
+/// Where release announcements are sent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Notifier {
+    Discord,
+    Slack,
+    Matrix,
+    Mastodon,
+    Http,
+    Email,
+}
+
+impl Notifier {
+    pub fn formatted(&self) -> String {
+        match self {
+            Self::Discord => "Discord",
+            Self::Slack => "Slack",
+            Self::Matrix => "Matrix",
+            Self::Mastodon => "Mastodon",
+            Self::Http => "HTTP",
+            Self::Email => "Email",
+        }
+        .to_string()
+    }
+
+    /// What [`WebhookResult::target`] is, for the summary.
+    pub fn target_kind(&self) -> &'static str {
+        match self {
+            Self::Discord | Self::Slack => "webhook",
+            Self::Matrix => "room",
+            Self::Mastodon => "post on",
+            Self::Http => "notifier",
+            Self::Email => "via",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookResult {
+    pub notifier: Notifier,
+    /// Environment variable of the webhook URL, the ID of the Matrix room, the Mastodon
+    /// instance, the name of the HTTP notifier or the SMTP server of emails.
+    pub target: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReleaseSummary {
+    pub version_name: String,
+    pub version_number: String,
+    pub version_type: VersionType,
+    pub changelog: Changelog,
+    pub loaders: Vec<Loader>,
+    pub game_versions: Vec<String>,
+    /// Size of the uploaded .mrpack or mod jar, in bytes.
+    pub file_size: Option<u64>,
+    /// Number of mods in the .mrpack, not known for mods.
+    pub mod_count: Option<usize>,
+    /// Name of the released .mrpack or mod jar.
+    pub file_name: Option<String>,
+    /// SHA-512 of the released .mrpack, not computed for mods.
+    pub sha512: Option<String>,
+    /// Name of the server pack, if one was released.
+    pub server_pack_file_name: Option<String>,
+    /// Where the server pack can be downloaded from.
+    pub server_pack_url: Option<String>,
+    pub results: Vec<PlatformResult>,
+    pub webhooks: Vec<WebhookResult>,
+    /// Errors of `post_release` hooks, which run after everything was released.
+    pub hook_failures: Vec<String>,
+    /// The Modrinth file of every artifact of a mod with several, empty otherwise.
+    pub downloads: Vec<LoaderDownload>,
+    /// `group:artifact:version` of a mod published to Maven.
+    pub maven_coordinates: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoaderDownload {
+    pub loaders: Vec<Loader>,
+    pub file_name: String,
+    pub url: String,
+}
+
+impl ReleaseSummary {
+    pub fn add(&mut self, platform: Platform, status: ReleaseStatus) {
+        self.results.push(PlatformResult { platform, status })
+    }
+
+    pub fn is_published(&self, platform: Platform) -> bool {
+        self.results.iter().any(|res| {
+            res.platform == platform
+                && matches!(
+                    res.status,
+                    ReleaseStatus::Succeeded { .. } | ReleaseStatus::AlreadyPresent { .. }
+                )
+        })
+    }
+
+    pub fn url(&self, platform: Platform) -> Option<&String> {
+        self.results.iter().find_map(|res| match &res.status {
+            ReleaseStatus::Succeeded { url } | ReleaseStatus::AlreadyPresent { url }
+                if res.platform == platform =>
+            {
+                Some(url)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn failures(&self) -> Vec<&PlatformResult> {
+        self.results
+            .iter()
+            .filter(|res| matches!(res.status, ReleaseStatus::Failed(_)))
+            .collect()
+    }
+
+    /// Mastodon posts and emails are left out, failing them only warns.
+    pub fn webhook_failures(&self) -> Vec<&WebhookResult> {
+        self.webhooks
+            .iter()
+            .filter(|res| {
+                res.error.is_some() && !matches!(res.notifier, Notifier::Mastodon | Notifier::Email)
+            })
+            .collect()
+    }
+
+    /// Fails the run when a platform or webhook failed, unless `allow_partial`.
+    pub fn check(&self, allow_partial: bool) -> Result<(), anyhow::Error> {
+        let failures = self.failures();
+        if !failures.is_empty() && !allow_partial {
+            return Err(anyhow!(
+                "Failed to release to: {}",
+                failures
+                    .iter()
+                    .map(|res| res.platform.formatted())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+
+        if !self.webhook_failures().is_empty() && !allow_partial {
+            return Err(anyhow!("Failed to send some notifications"));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the version was released to at least one platform, and failed on none.
+    pub fn is_successful(&self) -> bool {
+        self.failures().is_empty()
+            && self.results.iter().any(|res| {
+                matches!(
+                    res.status,
+                    ReleaseStatus::Succeeded { .. } | ReleaseStatus::AlreadyPresent { .. }
+                )
+            })
+    }
+
+    pub fn print(&self) {
+        println!("Release summary:");
+        println!(
+            "  Version: {} ({}, {})",
+            self.version_name,
+            self.version_number,
+            self.version_type.formatted()
+        );
+
+        if let Some(file_name) = &self.file_name {
+            let mut details = vec![];
+            if let Some(size) = self.file_size {
+                details.push(format!("{:.2} MiB", size as f64 / (1024.0 * 1024.0)));
+            }
+            if let Some(mod_count) = self.mod_count {
+                details.push(format!("{} mods", mod_count));
+            }
+
+            match details.is_empty() {
+                true => println!("  File: {}", file_name),
+                false => println!("  File: {} ({})", file_name, details.join(", ")),
+            }
+        }
+
+        for res in &self.results {
+            match &res.status {
+                ReleaseStatus::Succeeded { url } => {
+                    println!("  {}: succeeded ({})", res.platform.formatted(), url)
+                }
+                ReleaseStatus::Failed(err) => {
+                    println!("  {}: failed ({})", res.platform.formatted(), err)
+                }
+                ReleaseStatus::Skipped => println!("  {}: skipped", res.platform.formatted()),
+                ReleaseStatus::AlreadyPresent { url } => {
+                    println!("  {}: already released ({})", res.platform.formatted(), url)
+                }
+            }
+        }
+
+        if let Some(coordinates) = &self.maven_coordinates {
+            println!("  Maven: published ({})", coordinates);
+        }
+
+        for res in &self.webhooks {
+            let notifier = format!(
+                "{} {}",
+                res.notifier.formatted(),
+                res.notifier.target_kind()
+            );
+            match &res.error {
+                None => println!("  {} `{}`: sent", notifier, res.target),
+                Some(err) => println!("  {} `{}`: failed ({})", notifier, res.target, err),
+            }
+        }
+
+        for err in &self.hook_failures {
+            println!("  Hook failed: {}", err)
+        }
+    }
+}
+
+/// Written next to the config by `--resume` runs after the Discord, Slack or Matrix messages
+/// were sent, so that resuming again doesn't announce the same version twice.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReleaseState {
+    pub version: String,
+    pub discord_sent: bool,
+    #[serde(default)]
+    pub slack_sent: bool,
+    #[serde(default)]
+    pub matrix_sent: bool,
+    #[serde(default)]
+    pub mastodon_sent: bool,
+    #[serde(default)]
+    pub http_sent: bool,
+    #[serde(default)]
+    pub email_sent: bool,
+}
+
+impl ReleaseState {
+    pub fn is_sent(&self, notifier: Notifier) -> bool {
+        match notifier {
+            Notifier::Discord => self.discord_sent,
+            Notifier::Slack => self.slack_sent,
+            Notifier::Matrix => self.matrix_sent,
+            Notifier::Mastodon => self.mastodon_sent,
+            Notifier::Http => self.http_sent,
+            Notifier::Email => self.email_sent,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn test_summary() -> ReleaseSummary {
+        ReleaseSummary {
+            version_name: "Test Pack 1.0.0".to_string(),
+            version_number: "1.0.0".to_string(),
+            version_type: VersionType::Release,
+            changelog: Changelog::default(),
+            loaders: vec![],
+            game_versions: vec![],
+            file_size: None,
+            mod_count: None,
+            file_name: None,
+            sha512: None,
+            server_pack_file_name: None,
+            server_pack_url: None,
+            results: vec![],
+            webhooks: vec![],
+            hook_failures: vec![],
+            downloads: vec![],
+            maven_coordinates: None,
+        }
+    }
+
+    #[test]
+    fn modrinth_error_fails_release_that_github_published() {
+        let mut summary = test_summary();
+        summary.add(
+            Platform::Github,
+            ReleaseStatus::Succeeded {
+                url: "https://example.com".to_string(),
+            },
+        );
+        summary.add(
+            Platform::Modrinth,
+            ReleaseStatus::Failed(
+                "Failed to upload version to Modrinth: `400 Bad Request`".to_string(),
+            ),
+        );
+
+        assert!(summary.is_published(Platform::Github));
+        match &summary.failures()[..] {
+            [failure] => assert_eq!(failure.platform, Platform::Modrinth),
+            failures => panic!("expected only Modrinth to fail, got {:?}", failures),
+        }
+
+        let err = summary.check(false).unwrap_err().to_string();
+        assert_eq!(err, "Failed to release to: Modrinth");
+        assert!(summary.check(true).is_ok());
+    }
+
+    #[test]
+    fn skipped_platforms_dont_fail_release() {
+        let mut summary = test_summary();
+        summary.add(Platform::Github, ReleaseStatus::Skipped);
+        summary.add(
+            Platform::Modrinth,
+            ReleaseStatus::Succeeded {
+                url: "https://example.com".to_string(),
+            },
+        );
+
+        assert!(summary.check(false).is_ok());
+    }
+
+    #[test]
+    fn failed_webhook_fails_release() {
+        let mut summary = test_summary();
+        summary.webhooks.push(WebhookResult {
+            notifier: Notifier::Discord,
+            target: "WEBHOOK_URL".to_string(),
+            error: Some("Failed to send Discord webhook".to_string()),
+        });
+
+        let err = summary.check(false).unwrap_err().to_string();
+        assert_eq!(err, "Failed to send some notifications");
+        assert!(summary.check(true).is_ok());
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// An incoming webhook message. `text` is only shown in notifications and by clients that
+/// can't render the blocks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlackMessage {
+    pub text: String,
+    pub blocks: Vec<Block>,
+}
+
+// Based on Slack's Block Kit layout blocks here:
+// https://api.slack.com/reference/block-kit/blocks
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Block {
+    Header { text: TextObject },
+    Section { text: TextObject },
+    Actions { elements: Vec<ButtonElement> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextObject {
+    #[serde(rename = "type")]
+    pub text_type: TextType,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TextType {
+    PlainText,
+    Mrkdwn,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ButtonElement {
+    /// Always `button`.
+    #[serde(rename = "type")]
+    pub element_type: String,
+    pub text: TextObject,
+    pub url: String,
+}
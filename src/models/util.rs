@@ -1,11 +1,218 @@
+use crate::util::clean_up;
+use std::fs;
 use std::path::PathBuf;
+use tracing::{info, warn};
 
+/// Not copied to the temporary directory, unless `copy_excludes` is set.
+pub fn default_copy_excludes() -> Vec<String> {
+    [".git", "build/", ".gradle/", "node_modules/"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// Removes the temporary directory once dropped, so that it doesn't outlive failed releases.
+///
+/// Releases made in place use the project directory itself, which is never removed. The files
+/// changed for the release are restored instead.
 pub struct TempInfo {
-    pub dir_name: String,
     pub dir_path: PathBuf,
+    pub keep: bool,
+    pub in_place: bool,
+    /// Files to restore in place, with their original contents or `None` to remove them.
+    restore: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl TempInfo {
+    pub fn new(dir_path: PathBuf) -> Self {
+        Self {
+            dir_path,
+            keep: false,
+            in_place: false,
+            restore: vec![],
+        }
+    }
+
+    pub fn in_place(dir_path: PathBuf) -> Self {
+        Self {
+            dir_path,
+            keep: false,
+            in_place: true,
+            restore: vec![],
+        }
+    }
+
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+
+    /// Restores the file to its current contents once dropped when in place, or removes it if
+    /// it doesn't exist yet.
+    pub fn restore_on_drop(&mut self, path: PathBuf) {
+        if self.in_place {
+            let contents = fs::read(&path).ok();
+            self.restore.push((path, contents));
+        }
+    }
+
+    /// Removes a file written for the release once dropped when in place.
+    pub fn remove_on_drop(&mut self, path: PathBuf) {
+        if self.in_place {
+            self.restore.push((path, None));
+        }
+    }
 }
 
+impl Drop for TempInfo {
+    fn drop(&mut self) {
+        if self.in_place {
+            // In reverse, so that the first snapshot of a file wins
+            for (path, contents) in self.restore.drain(..).rev() {
+                let result = match contents {
+                    Some(contents) => fs::write(&path, contents),
+                    None if path.exists() => fs::remove_file(&path),
+                    None => Ok(()),
+                };
+
+                if let Err(err) = result {
+                    warn!("Failed to restore `{}`: {}", path.display(), err);
+                }
+            }
+            return;
+        }
+
+        if self.keep {
+            info!("Kept temporary directory at `{}`", self.dir_path.display());
+            return;
+        }
+
+        if let Err(err) = clean_up(&self.dir_path) {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// A file written by an export or build, to be uploaded as part of the release.
+#[derive(Debug, Clone)]
 pub struct OutputFileInfo {
+    /// Name of the file, as shown on the platforms it's uploaded to.
     pub file_name: String,
     pub file_path: PathBuf,
 }
+
+#[derive(Debug)]
+pub struct ModArtifact {
+    pub jar_path: PathBuf,
+    pub sources_jar_path: Option<PathBuf>,
+    pub javadoc_jar_path: Option<PathBuf>,
+}
+
+pub struct FileChecksums {
+    pub file_name: String,
+    pub sha256: String,
+    pub sha512: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_dir;
+    use crate::util::create_temp;
+    use anyhow::anyhow;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    /// Everything logged while `f` runs.
+    fn capture_logs(f: impl FnOnce()) -> String {
+        let logs = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let writer_logs = logs.clone();
+
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || LogWriter(writer_logs.clone()))
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+
+        let logs = logs.lock().unwrap();
+        String::from_utf8_lossy(&logs).to_string()
+    }
+
+    struct LogWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn release_that_fails(
+        project_dir: &Path,
+        tmp_dir: &mut Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_info = create_temp(project_dir, &[])?;
+        *tmp_dir = Some(tmp_info.dir_path.clone());
+
+        fs::write(tmp_info.dir_path.join("export.mrpack"), "mrpack")?;
+        Err(anyhow!("Failed to upload"))
+    }
+
+    #[test]
+    fn temp_dir_is_removed_when_release_fails() {
+        let project_dir = test_dir();
+        fs::write(project_dir.dir_path.join("pack.toml"), "name = \"Test\"").unwrap();
+
+        let mut tmp_dir = None;
+        assert!(release_that_fails(&project_dir.dir_path, &mut tmp_dir).is_err());
+
+        let tmp_dir = tmp_dir.unwrap();
+        assert_ne!(tmp_dir, project_dir.dir_path);
+        assert!(!tmp_dir.exists());
+        assert!(project_dir.dir_path.join("pack.toml").exists());
+    }
+
+    #[test]
+    fn kept_temp_dir_is_logged() {
+        let mut tmp_info = test_dir();
+        let dir_path = tmp_info.dir_path.clone();
+        tmp_info.keep();
+
+        let logs = capture_logs(|| drop(tmp_info));
+
+        assert!(dir_path.exists());
+        assert!(
+            logs.contains(&format!(
+                "Kept temporary directory at `{}`",
+                dir_path.display()
+            )),
+            "{}",
+            logs
+        );
+        fs::remove_dir_all(dir_path).unwrap();
+    }
+
+    #[test]
+    fn in_place_files_are_restored() {
+        let project_dir = test_dir();
+        let pack_toml = project_dir.dir_path.join("pack.toml");
+        let export = project_dir.dir_path.join("export.mrpack");
+        fs::write(&pack_toml, "version = \"1.0.0\"").unwrap();
+
+        let mut tmp_info = TempInfo::in_place(project_dir.dir_path.clone());
+        tmp_info.restore_on_drop(pack_toml.clone());
+        tmp_info.remove_on_drop(export.clone());
+        fs::write(&pack_toml, "version = \"1.1.0\"").unwrap();
+        fs::write(&export, "mrpack").unwrap();
+        drop(tmp_info);
+
+        assert!(project_dir.dir_path.exists());
+        assert_eq!(
+            fs::read_to_string(&pack_toml).unwrap(),
+            "version = \"1.0.0\""
+        );
+        assert!(!export.exists());
+    }
+}
@@ -1,7 +1,17 @@
-use crate::models::modrinth::Loader;
+use clap::ValueEnum;
 
+/// The exported .mrpack of a modpack release.
 pub struct VersionInfo {
-    pub loader: Loader,
+    /// Display name of the version, rendered from `version_name_format`.
     pub version_name: String,
-    pub file_contents: Vec<u8>,
+    /// Size of the .mrpack file in bytes. It's streamed from disk when uploaded, never read
+    /// into memory as a whole.
+    pub file_size: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BumpLevel {
+    Major,
+    Minor,
+    Patch,
 }
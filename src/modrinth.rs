@@ -1,28 +1,31 @@
 use crate::models::modrinth::version::VersionDependency;
 use crate::models::project_type::mc_mod::config::ModConfig;
-use crate::models::project_type::mc_mod::version::ModVersionInfo;
+use crate::models::project_type::mc_mod::version::{ModFile, ModVersionInfo};
+use crate::models::project_type::pack::config::PackConfig;
 use crate::models::{
+    changelog::Changelog,
     modrinth::{
-        version::{VersionRequest, VersionStatus, VersionType},
-        ModrinthUrl,
+        version::{
+            VersionFilesRequest, VersionFilesUpdateRequest, VersionRequest, VersionResponse,
+            VersionStatus, VersionType,
+        },
+        DependencyType, ModrinthUrl,
     },
     project_type::modpack::{config::ModpackConfig, PackFile},
-    util::OutputFileInfo,
+    util::{FileChecksums, OutputFileInfo},
     version::VersionInfo,
 };
+use crate::models::{ModrinthConfig, NetworkConfig};
+use crate::progress::{UploadBar, UploadProgress};
+use crate::redact::Secret;
+use crate::util::{error_body, file_part, mime_type_from_file_name, send_with_retry};
 use anyhow::anyhow;
 use reqwest::multipart::{Form, Part};
-use std::env;
-
-#[derive(Debug)]
-pub struct VersionForm {
-    pub form: Form,
-    pub part_names: Vec<String>,
-}
+use std::collections::HashMap;
+use tracing::{info, warn};
 
 #[derive(Debug)]
 pub struct JarPart {
-    pub file_name: String,
     pub file_part: Part,
     pub file_type: FileType,
 }
@@ -31,6 +34,7 @@ pub struct JarPart {
 pub enum FileType {
     Mod,
     Sources,
+    Javadoc,
 }
 
 impl FileType {
@@ -38,184 +42,575 @@ impl FileType {
         match self {
             Self::Mod => "mod_jar",
             Self::Sources => "sources_jar",
+            Self::Javadoc => "javadoc_jar",
         }
         .to_string()
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_modpack_release(
     config: &ModpackConfig,
+    modrinth_config: &ModrinthConfig,
     pack_file: &PackFile,
     output_file_info: &OutputFileInfo,
     version_info: &VersionInfo,
-    changelog: &String,
-    modrinth_token: String,
+    changelog: &Changelog,
+    additional_files: &[OutputFileInfo],
+    checksums: &[FileChecksums],
     modrinth_url: &ModrinthUrl,
-    version_type: VersionType
-) -> Result<(), anyhow::Error> {
-    let modrinth_config = config.modrinth.clone();
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<String, anyhow::Error> {
+    let dependencies: Vec<VersionDependency> = match &modrinth_config.dependencies {
+        Some(deps) => deps.iter().cloned().map(VersionDependency::from).collect(),
+        None => vec![],
+    };
 
-    println!("Uploading to Modrinth...");
+    let project_id = modrinth_config.project_id.clone();
 
     let modrinth_req = VersionRequest {
         name: version_info.version_name.clone(),
         version_number: pack_file.version.clone(),
-        changelog: Some(changelog.to_string()),
-        dependencies: vec![],
-        game_versions: vec![pack_file.versions.minecraft.clone()],
+        changelog: Some(changelog.render_modrinth()),
+        dependencies,
+        game_versions: modrinth_config.game_versions_for(pack_file),
         version_type,
-        loaders: vec![version_info.loader],
+        loaders: modrinth_config.loaders_for(pack_file),
         featured: false,
         requested_status: VersionStatus::Listed,
-        project_id: modrinth_config.project_id,
-        file_parts: vec!["file".to_string()],
-        primary_file: output_file_info.file_name.clone(),
+        project_id: modrinth_config.project_id.clone(),
+        file_parts: modpack_part_names(additional_files),
+        primary_file: "file".to_string(),
     };
 
-    let file_part = match Part::bytes(version_info.file_contents.clone())
-        .file_name(output_file_info.file_name.clone())
-        .mime_str("application/zip")
-    {
-        Ok(part) => part,
-        Err(err) => return Err(anyhow!("Failed to get part from .mrpack file: {}", err)),
-    };
+    if dry_run {
+        info!(
+            "{}",
+            describe_modrinth_release(
+                modrinth_url,
+                &modrinth_req,
+                output_file_info,
+                additional_files
+            )?
+        );
+        return Ok(format!("{}/project/{}", modrinth_url.knossos, project_id));
+    }
 
-    let form = Form::new()
-        .text("data", serde_json::to_string(&modrinth_req).unwrap())
-        .part("file", file_part);
+    let modrinth_token = modrinth_config.token()?;
 
-    let req = match reqwest::Client::new()
-        .post(format!("{}/version", modrinth_url.labrinth))
-        .header("Authorization", &modrinth_token)
-        .multipart(form)
-        .send()
-        .await
+    info!("Uploading to Modrinth...");
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let bar = UploadBar::new(&output_file_info.file_name, version_info.file_size);
+    let progress = bar.callback();
+
+    let req = match send_with_retry(&network, |client| {
+        let form = create_modpack_form(
+            output_file_info,
+            additional_files,
+            &modrinth_req,
+            Some(&progress),
+        )?;
+
+        Ok(client
+            .post(format!("{}/version", modrinth_url.labrinth))
+            .header("Authorization", modrinth_token.expose())
+            .multipart(form)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
     {
         Ok(res) => res,
         Err(err) => return Err(anyhow!("Error uploading version: {}", err)),
     };
 
-    if req.status().is_success() {
-        Ok(println!("Successfully uploaded version to Modrinth!"))
-    } else {
-        Err(anyhow!(
+    if !req.status().is_success() {
+        return Err(anyhow!(
             "Failed to upload version to Modrinth: {}",
-            req.text().await.unwrap()
-        ))
+            error_body(req).await
+        ));
+    }
+
+    info!("Successfully uploaded version to Modrinth!");
+
+    match req.json::<VersionResponse>().await {
+        Ok(version) => {
+            verify_version_checksums(&version, checksums)?;
+            Ok(format!(
+                "{}/project/{}/version/{}",
+                modrinth_url.knossos, project_id, version.id
+            ))
+        }
+        Err(err) => {
+            warn!("Couldn't verify checksums of the uploaded files: {}", err);
+            Ok(format!("{}/project/{}", modrinth_url.knossos, project_id))
+        }
     }
 }
 
-pub async fn create_mod_release(
-    config: &ModConfig,
-    mod_files: &ModVersionInfo,
-    changelog: &String,
-    modrinth_url: &ModrinthUrl,
-    version_name: &String,
-    version_type: VersionType
+/// Makes sure that Modrinth received the same files that were hashed locally.
+pub fn verify_version_checksums(
+    version: &VersionResponse,
+    checksums: &[FileChecksums],
 ) -> Result<(), anyhow::Error> {
-    let modrinth_config = config.modrinth.clone();
-    let modrinth_token = match env::var("MODRINTH_TOKEN") {
-        Ok(token) => token,
-        Err(err) => {
+    for checksum in checksums {
+        let uploaded_file = match version
+            .files
+            .iter()
+            .find(|file| file.filename == checksum.file_name)
+        {
+            Some(file) => file,
+            None => {
+                return Err(anyhow!(
+                    "Uploaded file `{}` is missing from the Modrinth version",
+                    checksum.file_name
+                ))
+            }
+        };
+
+        if uploaded_file.hashes.sha512 != checksum.sha512 {
             return Err(anyhow!(
-                "Failed to get Modrinth token from environment: {}",
-                err
+                "SHA-512 of `{}` on Modrinth doesn't match the local file!\n  \
+                 Local: {}\n  \
+                 Modrinth: {}",
+                checksum.file_name,
+                checksum.sha512,
+                uploaded_file.hashes.sha512
+            ));
+        }
+    }
+
+    info!("Verified checksums of the uploaded files");
+
+    Ok(())
+}
+
+/// Falls back to the project page if the created version can't be read from the response,
+/// as the upload itself already succeeded at this point.
+pub async fn created_version_url(
+    modrinth_url: &ModrinthUrl,
+    project_id: &String,
+    res: reqwest::Response,
+) -> String {
+    created_version(modrinth_url, project_id, res).await.0
+}
+
+/// The URL of the created version, and the name and download URL of each of its files.
+pub async fn created_version(
+    modrinth_url: &ModrinthUrl,
+    project_id: &String,
+    res: reqwest::Response,
+) -> (String, Vec<(String, String)>) {
+    let project_url = format!("{}/project/{}", modrinth_url.knossos, project_id);
+
+    let json = match res.json::<serde_json::Value>().await {
+        Ok(json) => json,
+        Err(_) => return (project_url, vec![]),
+    };
+
+    let url = match json["id"].as_str() {
+        Some(version_id) => format!("{}/version/{}", project_url, version_id),
+        None => project_url,
+    };
+
+    let files = json["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            Some((
+                file["filename"].as_str()?.to_string(),
+                file["url"].as_str()?.to_string(),
             ))
+        })
+        .collect();
+
+    (url, files)
+}
+
+pub fn describe_modrinth_release(
+    modrinth_url: &ModrinthUrl,
+    request_data: &VersionRequest,
+    output_file_info: &OutputFileInfo,
+    additional_files: &[OutputFileInfo],
+) -> Result<String, anyhow::Error> {
+    let request_json = serde_json::to_string_pretty(request_data)
+        .map_err(|err| anyhow!("Failed to serialize version request body: {}", err))?;
+
+    let additional_file_names = additional_files
+        .iter()
+        .map(|file| file.file_name.clone())
+        .collect::<Vec<String>>();
+
+    Ok(format!(
+        "[dry run] Would create Modrinth version at `{}/version`:\n  \
+         Version number: {}\n  \
+         File: {}\n  \
+         Additional files: {}\n  \
+         Request data:\n{}",
+        modrinth_url.labrinth,
+        request_data.version_number,
+        output_file_info.file_name,
+        if additional_file_names.is_empty() {
+            "none".to_string()
+        } else {
+            additional_file_names.join(", ")
+        },
+        request_json
+    ))
+}
+
+/// Adds what the jars' metadata depends on, as long as there's a Modrinth project for the mod
+/// ID and the config doesn't already list it. Required wins when jars disagree.
+fn add_metadata_dependencies(
+    dependencies: &mut Vec<VersionDependency>,
+    mod_ids: &HashMap<String, String>,
+    mod_files: &[&ModVersionInfo],
+) {
+    let configured = dependencies
+        .iter()
+        .filter_map(|dep| dep.project_id.clone())
+        .collect::<Vec<String>>();
+    let mut unmapped: Vec<String> = vec![];
+
+    for dependency in mod_files.iter().flat_map(|files| &files.dependencies) {
+        let project_id = match mod_ids.get(&dependency.mod_id) {
+            Some(project_id) => project_id,
+            None => {
+                if !unmapped.contains(&dependency.mod_id) {
+                    unmapped.push(dependency.mod_id.clone());
+                }
+                continue;
+            }
+        };
+        if configured.contains(project_id) {
+            continue;
+        }
+
+        match dependencies
+            .iter_mut()
+            .find(|dep| dep.project_id.as_ref() == Some(project_id))
+        {
+            Some(existing) => {
+                if dependency.dependency_type == DependencyType::Required {
+                    existing.dependency_type = DependencyType::Required;
+                }
+            }
+            None => dependencies.push(VersionDependency {
+                version_id: None,
+                project_id: Some(project_id.clone()),
+                file_name: None,
+                dependency_type: dependency.dependency_type,
+            }),
         }
+    }
+
+    if !unmapped.is_empty() {
+        warn!(
+            "No Modrinth project for the mod IDs {}, add them to `modrinth.mod_ids` to list \
+             them as dependencies",
+            unmapped
+                .iter()
+                .map(|mod_id| format!("`{}`", mod_id))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+}
+
+/// One version with the jars of every `mod_files`, the first being the primary file. Returns
+/// the URL of the version and the name and download URL of each of its files.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_mod_release(
+    config: &ModConfig,
+    modrinth_config: &ModrinthConfig,
+    mod_files: &[&ModVersionInfo],
+    changelog: &Changelog,
+    modrinth_url: &ModrinthUrl,
+    version_name: &String,
+    version_type: VersionType,
+    dry_run: bool,
+) -> Result<(String, Vec<(String, String)>), anyhow::Error> {
+    let first = match mod_files.first() {
+        Some(first) => first,
+        None => return Err(anyhow!("No mod jars to upload")),
     };
 
-    println!("Uploading to Modrinth...");
+    let mut file_part_names = vec![];
+    let mut loaders = vec![];
+    let mut game_versions: Vec<String> = vec![];
 
-    let mut file_part_names = vec![FileType::Mod.part_name()];
+    for (index, files) in mod_files.iter().enumerate() {
+        file_part_names.push(mod_part_name(FileType::Mod, index));
+        for (file_type, _) in extra_files(files) {
+            file_part_names.push(mod_part_name(file_type, index));
+        }
 
-    if mod_files.sources_file.is_some() {
-        file_part_names.push(FileType::Sources.part_name())
+        for loader in &files.loaders {
+            if !loaders.contains(loader) {
+                loaders.push(*loader);
+            }
+        }
+        for game_version in &files.game_versions {
+            if !game_versions.contains(game_version) {
+                game_versions.push(game_version.clone());
+            }
+        }
     }
 
     let mut dependencies: Vec<VersionDependency> = vec![];
 
-    if modrinth_config.dependencies.is_some() {
-        for dep in modrinth_config.dependencies.unwrap() {
+    if let Some(deps) = modrinth_config.dependencies.clone() {
+        for dep in deps {
             dependencies.push(VersionDependency::from(dep))
         }
     }
+    add_metadata_dependencies(&mut dependencies, &modrinth_config.mod_ids(), mod_files);
+
+    let project_id = modrinth_config.project_id.clone();
 
     let form_data = VersionRequest {
         name: version_name.into(),
-        version_number: mod_files.version.to_owned(),
-        changelog: Some(changelog.to_string()),
+        version_number: first.version.to_owned(),
+        changelog: Some(changelog.render_modrinth()),
         dependencies,
-        game_versions: config.mc_versions.to_owned(),
+        game_versions,
         version_type,
-        loaders: config.loaders.to_owned(),
+        loaders,
         featured: false,
         requested_status: VersionStatus::Listed,
-        project_id: modrinth_config.project_id,
+        project_id: modrinth_config.project_id.clone(),
         file_parts: file_part_names,
-        primary_file: FileType::Mod.part_name(),
+        primary_file: mod_part_name(FileType::Mod, 0),
     };
 
-    let form = match create_mod_form(mod_files, &form_data).await {
-        Ok(form) => form,
-        Err(err) => return Err(anyhow!("Failed to create mod form: {}", err)),
-    };
+    if dry_run {
+        let file_info = |file: &ModFile| OutputFileInfo {
+            file_name: file.name.clone(),
+            file_path: file.path.clone(),
+        };
+        // Every file but the primary one, in the order of the parts
+        let additional_files = mod_files
+            .iter()
+            .flat_map(|files| {
+                std::iter::once(&files.mod_file)
+                    .chain(extra_files(files).into_iter().map(|(_, file)| file))
+            })
+            .skip(1)
+            .map(file_info)
+            .collect::<Vec<OutputFileInfo>>();
+        info!(
+            "{}",
+            describe_modrinth_release(
+                modrinth_url,
+                &form_data,
+                &file_info(&first.mod_file),
+                &additional_files
+            )?
+        );
+        return Ok((
+            format!("{}/project/{}", modrinth_url.knossos, project_id),
+            vec![],
+        ));
+    }
 
-    let req = match reqwest::Client::new()
-        .post(format!("{}/version", modrinth_url.labrinth))
-        .header("Authorization", &modrinth_token)
-        .multipart(form)
-        .send()
-        .await
+    let modrinth_token = modrinth_config.token()?;
+
+    info!("Uploading to Modrinth...");
+
+    let network = config.project.network.clone().unwrap_or_default();
+
+    let req = match send_with_retry(&network, |client| {
+        let form = match create_mod_form(mod_files, &form_data) {
+            Ok(form) => form,
+            Err(err) => return Err(anyhow!("Failed to create mod form: {}", err)),
+        };
+
+        Ok(client
+            .post(format!("{}/version", modrinth_url.labrinth))
+            .header("Authorization", modrinth_token.expose())
+            .multipart(form)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
     {
         Ok(res) => res,
         Err(err) => return Err(anyhow!("Error uploading mod version: {}", err)),
     };
 
     if req.status().is_success() {
-        Ok(println!("Successfully uploaded version to Modrinth!"))
+        info!("Successfully uploaded version to Modrinth!");
+        Ok(created_version(modrinth_url, &project_id, req).await)
+    } else {
+        Err(anyhow!(
+            "Failed to upload version to Modrinth: {}",
+            error_body(req).await
+        ))
+    }
+}
+
+/// The jars of the first artifact keep the part names of single jar releases.
+fn mod_part_name(file_type: FileType, index: usize) -> String {
+    match index {
+        0 => file_type.part_name(),
+        _ => format!("{}_{}", file_type.part_name(), index),
+    }
+}
+
+/// Uploads the zip of a resource pack or data pack, tagged with the loader Modrinth uses for
+/// its project type.
+pub async fn create_pack_release(
+    config: &PackConfig,
+    output_file_info: &OutputFileInfo,
+    changelog: &Changelog,
+    modrinth_url: &ModrinthUrl,
+    version_name: &str,
+    version_number: &str,
+    version_type: VersionType,
+) -> Result<String, anyhow::Error> {
+    let modrinth_token = config.modrinth.token()?;
+    let network = config.network.clone().unwrap_or_default();
+
+    info!("Uploading to Modrinth...");
+
+    let form_data = VersionRequest {
+        name: version_name.to_string(),
+        version_number: version_number.to_string(),
+        changelog: Some(changelog.render_modrinth()),
+        dependencies: config
+            .modrinth
+            .dependencies
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(VersionDependency::from)
+            .collect(),
+        game_versions: config.mc_versions.clone(),
+        version_type,
+        loaders: vec![config.project_type.loader()],
+        featured: false,
+        requested_status: VersionStatus::Listed,
+        project_id: config.modrinth.project_id.clone(),
+        file_parts: modpack_part_names(&[]),
+        primary_file: "file".to_string(),
+    };
+
+    let res = match send_with_retry(&network, |client| {
+        let form = create_modpack_form(output_file_info, &[], &form_data, None)?;
+
+        Ok(client
+            .post(format!("{}/version", modrinth_url.labrinth))
+            .header("Authorization", modrinth_token.expose())
+            .multipart(form)
+            .timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Error uploading pack version: {}", err)),
+    };
+
+    if res.status().is_success() {
+        info!("Successfully uploaded version to Modrinth!");
+        Ok(created_version_url(modrinth_url, &config.modrinth.project_id, res).await)
     } else {
         Err(anyhow!(
             "Failed to upload version to Modrinth: {}",
-            req.text().await.unwrap()
+            error_body(res).await
         ))
     }
 }
 
-pub async fn create_mod_form(
-    mod_files: &ModVersionInfo,
+/// The .mrpack is always uploaded as the `file` part, and is the primary file.
+pub fn modpack_part_names(additional_files: &[OutputFileInfo]) -> Vec<String> {
+    let mut part_names = vec!["file".to_string()];
+
+    for index in 1..=additional_files.len() {
+        part_names.push(format!("file_{}", index))
+    }
+
+    part_names
+}
+
+/// The files are streamed from disk, the .mrpack reporting to `progress`.
+pub fn create_modpack_form(
+    output_file_info: &OutputFileInfo,
+    additional_files: &[OutputFileInfo],
     request_data: &VersionRequest,
+    progress: Option<&UploadProgress>,
 ) -> Result<Form, anyhow::Error> {
-    let mut file_names: Vec<String> = vec![mod_files.mod_file.clone().name];
-
-    let mod_part = JarPart {
-        file_name: mod_files.mod_file.name.clone(),
-        file_part: Part::bytes(mod_files.mod_file.contents.clone())
-            .file_name(mod_files.mod_file.clone().name)
-            .mime_str("application/java-archive")?,
-        file_type: FileType::Mod,
-    };
+    let mrpack_part = file_part(
+        &output_file_info.file_path,
+        &output_file_info.file_name,
+        "application/zip",
+        progress,
+    )?;
 
-    let sources_part = match &mod_files.sources_file {
-        Some(file) => {
-            file_names.push(file.name.to_owned());
+    let form_data = serde_json::to_string(request_data)
+        .map_err(|err| anyhow!("Failed to serialize version request body: {}", err))?;
 
-            let part = Part::bytes(file.contents.clone())
-                .file_name(file.name.clone())
-                .mime_str("application/java-archive")?;
+    let mut form = Form::new()
+        .text("data", form_data)
+        .part("file", mrpack_part);
 
-            Some(JarPart {
-                file_name: file.name.clone(),
-                file_part: part,
-                file_type: FileType::Sources,
-            })
-        }
-        None => None,
-    };
+    let part_names = modpack_part_names(additional_files);
 
-    let mut file_parts = vec![mod_part];
+    for (file, part_name) in additional_files.iter().zip(part_names.iter().skip(1)) {
+        let part = file_part(
+            &file.file_path,
+            &file.file_name,
+            mime_type_from_file_name(&file.file_name),
+            None,
+        )?;
 
-    if let Some(sources_part_val) = sources_part {
-        file_parts.push(sources_part_val);
+        form = form.part(part_name.clone(), part);
+    }
+
+    Ok(form)
+}
+
+/// Files uploaded next to the mod jar, which isn't primary.
+fn extra_files(files: &ModVersionInfo) -> Vec<(FileType, &ModFile)> {
+    [
+        (FileType::Sources, files.sources_file.as_ref()),
+        (FileType::Javadoc, files.javadoc_file.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(file_type, file)| file.map(|file| (file_type, file)))
+    .collect()
+}
+
+pub fn create_mod_form(
+    mod_files: &[&ModVersionInfo],
+    request_data: &VersionRequest,
+) -> Result<Form, anyhow::Error> {
+    let mut file_parts = vec![];
+
+    for (index, files) in mod_files.iter().enumerate() {
+        file_parts.push((
+            mod_part_name(FileType::Mod, index),
+            JarPart {
+                file_part: file_part(
+                    &files.mod_file.path,
+                    &files.mod_file.name,
+                    "application/java-archive",
+                    None,
+                )?,
+                file_type: FileType::Mod,
+            },
+        ));
+
+        for (file_type, file) in extra_files(files) {
+            file_parts.push((
+                mod_part_name(file_type, index),
+                JarPart {
+                    file_part: file_part(&file.path, &file.name, "application/java-archive", None)?,
+                    file_type,
+                },
+            ));
+        }
     }
 
     let form_data = serde_json::to_string(request_data)
@@ -223,9 +618,314 @@ pub async fn create_mod_form(
 
     let mut form = Form::new().text("data", form_data);
 
-    for part in file_parts {
-        form = form.part(part.file_type.part_name(), part.file_part);
+    for (part_name, part) in file_parts {
+        form = form.part(part_name, part.file_part);
     }
 
     Ok(form)
 }
+
+pub async fn get_project_versions(
+    modrinth_url: &ModrinthUrl,
+    project_id: &String,
+    modrinth_token: Option<Secret>,
+    network: &NetworkConfig,
+) -> Result<Vec<VersionResponse>, anyhow::Error> {
+    // Listed versions can be read without a token, but drafts and unlisted versions can't.
+    let res = match send_with_retry(network, |client| {
+        let req = client.get(format!(
+            "{}/project/{}/version",
+            modrinth_url.labrinth, project_id
+        ));
+
+        Ok(match &modrinth_token {
+            Some(token) => req.header("Authorization", token.expose()),
+            None => req,
+        })
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get Modrinth project versions: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to get Modrinth project versions: `{}`",
+            res.status()
+        ));
+    }
+
+    match res.json::<Vec<VersionResponse>>().await {
+        Ok(versions) => Ok(versions),
+        Err(err) => Err(anyhow!(
+            "Failed to parse Modrinth project versions: {}",
+            err
+        )),
+    }
+}
+
+/// Versions of the files with these hashes, keyed by hash. `algorithm` is `sha1` or `sha512`,
+/// hashes Modrinth doesn't know are left out.
+pub async fn get_versions_from_hashes(
+    modrinth_url: &ModrinthUrl,
+    hashes: Vec<String>,
+    algorithm: &str,
+    network: &NetworkConfig,
+) -> Result<HashMap<String, VersionResponse>, anyhow::Error> {
+    let request = VersionFilesRequest {
+        hashes,
+        algorithm: algorithm.to_string(),
+    };
+
+    post_version_files(modrinth_url, "version_files", &request, network).await
+}
+
+/// The newest version for these loaders and game versions of the project of every file,
+/// keyed by the hash of the file.
+pub async fn get_latest_versions_from_hashes(
+    modrinth_url: &ModrinthUrl,
+    request: &VersionFilesUpdateRequest,
+    network: &NetworkConfig,
+) -> Result<HashMap<String, VersionResponse>, anyhow::Error> {
+    post_version_files(modrinth_url, "version_files/update", request, network).await
+}
+
+async fn post_version_files<T: serde::Serialize>(
+    modrinth_url: &ModrinthUrl,
+    path: &str,
+    request: &T,
+    network: &NetworkConfig,
+) -> Result<HashMap<String, VersionResponse>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .post(format!("{}/{}", modrinth_url.labrinth, path))
+            .json(request))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get Modrinth versions: {}", err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to get Modrinth versions: `{}`",
+            res.status()
+        ));
+    }
+
+    match res.json::<HashMap<String, VersionResponse>>().await {
+        Ok(versions) => Ok(versions),
+        Err(err) => Err(anyhow!("Failed to parse Modrinth versions: {}", err)),
+    }
+}
+
+/// Modrinth versions have both a version number and a display name, and either colliding
+/// is confusing for users, so both are compared.
+pub fn find_existing_version<'a>(
+    versions: &'a [VersionResponse],
+    version_number: &str,
+    version_name: &str,
+) -> Option<&'a VersionResponse> {
+    versions
+        .iter()
+        .find(|ver| ver.version_number == version_number || ver.name == version_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::modrinth::Loader;
+    use crate::models::project_type::mc_mod::version::ModFile;
+    use crate::test_support::{test_dir, upload_sink};
+    use std::fs::File;
+
+    const MOD_CONFIG: &str = r#"
+config_format_version = 1
+version_name_format = "%project_name% %project_version%"
+mc_versions = ["1.20.1"]
+mc_version_alias = "1.20.1"
+
+[github]
+repo_owner = "LilydevMC"
+repo_name = "peony-test"
+
+[modrinth]
+project_id = "AABBCCDD"
+token_env = "PEONY_TEST_MODRINTH_TOKEN"
+"#;
+
+    fn mod_version_info(mod_file: ModFile) -> ModVersionInfo {
+        ModVersionInfo {
+            name: "Test Mod 1.0.0".to_string(),
+            version: "1.0.0".to_string(),
+            loaders: vec![Loader::Fabric],
+            game_versions: vec!["1.20.1".to_string()],
+            dependencies: vec![],
+            mod_file,
+            sources_file: None,
+            javadoc_file: None,
+        }
+    }
+
+    fn version_json(id: &str, version_number: &str, name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "project_id": "AABBCCDD",
+            "name": name,
+            "version_number": version_number,
+            "changelog": null,
+            "version_type": "release",
+            "date_published": "2024-01-01T00:00:00Z",
+            "game_versions": ["1.20.1"],
+            "loaders": ["quilt"],
+            "files": [],
+        })
+    }
+
+    fn test_versions() -> Vec<VersionResponse> {
+        serde_json::from_value(serde_json::json!([
+            version_json("VERSION1", "1.0.0", "Test Pack 1.0.0"),
+            version_json("VERSION2", "1.1.0", "Test Pack 1.1.0 (hotfix)"),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn existing_version_by_version_number() {
+        let versions = test_versions();
+        let existing = find_existing_version(&versions, "1.1.0", "Test Pack 1.1.0");
+
+        assert_eq!(existing.map(|ver| ver.id.as_str()), Some("VERSION2"));
+    }
+
+    #[test]
+    fn existing_version_by_version_name() {
+        let versions = test_versions();
+        let existing = find_existing_version(&versions, "1.2.0", "Test Pack 1.0.0");
+
+        assert_eq!(existing.map(|ver| ver.id.as_str()), Some("VERSION1"));
+    }
+
+    #[test]
+    fn no_existing_version() {
+        let versions = test_versions();
+
+        assert!(find_existing_version(&versions, "1.2.0", "Test Pack 1.2.0").is_none());
+        // Version numbers are compared exactly, not as prefixes
+        assert!(find_existing_version(&versions, "1.0", "Test Pack 1.0").is_none());
+    }
+
+    #[tokio::test]
+    async fn finds_existing_version_of_project() {
+        let mut server = mockito::Server::new_async().await;
+        let list_versions = server
+            .mock("GET", "/project/AABBCCDD/version")
+            .with_status(200)
+            .with_body(
+                serde_json::json!([version_json("VERSION1", "1.0.0", "Test Pack 1.0.0")])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let modrinth_url = ModrinthUrl {
+            labrinth: server.url(),
+            knossos: "https://modrinth.com".to_string(),
+        };
+        let versions = get_project_versions(
+            &modrinth_url,
+            &"AABBCCDD".to_string(),
+            None,
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        list_versions.assert_async().await;
+        assert!(find_existing_version(&versions, "1.0.0", "Test Pack 1.0.0 (again)").is_some());
+        assert!(find_existing_version(&versions, "1.0.1", "Test Pack 1.0.1").is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_project_fails_version_check() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/project/AABBCCDD/version")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let modrinth_url = ModrinthUrl {
+            labrinth: server.url(),
+            knossos: "https://modrinth.com".to_string(),
+        };
+        let err = get_project_versions(
+            &modrinth_url,
+            &"AABBCCDD".to_string(),
+            None,
+            &NetworkConfig::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().contains("404"), "{}", err);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn mod_upload_streams_large_jar() {
+        use crate::test_support::peak_memory;
+
+        const JAR_SIZE: u64 = 512 * 1024 * 1024;
+
+        let dir = test_dir();
+        let jar_path = dir.dir_path.join("test-mod-1.0.0.jar");
+        // Sparse, so the test doesn't write half a gigabyte
+        File::create(&jar_path).unwrap().set_len(JAR_SIZE).unwrap();
+
+        let config: ModConfig = toml::from_str(MOD_CONFIG).unwrap();
+        std::env::set_var("PEONY_TEST_MODRINTH_TOKEN", "test-token");
+
+        let info = mod_version_info(ModFile {
+            name: "test-mod-1.0.0.jar".to_string(),
+            path: jar_path,
+            size: JAR_SIZE,
+        });
+
+        let (url, server) = upload_sink(r#"{"id": "VERSION1", "files": []}"#.to_string()).await;
+        let modrinth_url = ModrinthUrl {
+            labrinth: url,
+            knossos: "https://modrinth.com".to_string(),
+        };
+
+        let memory_before = peak_memory();
+        let (version_url, _) = create_mod_release(
+            &config,
+            config.modrinth().unwrap(),
+            &[&info],
+            &Changelog::default(),
+            &modrinth_url,
+            &info.name,
+            VersionType::Release,
+            false,
+        )
+        .await
+        .unwrap();
+        let memory_growth = peak_memory().saturating_sub(memory_before);
+
+        let (head, received) = server.await.unwrap();
+        assert!(head.starts_with("POST /version "), "{}", head);
+        assert!(received > JAR_SIZE);
+        assert_eq!(
+            version_url,
+            "https://modrinth.com/project/AABBCCDD/version/VERSION1"
+        );
+        assert!(
+            memory_growth < JAR_SIZE / 4,
+            "uploading grew memory by {} bytes",
+            memory_growth
+        );
+    }
+}
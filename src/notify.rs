@@ -0,0 +1,142 @@
+//! `[[notify.http]]`, requests with a templated body to endpoints of the user's choosing.
+
+use crate::models::{
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    HttpNotifierConfig, NetworkConfig, NotifyConfig, TemplateEscape,
+};
+use crate::util::{error_body, send_with_retry};
+use crate::version::render_template;
+use anyhow::anyhow;
+use reqwest::header::CONTENT_TYPE;
+use tracing::{info, warn};
+
+/// How much of an error response is shown in the summary.
+const ERROR_BODY_SNIPPET_CHARS: usize = 200;
+
+/// Sends every configured request, one failing doesn't stop the others.
+pub async fn send_http_notifications(
+    notify_config: &NotifyConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<Vec<WebhookResult>, anyhow::Error> {
+    let mut results = vec![];
+
+    for (index, notifier_config) in notify_config.http().iter().enumerate() {
+        let name = notifier_config.name(index);
+        let body = match &notifier_config.body {
+            Some(template) => Some(render_body(notifier_config, template, release)?),
+            None => None,
+        };
+
+        if dry_run {
+            info!(
+                "[dry run] Would send HTTP notifier `{}`:\n{}",
+                name,
+                body.as_deref().unwrap_or("(no body)")
+            );
+            continue;
+        }
+
+        let error = match send_request(notifier_config, body, network).await {
+            Ok(_) => {
+                info!("Sent HTTP notifier `{}`!", name);
+                None
+            }
+            Err(err) => {
+                let err = anyhow!("HTTP notifier `{}` failed: {}", name, err);
+                warn!("{}", err);
+                Some(err.to_string())
+            }
+        };
+
+        results.push(WebhookResult {
+            notifier: Notifier::Http,
+            target: name,
+            error,
+        });
+    }
+
+    Ok(results)
+}
+
+pub fn render_body(
+    notifier_config: &HttpNotifierConfig,
+    template: &str,
+    release: &ReleaseSummary,
+) -> Result<String, anyhow::Error> {
+    let url = |platform| release.url(platform).cloned().unwrap_or_default();
+
+    let values = [
+        ("version", release.version_number.clone()),
+        ("version_name", release.version_name.clone()),
+        ("version_type", release.version_type.formatted()),
+        ("changelog", release.changelog.render_github()),
+        ("file_name", release.file_name.clone().unwrap_or_default()),
+        ("sha512", release.sha512.clone().unwrap_or_default()),
+        ("github_url", url(Platform::Github)),
+        ("gitlab_url", url(Platform::Gitlab)),
+        ("gitea_url", url(Platform::Gitea)),
+        ("modrinth_url", url(Platform::Modrinth)),
+        ("curseforge_url", url(Platform::Curseforge)),
+        (
+            "server_pack_url",
+            release.server_pack_url.clone().unwrap_or_default(),
+        ),
+    ]
+    .map(|(key, value)| match notifier_config.escape() {
+        TemplateEscape::Json => (key, escape_json(&value)),
+        TemplateEscape::None => (key, value),
+    });
+
+    let placeholders = values
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect::<Vec<(&str, &str)>>();
+
+    render_template("notify.http body", template, &placeholders)
+}
+
+/// The value as it would be inside a JSON string, without the quotes.
+fn escape_json(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+async fn send_request(
+    notifier_config: &HttpNotifierConfig,
+    body: Option<String>,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    let url = notifier_config.url()?;
+    let method = notifier_config.method()?;
+
+    let res = send_with_retry(network, |client| {
+        let mut request = client.request(method.clone(), url.expose());
+
+        for (name, value) in notifier_config.headers.iter().flatten() {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &body {
+            request = request
+                .header(CONTENT_TYPE, notifier_config.content_type())
+                .body(body.clone());
+        }
+
+        Ok(request)
+    })
+    .await?;
+
+    let status = res.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = error_body(res).await;
+    let snippet = match body.char_indices().nth(ERROR_BODY_SNIPPET_CHARS) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body,
+    };
+
+    Err(anyhow!("`{}`: {}", status, snippet.trim()))
+}
@@ -0,0 +1,215 @@
+//! `peony outdated`, the mods of the pack that have newer versions on Modrinth.
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::mod_list::read_metafile;
+use crate::models::{
+    modrinth::{version::VersionFilesUpdateRequest, Loader, ModrinthUrl},
+    outdated::OutdatedMod,
+    project_type::modpack::{index::PackwizMetafile, PackFile},
+    NetworkConfig,
+};
+use crate::modrinth::{get_latest_versions_from_hashes, get_versions_from_hashes};
+use crate::pack::read_packwiz_index;
+
+/// Only metafiles in the mods folder that download from Modrinth are checked, their file is
+/// looked up by the hash packwiz stores for it.
+pub async fn find_outdated_mods(
+    project_dir: &Path,
+    pack_file: &PackFile,
+    modrinth_url: &ModrinthUrl,
+    network: &NetworkConfig,
+) -> Result<Vec<OutdatedMod>, anyhow::Error> {
+    let metafiles = read_modrinth_metafiles(project_dir, pack_file)?;
+    info!("Checking {} mods for updates...", metafiles.len());
+
+    let mut loaders = pack_file.versions.loaders();
+    // Quilt loads Fabric mods too
+    if pack_file.versions.quilt.is_some() && pack_file.versions.fabric.is_none() {
+        loaders.push(Loader::Fabric);
+    }
+
+    let mut game_versions = vec![pack_file.versions.minecraft.clone()];
+    if let Some(acceptable) = pack_file
+        .options
+        .as_ref()
+        .and_then(|options| options.acceptable_game_versions.as_ref())
+    {
+        game_versions.extend(acceptable.iter().cloned());
+    }
+
+    let mut by_algorithm: HashMap<String, Vec<(String, PackwizMetafile)>> = HashMap::new();
+    for (hash_format, hash, metafile) in metafiles {
+        by_algorithm
+            .entry(hash_format)
+            .or_default()
+            .push((hash, metafile));
+    }
+
+    let mut outdated = vec![];
+
+    for (algorithm, metafiles) in by_algorithm {
+        let hashes = metafiles
+            .iter()
+            .map(|(hash, _)| hash.clone())
+            .collect::<Vec<String>>();
+
+        let current_versions =
+            get_versions_from_hashes(modrinth_url, hashes.clone(), &algorithm, network).await?;
+        let latest_versions = get_latest_versions_from_hashes(
+            modrinth_url,
+            &VersionFilesUpdateRequest {
+                hashes,
+                algorithm,
+                loaders: loaders.clone(),
+                game_versions: game_versions.clone(),
+            },
+            network,
+        )
+        .await?;
+
+        for (hash, metafile) in metafiles {
+            let (current, latest) = match (current_versions.get(&hash), latest_versions.get(&hash))
+            {
+                (Some(current), Some(latest)) => (current, latest),
+                (None, _) => {
+                    warn!(
+                        "`{}` isn't a known Modrinth file, skipping it",
+                        metafile.filename
+                    );
+                    continue;
+                }
+                (Some(_), None) => continue,
+            };
+
+            // Versions are compared by date, so that a newer file of an older version
+            // isn't suggested
+            if latest.id == current.id || latest.date_published <= current.date_published {
+                continue;
+            }
+
+            outdated.push(OutdatedMod {
+                name: metafile.name,
+                project_id: latest.project_id.clone(),
+                current_version: current.version_number.clone(),
+                latest_version: latest.version_number.clone(),
+                latest_published: latest.date_published.clone(),
+                age_days: DateTime::parse_from_rfc3339(&latest.date_published)
+                    .ok()
+                    .map(|published| (Utc::now() - published.with_timezone(&Utc)).num_days()),
+            });
+        }
+    }
+
+    outdated.sort_by_key(|outdated| outdated.name.to_lowercase());
+
+    Ok(outdated)
+}
+
+/// Hash format, hash and contents of every readable metafile with a Modrinth update section.
+fn read_modrinth_metafiles(
+    project_dir: &Path,
+    pack_file: &PackFile,
+) -> Result<Vec<(String, String, PackwizMetafile)>, anyhow::Error> {
+    let index = read_packwiz_index(project_dir, pack_file)?;
+    let index_dir = match project_dir.join(&pack_file.index.file).parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => return Err(anyhow!("Failed to get packwiz index directory")),
+    };
+
+    let mods_folder = format!(
+        "{}/",
+        pack_file
+            .options
+            .as_ref()
+            .and_then(|options| options.mods_folder.as_deref())
+            .unwrap_or("mods")
+    );
+
+    let mut metafiles = vec![];
+
+    for file in &index.files {
+        let path = file.alias.as_ref().unwrap_or(&file.file);
+        if !file.is_metafile() || !path.starts_with(&mods_folder) {
+            continue;
+        }
+
+        let metafile = match read_metafile(&index_dir.join(&file.file)) {
+            Ok(metafile) => metafile,
+            Err(err) => {
+                warn!("{}, skipping it", err);
+                continue;
+            }
+        };
+
+        let is_modrinth = metafile
+            .update
+            .as_ref()
+            .is_some_and(|update| update.modrinth.is_some());
+
+        match &metafile.download {
+            Some(download)
+                if is_modrinth && matches!(download.hash_format.as_str(), "sha1" | "sha512") =>
+            {
+                metafiles.push((
+                    download.hash_format.clone(),
+                    download.hash.clone(),
+                    metafile.clone(),
+                ))
+            }
+            _ => (),
+        }
+    }
+
+    Ok(metafiles)
+}
+
+/// Columns padded to the longest value, for the terminal.
+pub fn outdated_table(outdated: &[OutdatedMod]) -> String {
+    let age = |outdated: &OutdatedMod| match outdated.age_days {
+        Some(1) => "1 day".to_string(),
+        Some(days) => format!("{} days", days),
+        None => "unknown".to_string(),
+    };
+
+    let mut rows = vec![[
+        "Mod".to_string(),
+        "Current".to_string(),
+        "Latest".to_string(),
+        "Age".to_string(),
+    ]];
+    rows.extend(outdated.iter().map(|outdated| {
+        [
+            outdated.name.clone(),
+            outdated.current_version.clone(),
+            outdated.latest_version.clone(),
+            age(outdated),
+        ]
+    }));
+
+    let widths = (0..4)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<usize>>();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:width$}", value, width = width))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
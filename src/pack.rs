@@ -1,44 +1,288 @@
 use anyhow::anyhow;
 use glob::glob;
-use std::fs;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Seek};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+use tracing::warn;
 
 use crate::{
     models::{
-        project_type::modpack::PackFile,
-        util::{OutputFileInfo, TempInfo},
+        project_type::modpack::{
+            index::{MrpackContents, MrpackIndex, PackwizIndex},
+            PackFile,
+        },
+        util::{ModArtifact, OutputFileInfo, TempInfo},
+        ServerPackConfig,
     },
-    util::clean_up,
+    progress::Spinner,
+    util::file_name_from_path,
 };
 
-pub fn get_pack_file() -> Result<PackFile, anyhow::Error> {
-    let file = match fs::read_to_string("pack.toml") {
+pub fn get_pack_file(project_dir: &Path) -> Result<PackFile, anyhow::Error> {
+    let pack_file_path = project_dir.join("pack.toml");
+
+    let file = match fs::read_to_string(&pack_file_path) {
         Ok(file) => file,
-        Err(err) => return Err(anyhow!("Failed to read pack.toml file: {}", err)),
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to read pack.toml file at `{}`: {}",
+                pack_file_path.display(),
+                err
+            ))
+        }
     };
 
     let file_parsed: Result<PackFile, anyhow::Error> = match toml::from_str(file.as_str()) {
         Ok(pack) => Ok(pack),
-        Err(err) => Err(anyhow!("Failed to parse pack.toml file: {}", err)),
+        Err(err) => Err(anyhow!(
+            "Failed to parse pack.toml file at `{}`: {}",
+            pack_file_path.display(),
+            err
+        )),
     };
 
     file_parsed
 }
 
-pub fn write_pack_file(dir_path: &PathBuf, file_contents: String) -> Result<(), anyhow::Error> {
-    match fs::write(Path::new(dir_path).join("pack.toml"), file_contents) {
-        Ok(_) => Ok(()),
+/// Exports to `output_file_name` in the pack directory, or the name packwiz picks for it.
+pub fn export_modpack(
+    packwiz: &Path,
+    tmp_dir_info: &TempInfo,
+    format: &str,
+    output_file_name: Option<&str>,
+    verbose: bool,
+) -> Result<(), anyhow::Error> {
+    // packwiz prints its own output when verbose
+    let _spinner = (!verbose).then(|| Spinner::new(&format!("Exporting `{}` pack...", format)));
+
+    match output_file_name {
+        Some(file_name) => run_packwiz(
+            packwiz,
+            tmp_dir_info,
+            &[format, "export", "--output", file_name],
+            verbose,
+        ),
+        None => run_packwiz(packwiz, tmp_dir_info, &[format, "export"], verbose),
+    }
+}
+
+/// Runs `packwiz refresh` so that the export never uses a stale index. Returns whether the
+/// index changed, which means the user's committed index is stale too, as only the temporary
+/// copy was refreshed.
+pub fn refresh_modpack(
+    packwiz: &Path,
+    tmp_dir_info: &TempInfo,
+    pack_file: &PackFile,
+    verbose: bool,
+) -> Result<bool, anyhow::Error> {
+    let index_path = tmp_dir_info.dir_path.join(&pack_file.index.file);
+    let index_before = fs::read(&index_path).ok();
+
+    run_packwiz(packwiz, tmp_dir_info, &["refresh"], verbose)?;
+
+    Ok(fs::read(&index_path).ok() != index_before)
+}
+
+/// Removes the files left out of the server pack from `server_tmp_dir_info`, a copy of the
+/// pack, and refreshes its index so that they aren't exported. Returns how many were removed.
+pub fn prepare_server_pack(
+    packwiz: &Path,
+    server_tmp_dir_info: &TempInfo,
+    pack_file: &PackFile,
+    server_pack_config: &ServerPackConfig,
+    verbose: bool,
+) -> Result<usize, anyhow::Error> {
+    let exclude_sides = server_pack_config.exclude_sides();
+
+    let mut exclude_patterns = vec![];
+    for pattern in server_pack_config.exclude.iter().flatten() {
+        match glob::Pattern::new(pattern) {
+            Ok(pattern) => exclude_patterns.push(pattern),
+            Err(err) => {
+                return Err(anyhow!(
+                    "Invalid server pack exclude pattern `{}`: {}",
+                    pattern,
+                    err
+                ))
+            }
+        }
+    }
+
+    let index = read_packwiz_index(&server_tmp_dir_info.dir_path, pack_file)?;
+    let index_dir = match server_tmp_dir_info
+        .dir_path
+        .join(&pack_file.index.file)
+        .parent()
+    {
+        Some(dir) => dir.to_path_buf(),
+        None => return Err(anyhow!("Failed to get packwiz index directory")),
+    };
+
+    let mut removed = 0;
+
+    for file in &index.files {
+        let file_path = index_dir.join(&file.file);
+
+        let excluded = exclude_patterns
+            .iter()
+            .any(|pattern| pattern.matches(&file.file))
+            || (file.is_metafile()
+                && metafile_side(&file_path)?.is_some_and(|side| exclude_sides.contains(&side)));
+
+        if !excluded {
+            continue;
+        }
+
+        if let Err(err) = fs::remove_file(&file_path) {
+            return Err(anyhow!(
+                "Failed to remove `{}` from the server pack: {}",
+                file.file,
+                err
+            ));
+        }
+        removed += 1;
+    }
+
+    run_packwiz(packwiz, server_tmp_dir_info, &["refresh"], verbose)?;
+
+    Ok(removed)
+}
+
+fn metafile_side(path: &Path) -> Result<Option<String>, anyhow::Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    };
+
+    match contents.parse::<toml::Table>() {
+        Ok(metafile) => Ok(metafile
+            .get("side")
+            .and_then(|side| side.as_str())
+            .map(String::from)),
+        Err(err) => Err(anyhow!("Failed to parse `{}`: {}", path.display(), err)),
+    }
+}
+
+/// In verbose mode packwiz writes straight to the terminal, otherwise its output is only
+/// shown when it fails.
+fn run_packwiz(
+    packwiz: &Path,
+    tmp_dir_info: &TempInfo,
+    args: &[&str],
+    verbose: bool,
+) -> Result<(), anyhow::Error> {
+    let command_line = format!("packwiz {}", args.join(" "));
+
+    let mut packwiz_command = Command::new(packwiz);
+    let packwiz_command = packwiz_command
+        .args(args)
+        .current_dir(&tmp_dir_info.dir_path);
+
+    if verbose {
+        return match packwiz_command.status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(anyhow!("{} failed with {}", command_line, status)),
+            Err(err) => Err(anyhow!("Failed to run `{}`: {}", command_line, err)),
+        };
+    }
+
+    let output = match packwiz_command.output() {
+        Ok(output) => output,
+        Err(err) => return Err(anyhow!("Failed to run `{}`: {}", command_line, err)),
+    };
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} failed with {}\n\
+             stdout:\n{}\n\
+             stderr:\n{}",
+            command_line,
+            output.status,
+            String::from_utf8_lossy(&output.stdout).trim_end(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ))
+    }
+}
+
+/// The `version` of a pack.toml, with where it is in the file.
+#[derive(Deserialize)]
+struct PackVersion {
+    version: toml::Spanned<String>,
+}
+
+/// Only the `version` value is replaced, so that comments and formatting in the pack.toml
+/// are kept byte for byte.
+pub fn set_pack_version(dir_path: &Path, version: &str) -> Result<(), anyhow::Error> {
+    let pack_file_path = dir_path.join("pack.toml");
+
+    let contents = match fs::read_to_string(&pack_file_path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to read pack.toml file at `{}`: {}",
+                pack_file_path.display(),
+                err
+            ))
+        }
+    };
+
+    let pack_version = match toml::from_str::<PackVersion>(&contents) {
+        Ok(pack_version) => pack_version,
         Err(err) => {
-            clean_up(dir_path).expect("Failed to clean up temp directory");
-            Err(anyhow!("Failed to write new pack.toml data: {}", err))
+            return Err(anyhow!(
+                "Failed to find the version in `{}`: {}",
+                pack_file_path.display(),
+                err
+            ))
         }
+    };
+
+    // Only the bytes of the old value are replaced, everything else (line endings included)
+    // stays as it is
+    let mut new_contents = contents;
+    new_contents.replace_range(
+        pack_version.version.span(),
+        &toml_edit::Value::from(version).to_string(),
+    );
+
+    match fs::write(&pack_file_path, new_contents) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!("Failed to write new pack.toml data: {}", err)),
     }
 }
 
-pub fn get_output_file(tmp_dir_info: &TempInfo) -> Result<OutputFileInfo, anyhow::Error> {
-    // This should work, as there shouldn't be any more than one .mrpack file at a given time
-    let mut glob_pattern = match glob(
-        match Path::new(&tmp_dir_info.dir_path).join("*.mrpack").to_str() {
+/// Finds the exported file. With a `file_name` it's the file packwiz was told to export to,
+/// otherwise it has to be the only file with the extension, unless `latest` picks the newest.
+pub fn get_output_file(
+    tmp_dir_info: &TempInfo,
+    extension: &str,
+    file_name: Option<&str>,
+    latest: bool,
+) -> Result<OutputFileInfo, anyhow::Error> {
+    if let Some(file_name) = file_name {
+        let file_path = tmp_dir_info.dir_path.join(file_name);
+
+        return if file_path.is_file() {
+            Ok(OutputFileInfo {
+                file_name: file_name.to_string(),
+                file_path,
+            })
+        } else {
+            Err(anyhow!("Failed to find exported file `{}`", file_name))
+        };
+    }
+
+    let glob_pattern = match glob(
+        match Path::new(&tmp_dir_info.dir_path)
+            .join(format!("*.{}", extension))
+            .to_str()
+        {
             Some(path) => path,
             None => return Err(anyhow!("Failed to parse modpack glob to string.")),
         },
@@ -47,27 +291,829 @@ pub fn get_output_file(tmp_dir_info: &TempInfo) -> Result<OutputFileInfo, anyhow
         Err(err) => return Err(anyhow!("Failed to get paths with glob pattern: {}", err)),
     };
 
-    let mut mrpack_path_res = None;
-    if let Some(entry) = glob_pattern.next() {
-        mrpack_path_res = Some(entry);
-    }
-    let file_path = match mrpack_path_res {
-        Some(path) => match path {
-            Ok(res) => res,
+    let mut file_paths = vec![];
+    for path in glob_pattern {
+        match path {
+            Ok(path) => file_paths.push(path),
             Err(err) => return Err(anyhow!("Failed to parse modpack file path: {}", err)),
-        },
-        None => return Err(anyhow!("Failed to get modpack file path")),
-    };
-    let file_name = match file_path.file_name() {
-        Some(os_name) => match os_name.to_str() {
-            Some(name) => name.to_string(),
-            None => return Err(anyhow!("Failed to parse file name from OsString to &str")),
-        },
-        None => return Err(anyhow!("Failed to get mrpack file name")),
+        }
+    }
+
+    let file_path = match file_paths.len() {
+        0 => return Err(anyhow!("Failed to get .{} file path", extension)),
+        1 => file_paths.remove(0),
+        _ if latest => {
+            let file_path = newest_file(file_paths)?;
+            warn!(
+                "Found several .{} files, using the newest one, `{}`",
+                extension,
+                file_name_from_path(&file_path)?
+            );
+            file_path
+        }
+        _ => {
+            return Err(anyhow!(
+                "Found several .{} files: {}\n\
+                 Set `output_filename` or pass `--output-file` to choose one, or `--latest` to use \
+                 the newest",
+                extension,
+                file_paths
+                    .iter()
+                    .map(file_name_from_path)
+                    .collect::<Result<Vec<String>, anyhow::Error>>()?
+                    .join(", ")
+            ))
+        }
     };
 
     Ok(OutputFileInfo {
-        file_name,
+        file_name: file_name_from_path(&file_path)?,
         file_path,
     })
 }
+
+fn newest_file(file_paths: Vec<PathBuf>) -> Result<PathBuf, anyhow::Error> {
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+
+    for file_path in file_paths {
+        let modified = match fs::metadata(&file_path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to get modification time of `{}`: {}",
+                    file_path.display(),
+                    err
+                ))
+            }
+        };
+
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, file_path));
+        }
+    }
+
+    match newest {
+        Some((_, file_path)) => Ok(file_path),
+        None => Err(anyhow!("No files to pick the newest from")),
+    }
+}
+
+/// Jars with these classifiers are build by-products and never the mod itself.
+const IGNORED_JAR_SUFFIXES: [&str; 2] = ["-dev.jar", "-dev-shadow.jar"];
+
+pub fn get_mod_artifact(tmp_dir_info: &TempInfo) -> Result<ModArtifact, anyhow::Error> {
+    find_mod_artifact(
+        &tmp_dir_info
+            .dir_path
+            .join("build")
+            .join("libs")
+            .join("*.jar"),
+        tmp_dir_info.in_place,
+    )
+}
+
+/// The jar matching `pattern`, and its sources and javadoc jars if there are any. With
+/// `in_place`, the newest of several jars is taken.
+pub fn find_mod_artifact(pattern: &Path, in_place: bool) -> Result<ModArtifact, anyhow::Error> {
+    let jars = match glob(match pattern.to_str() {
+        Some(path) => path,
+        None => return Err(anyhow!("Failed to parse glob to string")),
+    }) {
+        Ok(paths) => paths,
+        Err(err) => return Err(anyhow!("Failed to find files with mod glob: {}", err)),
+    };
+
+    let mut possible_jars: Vec<PathBuf> = vec![];
+    let mut possible_sources_jars: Vec<PathBuf> = vec![];
+    let mut possible_javadoc_jars: Vec<PathBuf> = vec![];
+
+    for jar in jars {
+        let jar_path = match jar {
+            Ok(path) => path,
+            Err(err) => return Err(anyhow!("Failed to parse jar glob result as path: {}", err)),
+        };
+
+        let file_name = file_name_from_path(&jar_path)?;
+
+        if file_name.ends_with("-sources.jar") {
+            possible_sources_jars.push(jar_path)
+        } else if file_name.ends_with("-javadoc.jar") {
+            possible_javadoc_jars.push(jar_path)
+        } else if IGNORED_JAR_SUFFIXES
+            .iter()
+            .any(|suffix| file_name.ends_with(suffix))
+        {
+            continue;
+        } else {
+            possible_jars.push(jar_path)
+        }
+    }
+
+    // Gradle keeps the jars of earlier versions around in the project directory, and doesn't
+    // rebuild an up to date jar, so the newest one is the current build
+    if in_place && possible_jars.len() > 1 {
+        possible_jars = vec![newest_file(possible_jars)?];
+    }
+
+    if possible_jars.len() != 1 {
+        return Err(anyhow!(
+            "Found an invalid amount of mod jars matching `{}`: {}",
+            pattern.display(),
+            possible_jars.len()
+        ));
+    }
+
+    Ok(ModArtifact {
+        jar_path: possible_jars.remove(0),
+        sources_jar_path: classifier_jar(possible_sources_jars, "sources", in_place)?,
+        javadoc_jar_path: classifier_jar(possible_javadoc_jars, "javadoc", in_place)?,
+    })
+}
+
+fn classifier_jar(
+    mut jars: Vec<PathBuf>,
+    classifier: &str,
+    in_place: bool,
+) -> Result<Option<PathBuf>, anyhow::Error> {
+    if in_place && jars.len() > 1 {
+        jars = vec![newest_file(jars)?];
+    }
+
+    match jars.len() {
+        0 => Ok(None),
+        1 => Ok(Some(jars.remove(0))),
+        _ => Err(anyhow!(
+            "Found an invalid amount of {} jars: {}",
+            classifier,
+            jars.len()
+        )),
+    }
+}
+
+pub fn get_additional_files(
+    project_dir: &Path,
+    patterns: &[String],
+) -> Result<Vec<OutputFileInfo>, anyhow::Error> {
+    let mut files = vec![];
+
+    for pattern in patterns {
+        let full_pattern = project_dir.join(pattern);
+
+        let paths = match glob(match full_pattern.to_str() {
+            Some(pattern) => pattern,
+            None => return Err(anyhow!("Failed to parse glob `{}` to string", pattern)),
+        }) {
+            Ok(paths) => paths,
+            Err(err) => {
+                return Err(anyhow!(
+                    "Invalid additional files glob `{}`: {}",
+                    pattern,
+                    err
+                ))
+            }
+        };
+
+        let mut matched = false;
+
+        for path in paths {
+            let file_path = match path {
+                Ok(path) => path,
+                Err(err) => return Err(anyhow!("Failed to read additional file path: {}", err)),
+            };
+
+            if !file_path.is_file() {
+                continue;
+            }
+
+            matched = true;
+            files.push(OutputFileInfo {
+                file_name: file_name_from_path(&file_path)?,
+                file_path,
+            });
+        }
+
+        if !matched {
+            warn!(
+                "Additional files pattern `{}` didn't match any files",
+                pattern
+            );
+        }
+    }
+
+    Ok(files)
+}
+
+/// Makes sure packwiz wrote a complete .mrpack for this pack, before anything is uploaded.
+pub fn verify_mrpack(
+    output_file_info: &OutputFileInfo,
+    tmp_dir_info: &TempInfo,
+    pack_file: &PackFile,
+) -> Result<MrpackIndex, anyhow::Error> {
+    let file = match File::open(&output_file_info.file_path) {
+        Ok(file) => file,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to open `{}`: {}",
+                output_file_info.file_name,
+                err
+            ))
+        }
+    };
+
+    if file.metadata().is_ok_and(|metadata| metadata.len() == 0) {
+        return Err(anyhow!("`{}` is empty", output_file_info.file_name));
+    }
+
+    let mut archive = match zip::ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(err) => {
+            return Err(anyhow!(
+                "`{}` isn't a valid .mrpack: {}",
+                output_file_info.file_name,
+                err
+            ))
+        }
+    };
+
+    let index = index_from_archive(&mut archive)?;
+
+    let expected_dependencies = [
+        ("minecraft", Some(&pack_file.versions.minecraft)),
+        ("fabric-loader", pack_file.versions.fabric.as_ref()),
+        ("quilt-loader", pack_file.versions.quilt.as_ref()),
+        ("forge", pack_file.versions.forge.as_ref()),
+        ("neoforge", pack_file.versions.neoforge.as_ref()),
+    ];
+
+    for (dependency, expected) in expected_dependencies {
+        let exported = index.dependencies.get(dependency);
+
+        if exported != expected {
+            return Err(anyhow!(
+                "`{}` in the exported .mrpack is {}, but pack.toml has {}",
+                dependency,
+                exported.map_or("missing".to_string(), |version| format!("`{}`", version)),
+                expected.map_or("none".to_string(), |version| format!("`{}`", version))
+            ));
+        }
+    }
+
+    let packwiz_index = read_packwiz_index(&tmp_dir_info.dir_path, pack_file)?;
+    let archive_files = archive.file_names().collect::<HashSet<&str>>();
+
+    let missing_overrides = packwiz_index
+        .files
+        .iter()
+        .filter(|file| !file.is_metafile())
+        .map(|file| file.override_path())
+        .filter(|path| !archive_files.contains(path.as_str()))
+        .collect::<Vec<String>>();
+
+    if !missing_overrides.is_empty() {
+        return Err(anyhow!(
+            "The exported .mrpack is missing overrides: {}",
+            missing_overrides.join(", ")
+        ));
+    }
+
+    Ok(index)
+}
+
+/// `pack_dir` is the directory of `pack.toml`, the index path is relative to it.
+pub fn read_packwiz_index(
+    pack_dir: &Path,
+    pack_file: &PackFile,
+) -> Result<PackwizIndex, anyhow::Error> {
+    let index_path = pack_dir.join(&pack_file.index.file);
+
+    let contents = match fs::read_to_string(&index_path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to read `{}`: {}",
+                pack_file.index.file,
+                err
+            ))
+        }
+    };
+
+    match toml::from_str(&contents) {
+        Ok(index) => Ok(index),
+        Err(err) => Err(anyhow!(
+            "Failed to parse `{}`: {}",
+            pack_file.index.file,
+            err
+        )),
+    }
+}
+
+/// Only `modrinth.index.json` is read, so a .mrpack on disk can be passed as a `File`.
+pub fn read_mrpack_index<R: Read + Seek>(reader: R) -> Result<MrpackIndex, anyhow::Error> {
+    let mut archive = match zip::ZipArchive::new(reader) {
+        Ok(archive) => archive,
+        Err(err) => return Err(anyhow!("Failed to open .mrpack file: {}", err)),
+    };
+
+    index_from_archive(&mut archive)
+}
+
+/// The index and the overrides listing of a .mrpack on disk, for `peony diff`.
+pub fn read_mrpack(path: &Path) -> Result<MrpackContents, anyhow::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(anyhow!("Failed to open `{}`: {}", path.display(), err)),
+    };
+
+    let mut archive = match zip::ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(err) => {
+            return Err(anyhow!(
+                "`{}` isn't a valid .mrpack: {}",
+                path.display(),
+                err
+            ))
+        }
+    };
+
+    let index = index_from_archive(&mut archive)?;
+
+    let mut overrides = BTreeMap::new();
+    for i in 0..archive.len() {
+        let file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(err) => return Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+        };
+
+        let is_override = ["overrides/", "client-overrides/", "server-overrides/"]
+            .iter()
+            .any(|dir| file.name().starts_with(dir));
+
+        if is_override && !file.is_dir() {
+            overrides.insert(file.name().to_string(), file.crc32());
+        }
+    }
+
+    Ok(MrpackContents { index, overrides })
+}
+
+fn index_from_archive<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<MrpackIndex, anyhow::Error> {
+    let mut index_string = String::new();
+
+    match archive.by_name("modrinth.index.json") {
+        Ok(mut file) => file.read_to_string(&mut index_string)?,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to get `modrinth.index.json` from .mrpack file: {}",
+                err
+            ))
+        }
+    };
+
+    match serde_json::from_str(&index_string) {
+        Ok(index) => Ok(index),
+        Err(err) => Err(anyhow!("Failed to parse `modrinth.index.json`: {}", err)),
+    }
+}
+
+/// Only counts files downloaded into `mods/`, packs made of overrides alone have none.
+pub fn count_mods(index: &MrpackIndex) -> usize {
+    index
+        .files
+        .iter()
+        .filter(|file| file.path.starts_with("mods/"))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_dir;
+    use std::io::Write;
+    use std::time::Duration;
+
+    fn write_files(dir: &Path, file_names: &[&str]) {
+        fs::create_dir_all(dir).unwrap();
+        for file_name in file_names {
+            fs::write(dir.join(file_name), file_name).unwrap();
+        }
+    }
+
+    fn set_modified(path: &Path, seconds_ago: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+            .unwrap();
+    }
+
+    #[test]
+    fn mod_artifact_skips_other_classifiers() {
+        let dir = test_dir();
+        let libs_dir = dir.dir_path.join("build").join("libs");
+        write_files(
+            &libs_dir,
+            &[
+                "test-mod-1.0.0.jar",
+                "test-mod-1.0.0-sources.jar",
+                "test-mod-1.0.0-javadoc.jar",
+                "test-mod-1.0.0-dev.jar",
+                "test-mod-1.0.0-dev-shadow.jar",
+                "test-mod-1.0.0.pom",
+            ],
+        );
+
+        let artifact = get_mod_artifact(&dir).unwrap();
+
+        assert_eq!(artifact.jar_path, libs_dir.join("test-mod-1.0.0.jar"));
+        assert_eq!(
+            artifact.sources_jar_path,
+            Some(libs_dir.join("test-mod-1.0.0-sources.jar"))
+        );
+        assert_eq!(
+            artifact.javadoc_jar_path,
+            Some(libs_dir.join("test-mod-1.0.0-javadoc.jar"))
+        );
+    }
+
+    #[test]
+    fn mod_artifact_without_sources_or_javadoc() {
+        let dir = test_dir();
+        let libs_dir = dir.dir_path.join("build").join("libs");
+        write_files(&libs_dir, &["test-mod-1.0.0.jar", "test-mod-1.0.0-dev.jar"]);
+
+        let artifact = get_mod_artifact(&dir).unwrap();
+
+        assert_eq!(artifact.jar_path, libs_dir.join("test-mod-1.0.0.jar"));
+        assert_eq!(artifact.sources_jar_path, None);
+        assert_eq!(artifact.javadoc_jar_path, None);
+    }
+
+    #[test]
+    fn several_mod_jars_are_ambiguous() {
+        let dir = test_dir();
+        write_files(
+            &dir.dir_path.join("build").join("libs"),
+            &["test-mod-1.0.0.jar", "test-mod-1.1.0.jar"],
+        );
+
+        let err = get_mod_artifact(&dir).unwrap_err().to_string();
+
+        assert!(err.contains("invalid amount of mod jars"), "{}", err);
+        assert!(err.ends_with(": 2"), "{}", err);
+    }
+
+    #[test]
+    fn no_mod_jar() {
+        let dir = test_dir();
+        write_files(
+            &dir.dir_path.join("build").join("libs"),
+            &["test-mod-1.0.0-sources.jar"],
+        );
+
+        assert!(get_mod_artifact(&dir).is_err());
+    }
+
+    #[test]
+    fn in_place_mod_artifact_is_the_newest_jar() {
+        let dir = test_dir();
+        let libs_dir = dir.dir_path.join("build").join("libs");
+        write_files(
+            &libs_dir,
+            &[
+                "test-mod-1.0.0.jar",
+                "test-mod-1.0.0-sources.jar",
+                "test-mod-1.1.0.jar",
+                "test-mod-1.1.0-sources.jar",
+            ],
+        );
+        set_modified(&libs_dir.join("test-mod-1.0.0.jar"), 3600);
+        set_modified(&libs_dir.join("test-mod-1.0.0-sources.jar"), 3600);
+
+        let artifact = find_mod_artifact(&libs_dir.join("*.jar"), true).unwrap();
+
+        assert_eq!(artifact.jar_path, libs_dir.join("test-mod-1.1.0.jar"));
+        assert_eq!(
+            artifact.sources_jar_path,
+            Some(libs_dir.join("test-mod-1.1.0-sources.jar"))
+        );
+    }
+
+    const PACK_INDEX: &str = r#"
+hash-format = "sha256"
+
+[[files]]
+file = "mods/sodium.pw.toml"
+hash = "ff614f50beb3bc29a699dbcc0a61f65bd6c6154cd374f2763ee57cf578045a8f"
+metafile = true
+
+[[files]]
+file = "config/sodium-options.json"
+hash = "0b8e3c9d1f2a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4"
+"#;
+
+    /// The pack of `test_projects/modpack`, with an override in its index.
+    fn verify_test_pack(dir: &Path) -> PackFile {
+        let pack_toml = include_str!("../test_projects/modpack/pack.toml");
+        fs::write(dir.join("pack.toml"), pack_toml).unwrap();
+        fs::write(dir.join("index.toml"), PACK_INDEX).unwrap();
+
+        get_pack_file(dir).unwrap()
+    }
+
+    fn write_mrpack(
+        path: &Path,
+        dependencies: serde_json::Value,
+        files: &[&str],
+    ) -> OutputFileInfo {
+        let index = serde_json::json!({
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "0.1.0",
+            "name": "Test Pack",
+            "files": [],
+            "dependencies": dependencies,
+        });
+
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("modrinth.index.json", options).unwrap();
+        zip.write_all(index.to_string().as_bytes()).unwrap();
+        for file in files {
+            zip.start_file(*file, options).unwrap();
+            zip.write_all(b"{}").unwrap();
+        }
+        zip.finish().unwrap();
+
+        OutputFileInfo {
+            file_name: "Test Pack-0.1.0.mrpack".to_string(),
+            file_path: path.to_path_buf(),
+        }
+    }
+
+    fn verify(
+        dependencies: serde_json::Value,
+        files: &[&str],
+    ) -> Result<MrpackIndex, anyhow::Error> {
+        let dir = test_dir();
+        let pack_file = verify_test_pack(&dir.dir_path);
+        let output_file_info = write_mrpack(
+            &dir.dir_path.join("Test Pack-0.1.0.mrpack"),
+            dependencies,
+            files,
+        );
+
+        verify_mrpack(
+            &output_file_info,
+            &TempInfo::in_place(dir.dir_path.clone()),
+            &pack_file,
+        )
+    }
+
+    #[test]
+    fn verifies_complete_mrpack() {
+        let index = verify(
+            serde_json::json!({ "minecraft": "1.20.2", "quilt-loader": "0.21.0" }),
+            &["overrides/config/sodium-options.json"],
+        )
+        .unwrap();
+
+        assert_eq!(index.version_id, "0.1.0");
+    }
+
+    #[test]
+    fn rejects_mismatched_loader_version() {
+        let err = verify(
+            serde_json::json!({ "minecraft": "1.20.2", "quilt-loader": "0.20.2" }),
+            &["overrides/config/sodium-options.json"],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "`quilt-loader` in the exported .mrpack is `0.20.2`, but pack.toml has `0.21.0`"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_unexpected_dependencies() {
+        let err = verify(
+            serde_json::json!({ "quilt-loader": "0.21.0" }),
+            &["overrides/config/sodium-options.json"],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`minecraft` in the exported .mrpack is missing, but pack.toml has `1.20.2`"
+        );
+
+        let err = verify(
+            serde_json::json!({
+                "minecraft": "1.20.2",
+                "fabric-loader": "0.15.0",
+                "quilt-loader": "0.21.0",
+            }),
+            &["overrides/config/sodium-options.json"],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`fabric-loader` in the exported .mrpack is `0.15.0`, but pack.toml has none"
+        );
+    }
+
+    #[test]
+    fn rejects_mrpack_missing_overrides() {
+        let err = verify(
+            serde_json::json!({ "minecraft": "1.20.2", "quilt-loader": "0.21.0" }),
+            &[],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "The exported .mrpack is missing overrides: overrides/config/sodium-options.json"
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_invalid_mrpacks() {
+        let dir = test_dir();
+        let pack_file = verify_test_pack(&dir.dir_path);
+        let tmp_dir_info = TempInfo::in_place(dir.dir_path.clone());
+        let output_file_info = OutputFileInfo {
+            file_name: "Test Pack-0.1.0.mrpack".to_string(),
+            file_path: dir.dir_path.join("Test Pack-0.1.0.mrpack"),
+        };
+
+        fs::write(&output_file_info.file_path, "").unwrap();
+        let err = verify_mrpack(&output_file_info, &tmp_dir_info, &pack_file).unwrap_err();
+        assert_eq!(err.to_string(), "`Test Pack-0.1.0.mrpack` is empty");
+
+        fs::write(&output_file_info.file_path, "not a zip").unwrap();
+        let err = verify_mrpack(&output_file_info, &tmp_dir_info, &pack_file).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("`Test Pack-0.1.0.mrpack` isn't a valid .mrpack"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn single_mrpack_is_the_output_file() {
+        let dir = test_dir();
+        write_files(&dir.dir_path, &["Test Pack-1.0.0.mrpack", "pack.toml"]);
+
+        let output_file = get_output_file(&dir, "mrpack", None, false).unwrap();
+
+        assert_eq!(output_file.file_name, "Test Pack-1.0.0.mrpack");
+        assert_eq!(
+            output_file.file_path,
+            dir.dir_path.join("Test Pack-1.0.0.mrpack")
+        );
+    }
+
+    #[test]
+    fn several_mrpacks_are_ambiguous() {
+        let dir = test_dir();
+        write_files(
+            &dir.dir_path,
+            &["Test Pack-1.0.0.mrpack", "Test Pack-1.1.0.mrpack"],
+        );
+
+        let err = get_output_file(&dir, "mrpack", None, false)
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            err.starts_with(
+                "Found several .mrpack files: Test Pack-1.0.0.mrpack, Test Pack-1.1.0.mrpack\n"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn latest_picks_the_newest_mrpack() {
+        let dir = test_dir();
+        write_files(
+            &dir.dir_path,
+            &["Test Pack-1.0.0.mrpack", "Test Pack-1.1.0.mrpack"],
+        );
+        set_modified(&dir.dir_path.join("Test Pack-1.0.0.mrpack"), 0);
+        set_modified(&dir.dir_path.join("Test Pack-1.1.0.mrpack"), 60);
+
+        let output_file = get_output_file(&dir, "mrpack", None, true).unwrap();
+
+        assert_eq!(output_file.file_name, "Test Pack-1.0.0.mrpack");
+    }
+
+    #[test]
+    fn file_name_picks_one_of_several_mrpacks() {
+        let dir = test_dir();
+        write_files(
+            &dir.dir_path,
+            &["Test Pack-1.0.0.mrpack", "Test Pack-1.1.0.mrpack"],
+        );
+
+        let output_file =
+            get_output_file(&dir, "mrpack", Some("Test Pack-1.1.0.mrpack"), false).unwrap();
+        assert_eq!(output_file.file_name, "Test Pack-1.1.0.mrpack");
+
+        let err =
+            get_output_file(&dir, "mrpack", Some("Test Pack-1.2.0.mrpack"), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to find exported file `Test Pack-1.2.0.mrpack`"
+        );
+    }
+
+    #[test]
+    fn no_mrpack() {
+        let dir = test_dir();
+        write_files(&dir.dir_path, &["pack.toml", "Test Pack-1.0.0.zip"]);
+
+        let err = get_output_file(&dir, "mrpack", None, true).unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to get .mrpack file path");
+    }
+
+    const COMMENTED_PACK_TOML: &str = include_str!("../test_projects/commented-pack/pack.toml");
+
+    fn changed_lines<'a>(before: &'a str, after: &'a str) -> Vec<(&'a str, &'a str)> {
+        assert_eq!(before.lines().count(), after.lines().count());
+        before
+            .lines()
+            .zip(after.lines())
+            .filter(|(before, after)| before != after)
+            .collect()
+    }
+
+    #[test]
+    fn set_pack_version_only_changes_the_version_line() {
+        let dir = test_dir();
+        fs::write(dir.dir_path.join("pack.toml"), COMMENTED_PACK_TOML).unwrap();
+
+        set_pack_version(&dir.dir_path, "0.2.0-beta.1").unwrap();
+
+        let contents = fs::read_to_string(dir.dir_path.join("pack.toml")).unwrap();
+        assert_eq!(
+            changed_lines(COMMENTED_PACK_TOML, &contents),
+            [(
+                "version = '0.1.0'  # bumped on release",
+                "version = \"0.2.0-beta.1\"  # bumped on release"
+            )]
+        );
+        assert_eq!(
+            get_pack_file(&dir.dir_path).unwrap().version,
+            "0.2.0-beta.1"
+        );
+    }
+
+    #[test]
+    fn set_pack_version_keeps_line_endings() {
+        let dir = test_dir();
+        let pack_toml = COMMENTED_PACK_TOML.replace('\n', "\r\n");
+        fs::write(dir.dir_path.join("pack.toml"), &pack_toml).unwrap();
+
+        set_pack_version(&dir.dir_path, "0.2.0").unwrap();
+
+        let contents = fs::read_to_string(dir.dir_path.join("pack.toml")).unwrap();
+        assert_eq!(
+            contents,
+            pack_toml.replace("version = '0.1.0'", "version = \"0.2.0\"")
+        );
+    }
+
+    #[test]
+    fn set_pack_version_escapes_the_version() {
+        let dir = test_dir();
+        fs::write(dir.dir_path.join("pack.toml"), COMMENTED_PACK_TOML).unwrap();
+
+        set_pack_version(&dir.dir_path, "0.2.0 \"hotfix\"").unwrap();
+
+        assert_eq!(
+            get_pack_file(&dir.dir_path).unwrap().version,
+            "0.2.0 \"hotfix\""
+        );
+    }
+
+    #[test]
+    fn set_pack_version_without_version_fails() {
+        let dir = test_dir();
+        fs::write(dir.dir_path.join("pack.toml"), "name = \"Skylands\"\n").unwrap();
+
+        let err = set_pack_version(&dir.dir_path, "0.2.0").unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("Failed to find the version in `"),
+            "{}",
+            err
+        );
+    }
+}
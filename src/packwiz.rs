@@ -0,0 +1,480 @@
+//! Finds the packwiz executable to export with. A version pinned in `[packwiz]` is downloaded
+//! from the packwiz releases into a cache directory once, so that every machine exports with
+//! the same packwiz. Its SHA-256 is kept next to the cached binary and checked before every
+//! use.
+
+use anyhow::anyhow;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::models::{github::ReleaseResponse, NetworkConfig, PackwizConfig};
+use crate::util::{encode_path_segment, error_body, send_with_retry};
+
+const BINARY_NAME: &str = if cfg!(windows) {
+    "packwiz.exe"
+} else {
+    "packwiz"
+};
+
+/// Returns the packwiz executable to run, the one on `PATH` unless a version is pinned.
+pub async fn resolve_packwiz(
+    packwiz_config: Option<&PackwizConfig>,
+    network: &NetworkConfig,
+) -> Result<PathBuf, anyhow::Error> {
+    let (packwiz_config, version) =
+        match packwiz_config.and_then(|config| config.pinned_version().map(|v| (config, v))) {
+            Some(pinned) => pinned,
+            None => return find_system_packwiz(),
+        };
+
+    let packwiz_path = cached_packwiz_path(version)?;
+    if packwiz_path.is_file() {
+        if cached_binary_is_intact(&packwiz_path) {
+            return Ok(packwiz_path);
+        }
+
+        warn!(
+            "Cached packwiz `{}` doesn't match its SHA-256, getting it again",
+            packwiz_path.display()
+        );
+    }
+
+    if is_commit(version) {
+        build_packwiz(packwiz_config, version, &packwiz_path)?;
+    } else {
+        download_packwiz(packwiz_config, version, &packwiz_path, network).await?;
+    }
+
+    Ok(packwiz_path)
+}
+
+pub fn find_system_packwiz() -> Result<PathBuf, anyhow::Error> {
+    match which::which("packwiz") {
+        Ok(path) => Ok(path),
+        Err(err) => Err(anyhow!("Failed to find packwiz executable: {}", err)),
+    }
+}
+
+/// `<cache dir>/peony/packwiz/<version>/packwiz`, where the cache dir is `$XDG_CACHE_HOME`,
+/// `~/.cache` or `%LOCALAPPDATA%` on Windows.
+pub fn cached_packwiz_path(version: &str) -> Result<PathBuf, anyhow::Error> {
+    let cache_dir = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None if cfg!(windows) => env::var_os("LOCALAPPDATA").map(PathBuf::from),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")),
+    };
+
+    match cache_dir {
+        Some(dir) => Ok(dir
+            .join("peony")
+            .join("packwiz")
+            .join(version)
+            .join(BINARY_NAME)),
+        None => Err(anyhow!(
+            "Failed to find a cache directory for packwiz, set `XDG_CACHE_HOME`"
+        )),
+    }
+}
+
+fn is_commit(version: &str) -> bool {
+    (7..=40).contains(&version.len()) && version.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn checksum_path(packwiz_path: &Path) -> PathBuf {
+    packwiz_path.with_extension("sha256")
+}
+
+/// Whether the cached binary still has the SHA-256 recorded when it was downloaded or built.
+fn cached_binary_is_intact(packwiz_path: &Path) -> bool {
+    let expected_hash = match fs::read_to_string(checksum_path(packwiz_path)) {
+        Ok(hash) => hash,
+        Err(_) => return false,
+    };
+
+    match fs::read(packwiz_path) {
+        Ok(binary) => format!("{:x}", Sha256::digest(&binary)) == expected_hash.trim(),
+        Err(_) => false,
+    }
+}
+
+/// Commits have no prebuilt binaries, so they're built with `go install`, which checks the
+/// sources against the Go checksum database. The built binary is checked against `sha256`
+/// when it's set.
+fn build_packwiz(
+    packwiz_config: &PackwizConfig,
+    commit: &str,
+    packwiz_path: &Path,
+) -> Result<(), anyhow::Error> {
+    let go_path = match which::which("go") {
+        Ok(path) => path,
+        Err(_) => {
+            return Err(anyhow!(
+                "Building packwiz {} needs Go, install it or pin a release tag instead",
+                commit
+            ))
+        }
+    };
+
+    info!("Building packwiz {} with Go...", commit);
+
+    let bin_dir = match packwiz_path.parent() {
+        Some(dir) => dir,
+        None => return Err(anyhow!("Failed to get packwiz cache directory")),
+    };
+
+    // Without local paths in the binary, a build with the same Go has the same SHA-256
+    let status = Command::new(go_path)
+        .arg("install")
+        .arg("-trimpath")
+        .arg(format!("github.com/{}@{}", packwiz_config.repo(), commit))
+        .env("GOBIN", bin_dir)
+        .status();
+
+    match status {
+        Ok(status) if status.success() && packwiz_path.is_file() => {}
+        Ok(status) if status.success() => {
+            return Err(anyhow!(
+                "`go install` didn't create `{}`",
+                packwiz_path.display()
+            ))
+        }
+        Ok(status) => return Err(anyhow!("Building packwiz failed with {}", status)),
+        Err(err) => return Err(anyhow!("Failed to run `go install`: {}", err)),
+    }
+
+    let binary = match fs::read(packwiz_path) {
+        Ok(binary) => binary,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to read `{}`: {}",
+                packwiz_path.display(),
+                err
+            ))
+        }
+    };
+    let actual_hash = format!("{:x}", Sha256::digest(&binary));
+
+    if let Some(expected_hash) = &packwiz_config.sha256 {
+        if actual_hash != expected_hash.to_lowercase() {
+            // Not cached, so that the next run doesn't pick it up
+            let _ = fs::remove_file(packwiz_path);
+            return Err(anyhow!(
+                "SHA-256 of packwiz built from {} doesn't match, expected {} but got {}",
+                commit,
+                expected_hash.to_lowercase(),
+                actual_hash
+            ));
+        }
+    }
+
+    write_checksum(packwiz_path, &actual_hash)
+}
+
+async fn download_packwiz(
+    packwiz_config: &PackwizConfig,
+    version: &str,
+    packwiz_path: &Path,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    info!("Downloading packwiz {}...", version);
+
+    let release = get_packwiz_release(packwiz_config, version, network).await?;
+
+    let no_binary = || {
+        anyhow!(
+            "packwiz {} has no prebuilt binary for {}/{}, install packwiz yourself and set \
+             `system = true` in `[packwiz]`",
+            version,
+            env::consts::OS,
+            env::consts::ARCH
+        )
+    };
+
+    let (os_names, arch_names) = match platform_names() {
+        Some(names) => names,
+        None => return Err(no_binary()),
+    };
+
+    let asset = match release.assets.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        !is_checksums_file(&name)
+            && os_names.iter().any(|os| name.contains(os))
+            && arch_names.iter().any(|arch| name.contains(arch))
+    }) {
+        Some(asset) => asset,
+        None => return Err(no_binary()),
+    };
+
+    let contents = download(&asset.browser_download_url, network).await?;
+
+    let expected_hash = match &packwiz_config.sha256 {
+        Some(hash) => hash.to_lowercase(),
+        None => release_checksum(&release, &asset.name, network).await?,
+    };
+    let actual_hash = format!("{:x}", Sha256::digest(&contents));
+
+    if actual_hash != expected_hash {
+        return Err(anyhow!(
+            "SHA-256 of `{}` doesn't match, expected {} but got {}",
+            asset.name,
+            expected_hash,
+            actual_hash
+        ));
+    }
+
+    let binary = if asset.name.ends_with(".zip") {
+        extract_binary(contents)?
+    } else {
+        contents
+    };
+
+    write_executable(packwiz_path, &binary)?;
+
+    info!("Downloaded packwiz to `{}`", packwiz_path.display());
+
+    Ok(())
+}
+
+async fn get_packwiz_release(
+    packwiz_config: &PackwizConfig,
+    version: &str,
+    network: &NetworkConfig,
+) -> Result<ReleaseResponse, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client
+            .get(format!(
+                "https://api.github.com/repos/{}/releases/tags/{}",
+                packwiz_config.repo(),
+                encode_path_segment(version)
+            ))
+            .header("Accept", "application/vnd.github+json"))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to get packwiz release: {}", err)),
+    };
+
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(anyhow!(
+            "Failed to find packwiz release `{}` in `{}`",
+            version,
+            packwiz_config.repo()
+        ));
+    }
+
+    if !res.status().is_success() {
+        return Err(anyhow!(
+            "Failed to get packwiz release: {}",
+            error_body(res).await
+        ));
+    }
+
+    match res.json::<ReleaseResponse>().await {
+        Ok(release) => Ok(release),
+        Err(err) => Err(anyhow!("Failed to parse packwiz release: {}", err)),
+    }
+}
+
+async fn download(url: &str, network: &NetworkConfig) -> Result<Vec<u8>, anyhow::Error> {
+    let res = match send_with_retry(network, |client| {
+        Ok(client.get(url).timeout(network.transfer_timeout()))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => return Err(anyhow!("Failed to download `{}`: {}", url, err)),
+    };
+
+    if !res.status().is_success() {
+        return Err(anyhow!("Failed to download `{}`: `{}`", url, res.status()));
+    }
+
+    match res.bytes().await {
+        Ok(bytes) => Ok(bytes.to_vec()),
+        Err(err) => Err(anyhow!("Failed to download `{}`: {}", url, err)),
+    }
+}
+
+/// Names that release assets use for the current OS and architecture.
+fn platform_names() -> Option<(&'static [&'static str], &'static [&'static str])> {
+    let os_names: &[&str] = match env::consts::OS {
+        "linux" => &["linux"],
+        "macos" => &["darwin", "macos"],
+        "windows" => &["windows"],
+        "freebsd" => &["freebsd"],
+        _ => return None,
+    };
+
+    let arch_names: &[&str] = match env::consts::ARCH {
+        "x86_64" => &["amd64", "x86_64", "x64"],
+        "aarch64" => &["arm64", "aarch64"],
+        "x86" => &["386", "i686"],
+        _ => return None,
+    };
+
+    Some((os_names, arch_names))
+}
+
+fn is_checksums_file(name: &str) -> bool {
+    name.ends_with(".sha256") || name.contains("checksums") || name.contains("sha256sums")
+}
+
+/// Finds the SHA-256 of `asset_name` in the checksums file of the release, which can be in
+/// `sha256sum` or BSD format, or a `<asset>.sha256` file.
+async fn release_checksum(
+    release: &ReleaseResponse,
+    asset_name: &str,
+    network: &NetworkConfig,
+) -> Result<String, anyhow::Error> {
+    for checksums_asset in release
+        .assets
+        .iter()
+        .filter(|asset| is_checksums_file(&asset.name.to_lowercase()))
+    {
+        let contents = download(&checksums_asset.browser_download_url, network).await?;
+        let contents = String::from_utf8_lossy(&contents);
+        let single_file = checksums_asset.name == format!("{}.sha256", asset_name);
+
+        let hash = contents
+            .lines()
+            .filter(|line| single_file || line.contains(asset_name))
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == '='))
+            .find(|word| word.len() == 64 && word.chars().all(|c| c.is_ascii_hexdigit()));
+
+        if let Some(hash) = hash {
+            return Ok(hash.to_lowercase());
+        }
+    }
+
+    Err(anyhow!(
+        "Failed to find a checksum for `{}` in the packwiz release, set `sha256` in `[packwiz]` \
+         to verify it",
+        asset_name
+    ))
+}
+
+fn extract_binary(contents: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
+    let mut archive = match zip::ZipArchive::new(Cursor::new(contents)) {
+        Ok(archive) => archive,
+        Err(err) => return Err(anyhow!("Failed to open packwiz archive: {}", err)),
+    };
+
+    let binary_name = archive
+        .file_names()
+        .find(|name| {
+            Path::new(name)
+                .file_name()
+                .is_some_and(|name| name == BINARY_NAME)
+        })
+        .map(String::from);
+
+    let binary_name = match binary_name {
+        Some(name) => name,
+        None => {
+            return Err(anyhow!(
+                "Failed to find `{}` in packwiz archive",
+                BINARY_NAME
+            ))
+        }
+    };
+
+    let mut binary = vec![];
+    match archive.by_name(&binary_name) {
+        Ok(mut file) => file.read_to_end(&mut binary)?,
+        Err(err) => return Err(anyhow!("Failed to extract `{}`: {}", binary_name, err)),
+    };
+
+    Ok(binary)
+}
+
+fn write_checksum(packwiz_path: &Path, hash: &str) -> Result<(), anyhow::Error> {
+    let path = checksum_path(packwiz_path);
+
+    match fs::write(&path, hash) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!("Failed to write `{}`: {}", path.display(), err)),
+    }
+}
+
+/// Written next to its final path first, so that an interrupted download isn't cached. Its
+/// SHA-256 is recorded before it's moved there.
+fn write_executable(path: &Path, contents: &[u8]) -> Result<(), anyhow::Error> {
+    if let Some(dir) = path.parent() {
+        if let Err(err) = fs::create_dir_all(dir) {
+            return Err(anyhow!("Failed to create `{}`: {}", dir.display(), err));
+        }
+    }
+
+    let partial_path = path.with_extension("part");
+
+    if let Err(err) = fs::write(&partial_path, contents) {
+        return Err(anyhow!(
+            "Failed to write `{}`: {}",
+            partial_path.display(),
+            err
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Err(err) = fs::set_permissions(&partial_path, fs::Permissions::from_mode(0o755)) {
+            return Err(anyhow!(
+                "Failed to make `{}` executable: {}",
+                partial_path.display(),
+                err
+            ));
+        }
+    }
+
+    write_checksum(path, &format!("{:x}", Sha256::digest(contents)))?;
+
+    match fs::rename(&partial_path, path) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!("Failed to write `{}`: {}", path.display(), err)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_support::test_dir;
+
+    /// Caches `script` as packwiz `version` in `cache_dir`, so that a release pinned to it
+    /// runs the script instead of downloading packwiz.
+    #[cfg(unix)]
+    pub(crate) fn cache_fake_packwiz(cache_dir: &Path, version: &str, script: &str) {
+        env::set_var("XDG_CACHE_HOME", cache_dir);
+        write_executable(&cached_packwiz_path(version).unwrap(), script.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn cached_binary_is_checked_against_its_recorded_hash() {
+        let dir = test_dir();
+        let packwiz_path = dir.dir_path.join(BINARY_NAME);
+
+        write_executable(&packwiz_path, b"packwiz binary").unwrap();
+        assert!(cached_binary_is_intact(&packwiz_path));
+
+        fs::write(&packwiz_path, b"tampered binary").unwrap();
+        assert!(!cached_binary_is_intact(&packwiz_path));
+    }
+
+    #[test]
+    fn cached_binary_without_recorded_hash_is_fetched_again() {
+        let dir = test_dir();
+        let packwiz_path = dir.dir_path.join(BINARY_NAME);
+
+        write_executable(&packwiz_path, b"packwiz binary").unwrap();
+        fs::remove_file(checksum_path(&packwiz_path)).unwrap();
+
+        assert!(!cached_binary_is_intact(&packwiz_path));
+    }
+}
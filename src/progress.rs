@@ -0,0 +1,152 @@
+//! Progress bars of exports, copies and uploads. They're only drawn once [`enable`] is called,
+//! which the CLI does when stdout is a terminal, so that the library prints nothing by itself.
+//! Without them, uploads log how far they got every quarter instead.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::info;
+
+/// Called with the number of bytes of a file that were sent so far.
+pub type UploadProgress = Arc<dyn Fn(u64) + Send + Sync>;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Uploads run at the same time, so their bars are drawn together.
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+fn bars() -> &'static MultiProgress {
+    BARS.get_or_init(|| MultiProgress::with_draw_target(ProgressDrawTarget::stderr()))
+}
+
+/// Draws progress bars to stderr from now on.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Hides the bars while `f` runs, so that log lines don't get mixed into them.
+pub fn suspend<F: FnOnce() -> R, R>(f: F) -> R {
+    match BARS.get() {
+        Some(bars) => bars.suspend(f),
+        None => f(),
+    }
+}
+
+/// Adds a bar with the given template, or a hidden one when bars aren't enabled. Bars without
+/// a length are redrawn regularly, as their spinner would stand still otherwise.
+fn add_bar(bar: ProgressBar, template: &str) -> ProgressBar {
+    if !is_enabled() {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ");
+
+    let bar = bars().add(bar.with_style(style));
+    if bar.length().is_none() {
+        bar.enable_steady_tick(Duration::from_millis(100));
+    }
+
+    bar
+}
+
+/// Bar of the upload of a file, cleared once it's dropped.
+pub struct UploadBar {
+    bar: ProgressBar,
+    file_name: String,
+    size: u64,
+}
+
+impl UploadBar {
+    pub fn new(file_name: &str, size: u64) -> Self {
+        let bar = add_bar(
+            ProgressBar::new(size),
+            "{msg} [{bar:30}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+        );
+        bar.set_message(format!("Uploading {}", file_name));
+
+        Self {
+            bar,
+            file_name: file_name.to_string(),
+            size,
+        }
+    }
+
+    pub fn callback(&self) -> UploadProgress {
+        if is_enabled() {
+            let bar = self.bar.clone();
+            return Arc::new(move |sent| bar.set_position(sent));
+        }
+
+        let file_name = self.file_name.clone();
+        let size = self.size.max(1);
+        let logged_quarters = AtomicU64::new(0);
+
+        Arc::new(move |sent| {
+            // Retries start over from 0, so only quarters that weren't logged yet are
+            let quarters = sent * 4 / size;
+            if quarters < 4 && quarters > logged_quarters.fetch_max(quarters, Ordering::Relaxed) {
+                info!("Uploaded {}% of `{}`", quarters * 25, file_name);
+            }
+        })
+    }
+}
+
+impl Drop for UploadBar {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Bytes copied so far, for copies whose size isn't known up front.
+pub struct CopyBar {
+    bar: ProgressBar,
+}
+
+impl CopyBar {
+    pub fn new(message: &str) -> Self {
+        let bar = add_bar(
+            ProgressBar::no_length(),
+            "{spinner} {msg} {bytes} ({bytes_per_sec})",
+        );
+        bar.set_message(message.to_string());
+
+        Self { bar }
+    }
+
+    pub fn add(&self, bytes: u64) {
+        self.bar.inc(bytes);
+    }
+}
+
+impl Drop for CopyBar {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Spinner for steps without any progress to show, like a packwiz export.
+pub struct Spinner {
+    bar: ProgressBar,
+}
+
+impl Spinner {
+    pub fn new(message: &str) -> Self {
+        let bar = add_bar(ProgressBar::new_spinner(), "{spinner} {msg} ({elapsed})");
+        bar.set_message(message.to_string());
+
+        Self { bar }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
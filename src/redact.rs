@@ -0,0 +1,184 @@
+//! Tokens and webhook URLs read from the environment are registered here, so that they can be
+//! masked in anything that's logged or printed.
+
+use std::fmt;
+use std::sync::Mutex;
+
+const MASK: &str = "***";
+
+/// Registered secrets, with what they're replaced with.
+static SECRETS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// A token or webhook URL. It never shows up in `Debug` or `Display` output, use
+/// [`Secret::expose`] where the value itself is needed.
+#[derive(Clone)]
+pub struct Secret {
+    value: String,
+    masked: String,
+}
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        register_secret(&value, MASK);
+
+        Self {
+            value,
+            masked: MASK.to_string(),
+        }
+    }
+
+    /// Discord webhook URLs are shown as their ID, the part before the webhook's token, so that
+    /// it's still clear which webhook a message is about.
+    pub fn webhook_url(value: String) -> Self {
+        let masked = match webhook_parts(&value) {
+            Some((id, token)) => {
+                // The token alone can show up too, e.g. in a path in an error message
+                register_secret(token, MASK);
+                format!("<webhook {}>", id)
+            }
+            None => MASK.to_string(),
+        };
+
+        register_secret(&value, &masked);
+
+        Self { value, masked }
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", self.masked)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.masked)
+    }
+}
+
+/// Splits `https://discord.com/api/webhooks/<id>/<token>` into its ID and token.
+fn webhook_parts(url: &str) -> Option<(&str, &str)> {
+    let (_, path) = url.split_once("/webhooks/")?;
+    let (id, token) = path.split_once('/')?;
+    let token = token.split(['/', '?']).next()?;
+
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && !token.is_empty() {
+        Some((id, token))
+    } else {
+        None
+    }
+}
+
+fn register_secret(secret: &str, replacement: &str) {
+    if secret.is_empty() {
+        return;
+    }
+
+    let mut secrets = match SECRETS.lock() {
+        Ok(secrets) => secrets,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    if !secrets.iter().any(|(known, _)| known == secret) {
+        secrets.push((secret.to_string(), replacement.to_string()));
+        // Longest first, so that a webhook URL is replaced before the token it contains
+        secrets.sort_by_key(|(secret, _)| std::cmp::Reverse(secret.len()));
+    }
+}
+
+/// Replaces every registered secret in `text`.
+pub fn redact(text: &str) -> String {
+    let secrets = match SECRETS.lock() {
+        Ok(secrets) => secrets,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    let mut redacted = text.to_string();
+    for (secret, replacement) in secrets.iter() {
+        redacted = redacted.replace(secret.as_str(), replacement);
+    }
+
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::read_secret_env;
+    use anyhow::anyhow;
+
+    const WEBHOOK_URL: &str = "https://discord.com/api/webhooks/112233445566/webhook-token-R3dact";
+
+    #[test]
+    fn secrets_are_masked_when_formatted() {
+        let token = Secret::new("mrp_formatted_S3cret".to_string());
+
+        assert_eq!(format!("{}", token), "***");
+        assert_eq!(format!("{:?}", token), "Secret(***)");
+        assert_eq!(token.expose(), "mrp_formatted_S3cret");
+    }
+
+    #[test]
+    fn tokens_are_masked_in_error_chains() {
+        std::env::set_var("PEONY_TEST_REDACTED_TOKEN", "mrp_error_chain_S3cret");
+        let token = read_secret_env("PEONY_TEST_REDACTED_TOKEN").unwrap();
+
+        let err = anyhow!("Invalid token `{}`", token.expose())
+            .context("Failed to create Modrinth version");
+        let printed = redact(&format!("{:?}", err));
+
+        assert!(!printed.contains("mrp_error_chain_S3cret"), "{}", printed);
+        assert!(printed.contains("Invalid token `***`"), "{}", printed);
+    }
+
+    #[tokio::test]
+    async fn tokens_are_masked_in_request_errors() {
+        let token = Secret::new("mrp_request_S3cret".to_string());
+
+        // Nothing listens on port 1, so the request fails with its url in the error
+        let err = reqwest::get(format!(
+            "http://127.0.0.1:1/project?token={}",
+            token.expose()
+        ))
+        .await
+        .unwrap_err();
+        let err = anyhow!("Failed to get Modrinth project: {}", err);
+        assert!(format!("{:?}", err).contains("mrp_request_S3cret"));
+
+        let printed = redact(&format!("{:?}", err));
+        assert!(!printed.contains("mrp_request_S3cret"), "{}", printed);
+        assert!(printed.contains("token=***"), "{}", printed);
+    }
+
+    #[test]
+    fn webhook_urls_are_shown_as_their_id() {
+        let url = Secret::webhook_url(WEBHOOK_URL.to_string());
+
+        assert_eq!(format!("{}", url), "<webhook 112233445566>");
+        assert_eq!(
+            redact(&format!("Failed to send `{}`", WEBHOOK_URL)),
+            "Failed to send `<webhook 112233445566>`"
+        );
+        assert_eq!(
+            redact("Request to `/api/webhooks/112233445566/webhook-token-R3dact?wait=true` failed"),
+            "Request to `/api/webhooks/112233445566/***?wait=true` failed"
+        );
+    }
+
+    #[test]
+    fn other_urls_are_masked_entirely() {
+        let url =
+            Secret::webhook_url("https://hooks.slack.com/services/T000/B000/sl4ck".to_string());
+
+        assert_eq!(format!("{}", url), "***");
+        assert_eq!(
+            redact("Failed to post to https://hooks.slack.com/services/T000/B000/sl4ck"),
+            "Failed to post to ***"
+        );
+    }
+}
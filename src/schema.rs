@@ -0,0 +1,133 @@
+//! JSON Schema of the config files, so that editors can complete and check their keys.
+//! Descriptions are the doc comments of the config structs.
+
+use crate::models::project_type::{
+    mc_mod::config::ModConfig, modpack::config::ModpackConfig, pack::config::PackConfig,
+};
+use anyhow::anyhow;
+use clap::ValueEnum;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject};
+use schemars::schema_for;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ConfigKind {
+    /// `mrpack.toml`
+    Modpack,
+    /// `peony_mod.toml`
+    Mod,
+    /// `peony_pack.toml`
+    Pack,
+}
+
+pub fn config_schema(kind: ConfigKind) -> Result<String, anyhow::Error> {
+    let mut schema = match kind {
+        ConfigKind::Modpack => schema_for!(ModpackConfig),
+        ConfigKind::Mod => schema_for!(ModConfig),
+        ConfigKind::Pack => schema_for!(PackConfig),
+    };
+
+    // Profiles are merged into the config before it's parsed, so it has no field for them
+    schema.schema.object().properties.insert(
+        "profile".to_string(),
+        Schema::Object(SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "Tables merged into the rest of the config with `--profile <name>`."
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::Object.into()),
+            ..Default::default()
+        }),
+    );
+
+    match serde_json::to_string_pretty(&schema) {
+        Ok(json) => Ok(json),
+        Err(err) => Err(anyhow!("Failed to serialize config schema: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(kind: ConfigKind) -> serde_json::Value {
+        serde_json::from_str(&config_schema(kind).unwrap()).unwrap()
+    }
+
+    fn has_properties(schema: &serde_json::Value, names: &[&str]) {
+        let properties = schema["properties"].as_object().unwrap();
+        for name in names {
+            assert!(properties.contains_key(*name), "no `{}` in schema", name);
+        }
+    }
+
+    #[test]
+    fn modpack_schema_has_top_level_sections() {
+        let schema = schema(ConfigKind::Modpack);
+
+        has_properties(
+            &schema,
+            &[
+                "config_format_version",
+                "version_name_format",
+                "github",
+                "modrinth",
+                "curseforge",
+                "packwiz",
+                "discord",
+                "changelog",
+                "hooks",
+                "network",
+                "profile",
+            ],
+        );
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["config_format_version", "version_name_format"])
+        );
+    }
+
+    #[test]
+    fn mod_schema_has_top_level_sections() {
+        let schema = schema(ConfigKind::Mod);
+
+        has_properties(
+            &schema,
+            &[
+                "config_format_version",
+                "mc_versions",
+                "mc_version_alias",
+                "github",
+                "modrinth",
+                "maven",
+                "discord",
+                "changelog",
+                "network",
+                "profile",
+            ],
+        );
+        // Shared with modpacks, where every platform is optional
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"mc_versions".into()));
+        assert!(!required.contains(&"github".into()));
+        assert!(!required.contains(&"modrinth".into()));
+    }
+
+    #[test]
+    fn pack_schema_parses() {
+        has_properties(&schema(ConfigKind::Pack), &["profile"]);
+    }
+
+    #[test]
+    fn schema_has_doc_comments_as_descriptions() {
+        let schema = schema(ConfigKind::Modpack);
+
+        assert_eq!(
+            schema["properties"]["output_filename"]["description"],
+            "Name of the exported file, with `{name}`, `{version}` and `{mc_version}` \
+             placeholders. The name packwiz picks is kept when it isn't set."
+        );
+    }
+}
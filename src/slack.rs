@@ -0,0 +1,235 @@
+use crate::discord::{fit_changelog, truncate_chars};
+use crate::models::slack::{Block, ButtonElement, SlackMessage, TextObject, TextType};
+use crate::models::{
+    release::{Notifier, Platform, ReleaseSummary, WebhookResult},
+    NetworkConfig, SlackConfig,
+};
+use crate::util::{error_body, read_secret_env, send_with_retry};
+use anyhow::anyhow;
+use tracing::{info, warn};
+
+// https://api.slack.com/reference/block-kit/blocks
+const HEADER_TEXT_LIMIT: usize = 150;
+const SECTION_TEXT_LIMIT: usize = 3000;
+
+/// Posts the release to the Slack incoming webhook of the config. The message only uses the
+/// summary, so it works the same for modpacks and mods.
+pub async fn send_slack_notification(
+    slack_config: &SlackConfig,
+    release: &ReleaseSummary,
+    network: &NetworkConfig,
+    dry_run: bool,
+) -> Result<WebhookResult, anyhow::Error> {
+    let env_var = slack_config.webhook_env();
+    let message = release_message(slack_config, release);
+
+    if dry_run {
+        let message_preview = match serde_json::to_string_pretty(&message) {
+            Ok(json) => json,
+            Err(err) => return Err(anyhow!("Failed to serialize Slack message: {}", err)),
+        };
+
+        info!(
+            "[dry run] Would send Slack webhook `{}`:\n{}",
+            env_var, message_preview
+        );
+        return Ok(WebhookResult {
+            notifier: Notifier::Slack,
+            target: env_var,
+            error: None,
+        });
+    }
+
+    let error = match execute_webhook(&env_var, &message, network).await {
+        Ok(_) => {
+            info!("Sent Slack webhook `{}`!", env_var);
+            None
+        }
+        Err(err) => {
+            warn!("{}", err);
+            Some(err.to_string())
+        }
+    };
+
+    Ok(WebhookResult {
+        notifier: Notifier::Slack,
+        target: env_var,
+        error,
+    })
+}
+
+pub fn release_message(slack_config: &SlackConfig, release: &ReleaseSummary) -> SlackMessage {
+    let title = match &slack_config.title_emoji {
+        Some(emoji) => format!("{} {}", emoji, release.version_name),
+        None => release.version_name.clone(),
+    };
+
+    let mut details = vec![format!("*Version:* `{}`", release.version_number)];
+    if !release.game_versions.is_empty() {
+        details.push(format!("*Minecraft:* {}", release.game_versions.join(", ")));
+    }
+    if !release.loaders.is_empty() {
+        details.push(format!(
+            "*Loaders:* {}",
+            release
+                .loaders
+                .iter()
+                .map(|loader| loader.formatted())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+
+    let mut blocks = vec![
+        Block::Header {
+            text: plain_text(&truncate_chars(&title, HEADER_TEXT_LIMIT)),
+        },
+        Block::Section {
+            text: mrkdwn(&details.join("\n")),
+        },
+    ];
+
+    // Full changelogs are on the release pages the buttons link to
+    let changelog = markdown_to_mrkdwn(&release.changelog.render_github());
+    if !changelog.trim().is_empty() {
+        blocks.push(Block::Section {
+            text: mrkdwn(&fit_changelog(&changelog, SECTION_TEXT_LIMIT, None)),
+        });
+    }
+
+    let buttons = [
+        Platform::Github,
+        Platform::Gitlab,
+        Platform::Gitea,
+        Platform::Modrinth,
+        Platform::Curseforge,
+    ]
+    .into_iter()
+    .filter_map(|platform| {
+        release.url(platform).map(|url| ButtonElement {
+            element_type: "button".to_string(),
+            text: plain_text(&platform.formatted()),
+            url: url.clone(),
+        })
+    })
+    .collect::<Vec<ButtonElement>>();
+
+    if !buttons.is_empty() {
+        blocks.push(Block::Actions { elements: buttons });
+    }
+
+    SlackMessage {
+        text: format!("New release: {}", release.version_name),
+        blocks,
+    }
+}
+
+fn plain_text(text: &str) -> TextObject {
+    TextObject {
+        text_type: TextType::PlainText,
+        text: text.to_string(),
+    }
+}
+
+fn mrkdwn(text: &str) -> TextObject {
+    TextObject {
+        text_type: TextType::Mrkdwn,
+        text: text.to_string(),
+    }
+}
+
+/// Slack's `mrkdwn` isn't Markdown, so the parts changelogs use are rewritten: headings and
+/// `**bold**` become `*bold*`, links become `<url|text>` and list items get bullets.
+pub fn markdown_to_mrkdwn(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+
+            let line = if trimmed.starts_with('#') {
+                let heading = trimmed.trim_start_matches('#').trim();
+                if heading.is_empty() {
+                    String::new()
+                } else {
+                    format!("*{}*", heading.replace("**", ""))
+                }
+            } else if let Some(item) = trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+            {
+                let indent = &line[..line.len() - trimmed.len()];
+                format!("{}• {}", indent, item.replace("**", "*"))
+            } else {
+                line.replace("**", "*")
+            };
+
+            convert_links(&line)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn convert_links(line: &str) -> String {
+    let mut converted = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('[') {
+        let link = rest[start + 1..]
+            .split_once("](")
+            .and_then(|(text, after)| {
+                let (url, after) = after.split_once(')')?;
+                (!text.contains('[')).then_some((text, url, after))
+            });
+
+        match link {
+            Some((text, url, after)) => {
+                converted.push_str(&rest[..start]);
+                converted.push_str(&format!("<{}|{}>", url, text));
+                rest = after;
+            }
+            None => {
+                converted.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+
+    converted.push_str(rest);
+    converted
+}
+
+pub async fn execute_webhook(
+    env_var: &str,
+    message: &SlackMessage,
+    network: &NetworkConfig,
+) -> Result<(), anyhow::Error> {
+    let url = match read_secret_env(env_var) {
+        Ok(url) => url,
+        Err(_) => return Err(anyhow!("Failed to get webhook url from `{}`", env_var)),
+    };
+
+    let res = match send_with_retry(network, |client| {
+        Ok(client.post(url.expose()).json(message))
+    })
+    .await
+    {
+        Ok(res) => res,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to send Slack webhook `{}`: {}",
+                env_var,
+                err
+            ))
+        }
+    };
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to send Slack webhook `{}`: {}",
+            env_var,
+            error_body(res).await
+        ))
+    }
+}
@@ -0,0 +1,130 @@
+//! Helpers shared by the tests of several modules: temporary directories, config fixtures
+//! and a server that counts uploaded bytes.
+
+use crate::config_check::CheckConfig;
+use crate::models::{project_type::modpack::PackFile, util::TempInfo};
+use crate::pack::get_pack_file;
+use crate::util::read_config;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::{env, fs};
+
+/// The keys every modpack config needs, for tests to add their own sections to.
+pub const MODPACK_CONFIG: &str = r#"
+config_format_version = 1
+version_name_format = "{pack_name} {version}"
+"#;
+
+/// Parses `base` with `extra` appended, without the checks of [`read_test_config`].
+pub fn config_with<T: DeserializeOwned>(base: &str, extra: &str) -> T {
+    toml::from_str(&format!("{}\n{}", base, extra)).unwrap()
+}
+
+/// The `pack.toml` of `test_projects/modpack`.
+pub fn test_pack_file() -> PackFile {
+    get_pack_file(&Path::new(env!("CARGO_MANIFEST_DIR")).join("test_projects/modpack")).unwrap()
+}
+
+/// Reads `contents` the way config files are read, with profiles, interpolation and checks.
+pub fn read_test_config<T: DeserializeOwned + CheckConfig>(
+    contents: &str,
+    profile: Option<&str>,
+) -> Result<T, anyhow::Error> {
+    let dir = test_dir();
+    let path = dir.dir_path.join("mrpack.toml");
+    fs::write(&path, contents).unwrap();
+
+    read_config(&path, profile)
+}
+
+/// An empty temporary directory for tests, removed once dropped.
+pub fn test_dir() -> TempInfo {
+    let dir_path = env::temp_dir().join(format!(
+        "{}_test_{}",
+        env!("CARGO_PKG_NAME"),
+        uuid::Uuid::new_v4()
+    ));
+    fs::create_dir(&dir_path).unwrap();
+
+    TempInfo::new(dir_path)
+}
+
+/// A server for upload tests that answers one request with `response_body`, counting the bytes
+/// of its body without keeping them. Returns its URL, and the request's head and body size
+/// once it was answered.
+pub async fn upload_sink(
+    response_body: String,
+) -> (String, tokio::task::JoinHandle<(String, u64)>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 64 * 1024];
+        let mut head = vec![];
+
+        let mut received = loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            assert!(read > 0, "connection closed before the request's head");
+            head.extend_from_slice(&buf[..read]);
+
+            if let Some(end) = head.windows(4).position(|window| window == b"\r\n\r\n") {
+                let received = (head.len() - end - 4) as u64;
+                head.truncate(end);
+                break received;
+            }
+        };
+
+        let head = String::from_utf8(head).unwrap();
+        let content_length = head
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                match name.eq_ignore_ascii_case("content-length") {
+                    true => value.trim().parse::<u64>().ok(),
+                    false => None,
+                }
+            })
+            .expect("upload without a Content-Length");
+
+        while received < content_length {
+            match stream.read(&mut buf).await.unwrap() {
+                0 => break,
+                read => received += read as u64,
+            }
+        }
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response_body.len(),
+            response_body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+
+        (head, received)
+    });
+
+    (url, handle)
+}
+
+/// Highest resident memory of the process so far, in bytes.
+#[cfg(target_os = "linux")]
+pub fn peak_memory() -> u64 {
+    let status = fs::read_to_string("/proc/self/status").unwrap();
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .unwrap();
+
+    kilobytes * 1024
+}
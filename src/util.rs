@@ -1,17 +1,235 @@
-use crate::models::util::TempInfo;
+use crate::config_check::{describe_parse_error, format_problems, unknown_keys, CheckConfig};
+use crate::credentials::read_credential;
+use crate::interpolate::interpolate_value;
+use crate::models::{release::ReleaseState, util::TempInfo, NetworkConfig};
+use crate::progress::{CopyBar, UploadProgress};
+use crate::redact::Secret;
 use anyhow::anyhow;
+use futures::TryStreamExt;
+use ignore::{overrides::OverrideBuilder, WalkBuilder, WalkState};
+use reqwest::multipart::Part;
+use reqwest::{Body, Certificate, Client, Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fs};
+use tracing::{debug, info, warn};
 
-pub fn create_temp() -> Result<TempInfo, anyhow::Error> {
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Modrinth asks for a User-Agent that identifies the application and how to contact its
+/// authors.
+pub const USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("CARGO_PKG_REPOSITORY"),
+    ")"
+);
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// How much of a failed response's body is logged, error messages still get all of it.
+const ERROR_BODY_LOG_CHARS: usize = 500;
+
+/// With a `profile`, its `[profile.<name>]` table is merged into the rest of the config.
+/// Unknown keys and the problems of [`CheckConfig`] are reported together, with their lines.
+pub fn read_config<T: DeserializeOwned + CheckConfig>(
+    path: &Path,
+    profile: Option<&str>,
+) -> Result<T, anyhow::Error> {
+    if !path.exists() {
+        return Err(anyhow!(
+            "Failed to find config file at `{}`",
+            path.display()
+        ));
+    }
+
+    let content_string = match fs::read_to_string(path) {
+        Ok(content_string) => content_string,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to read config file `{}`: {}",
+                path.display(),
+                err
+            ))
+        }
+    };
+
+    // Variables are interpolated after parsing, so that their values can't break the TOML
+    let mut config_table = match toml::from_str::<toml::Table>(&content_string) {
+        Ok(table) => table,
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to parse config file `{}`: {}",
+                path.display(),
+                err
+            ))
+        }
+    };
+
+    let mut profiles = match config_table.remove("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => {
+            return Err(anyhow!(
+                "Failed to parse config file `{}`: `profile` must be a table",
+                path.display()
+            ))
+        }
+        None => toml::Table::new(),
+    };
+
+    if let Some(profile) = profile {
+        match profiles.remove(profile) {
+            Some(toml::Value::Table(overrides)) => merge_toml_tables(&mut config_table, overrides),
+            Some(_) => {
+                return Err(anyhow!(
+                    "Failed to parse config file `{}`: `profile.{}` must be a table",
+                    path.display(),
+                    profile
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "Failed to find profile `{}` in `{}`",
+                    profile,
+                    path.display()
+                ))
+            }
+        }
+    }
+
+    let mut config_value = toml::Value::Table(config_table);
+    interpolate_value(&mut config_value, "")?;
+
+    let raw_table = match &config_value {
+        toml::Value::Table(table) => table.clone(),
+        _ => toml::Table::new(),
+    };
+
+    let config: T = match config_value.try_into() {
+        Ok(config) => config,
+        Err(err) => {
+            // Parsing the file alone points at the value, when the profile isn't what broke it.
+            // Errors of the flattened sections point at the whole document instead.
+            let span = toml::from_str::<T>(&content_string)
+                .err()
+                .filter(|file_err| file_err.message() == err.message())
+                .and_then(|file_err| file_err.span())
+                .filter(|span| span.start > 0);
+
+            return Err(anyhow!(
+                "Failed to parse config file `{}`: {}",
+                path.display(),
+                describe_parse_error(&content_string, profile, &raw_table, &err, span)
+            ));
+        }
+    };
+
+    let mut problems = match serde_json::to_value(&config) {
+        Ok(parsed) => unknown_keys(&raw_table, &parsed),
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to check config file `{}`: {}",
+                path.display(),
+                err
+            ))
+        }
+    };
+    problems.extend(config.problems());
+
+    if !problems.is_empty() {
+        return Err(anyhow!(
+            "Invalid config file `{}`:{}",
+            path.display(),
+            format_problems(&content_string, profile, &problems)
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Tables are merged key by key, any other value of `overrides` replaces the base value,
+/// arrays included.
+pub fn merge_toml_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(override_table)) => {
+                merge_toml_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Relative paths are resolved against the directory containing the config file,
+/// so that peony can be run from outside the project directory.
+pub fn project_dir_from_config(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+const RELEASE_STATE_FILE: &str = ".peony-state.json";
+
+/// A missing or unreadable state file is treated as nothing having been sent yet.
+pub fn read_release_state(project_dir: &Path) -> Option<ReleaseState> {
+    let contents = fs::read_to_string(project_dir.join(RELEASE_STATE_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+pub fn write_release_state(project_dir: &Path, state: &ReleaseState) -> Result<(), anyhow::Error> {
+    write_json_file(&project_dir.join(RELEASE_STATE_FILE), state)
+}
+
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), anyhow::Error> {
+    let contents = match serde_json::to_string_pretty(value) {
+        Ok(json) => json,
+        Err(err) => return Err(anyhow!("Failed to serialize `{}`: {}", path.display(), err)),
+    };
+
+    match fs::write(path, contents) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow!("Failed to write `{}`: {}", path.display(), err)),
+    }
+}
+
+/// The directory a release is made in, the project directory itself when `in_place`.
+pub fn create_work_dir(
+    project_dir: &Path,
+    in_place: bool,
+    excludes: &[String],
+) -> Result<TempInfo, anyhow::Error> {
+    if in_place {
+        Ok(TempInfo::in_place(project_dir.to_path_buf()))
+    } else {
+        create_temp(project_dir, excludes)
+    }
+}
+
+/// Copies the project to a new temporary directory, without the files matched by `excludes`
+/// (gitignore syntax) or ignored by its `.gitignore` files.
+pub fn create_temp(project_dir: &Path, excludes: &[String]) -> Result<TempInfo, anyhow::Error> {
     let new_uuid = uuid::Uuid::new_v4();
     let new_tmp_dir_name = format!("{}_{}", env!("CARGO_PKG_NAME"), new_uuid);
     let new_tmp_dir = Path::new(env::temp_dir().as_path()).join(&new_tmp_dir_name);
 
-    let current_dir = match env::current_dir() {
+    let current_dir = match project_dir.canonicalize() {
         Ok(dir) => dir,
-        Err(err) => return Err(anyhow!("Failed to get current directory: {}", err)),
+        Err(err) => {
+            return Err(anyhow!(
+                "Failed to resolve project directory `{}`: {}",
+                project_dir.display(),
+                err
+            ))
+        }
     };
 
     match fs::create_dir(&new_tmp_dir) {
@@ -19,35 +237,129 @@ pub fn create_temp() -> Result<TempInfo, anyhow::Error> {
         Err(err) => return Err(anyhow!("Failed to create temporary directory: {}", err)),
     }
 
-    let copy_files_res: Result<(), anyhow::Error> = match fs_extra::dir::copy(
-        current_dir,
-        &new_tmp_dir,
-        &fs_extra::dir::CopyOptions::new().content_only(true),
-    ) {
-        Ok(_) => Ok(()),
+    // Created before copying so that a failed copy is cleaned up as well
+    let tmp_info = TempInfo::new(new_tmp_dir);
+
+    let started_at = Instant::now();
+
+    match copy_dir(&current_dir, &tmp_info.dir_path, excludes) {
+        Ok(copied) => {
+            debug!(
+                "Copied {} files to the temporary directory in {:.2?}",
+                copied,
+                started_at.elapsed()
+            );
+            Ok(tmp_info)
+        }
+        Err(err) => Err(anyhow!(
+            "Failed to copy files to temporary directory: {}",
+            err
+        )),
+    }
+}
+
+/// Copies on several threads, returns the number of files copied.
+fn copy_dir(from: &Path, to: &Path, excludes: &[String]) -> Result<usize, anyhow::Error> {
+    let mut overrides = OverrideBuilder::new(from);
+    for pattern in excludes {
+        // Overrides are a whitelist, `!` turns them into excludes
+        if let Err(err) = overrides.add(&format!("!{}", pattern)) {
+            return Err(anyhow!("Invalid copy exclude `{}`: {}", pattern, err));
+        }
+    }
+    let overrides = match overrides.build() {
+        Ok(overrides) => overrides,
+        Err(err) => return Err(anyhow!("Invalid copy excludes: {}", err)),
+    };
+
+    let copied = AtomicUsize::new(0);
+    let errors = Mutex::new(vec![]);
+    let bar = CopyBar::new("Copying project...");
+
+    WalkBuilder::new(from)
+        .hidden(false)
+        // The temporary directory isn't a repository, and should be copied the same way
+        .require_git(false)
+        .overrides(overrides)
+        .build_parallel()
+        .run(|| {
+            Box::new(|entry| {
+                let result = entry
+                    .map_err(|err| anyhow!("{}", err))
+                    .and_then(|entry| copy_entry(from, to, entry.path()));
+
+                match result {
+                    Ok(Some(bytes)) => {
+                        copied.fetch_add(1, Ordering::Relaxed);
+                        bar.add(bytes);
+                        WalkState::Continue
+                    }
+                    Ok(None) => WalkState::Continue,
+                    Err(err) => {
+                        if let Ok(mut errors) = errors.lock() {
+                            errors.push(err.to_string());
+                        }
+                        WalkState::Quit
+                    }
+                }
+            })
+        });
+
+    let errors = match errors.into_inner() {
+        Ok(errors) => errors,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    match errors.first() {
+        Some(err) => Err(anyhow!("{}", err)),
+        None => Ok(copied.into_inner()),
+    }
+}
+
+/// Returns the size of the copied file, directories are only created.
+fn copy_entry(from: &Path, to: &Path, path: &Path) -> Result<Option<u64>, anyhow::Error> {
+    let relative_path = match path.strip_prefix(from) {
+        Ok(relative_path) => relative_path,
         Err(err) => {
             return Err(anyhow!(
-                "Failed to copy files to temporary directory: {}",
+                "`{}` is outside of the project: {}",
+                path.display(),
                 err
             ))
         }
     };
+    let target = to.join(relative_path);
+
+    if path.is_dir() {
+        return match fs::create_dir_all(&target) {
+            Ok(_) => Ok(None),
+            Err(err) => Err(anyhow!("Failed to create `{}`: {}", target.display(), err)),
+        };
+    }
+
+    // Directories can be visited after their files by other threads
+    if let Some(parent) = target.parent() {
+        if let Err(err) = fs::create_dir_all(parent) {
+            return Err(anyhow!("Failed to create `{}`: {}", parent.display(), err));
+        }
+    }
 
-    match copy_files_res {
-        Ok(_) => Ok(TempInfo {
-            dir_name: new_tmp_dir_name.to_owned(),
-            dir_path: new_tmp_dir,
-        }),
-        Err(err) => Err(anyhow!("Failed to get temp directory info: {}", err)),
+    match fs::copy(path, &target) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) => Err(anyhow!(
+            "Failed to copy `{}`: {}",
+            relative_path.display(),
+            err
+        )),
     }
 }
 
 pub fn clean_up(tmp_dir: &PathBuf) -> Result<(), anyhow::Error> {
-    println!("Cleaning up...");
+    info!("Cleaning up...");
 
     match fs_extra::dir::remove(tmp_dir) {
         Ok(_) => {
-            println!("Removed temporary directory!");
+            info!("Removed temporary directory!");
             Ok(())
         }
         Err(err) => Err(anyhow!("Failed to remove temporary directory: {}", err)),
@@ -72,9 +384,680 @@ pub fn trim_quotes(string: String) -> String {
     string.trim_matches(&['\"'] as &[_]).to_string()
 }
 
+/// Joins with exactly one slash, whether or not `base` ends with one or `path` starts with one.
+pub fn join_url(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Percent-encodes everything but unreserved characters, so that a tag like `mod/1.0+fabric`
+/// stays a single path segment.
+pub fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Reads the first of `env_vars` that is set, the error names every variable looked up.
+/// The keyring is only checked once none of them is set.
+pub fn read_token(env_vars: &[String]) -> Result<Secret, anyhow::Error> {
+    for env_var in env_vars {
+        if let Ok(token) = env::var(env_var) {
+            return Ok(Secret::new(token));
+        }
+    }
+
+    for env_var in env_vars {
+        if let Some(token) = read_credential(env_var) {
+            return Ok(Secret::new(token));
+        }
+    }
+
+    let names = env_vars
+        .iter()
+        .map(|env_var| format!("`{}`", env_var))
+        .collect::<Vec<String>>()
+        .join(" or ");
+
+    Err(anyhow!("Failed to get {} from environment", names))
+}
+
+/// Reads a token or webhook URL from the environment or else the keyring, which is then
+/// redacted from logs.
+pub fn read_secret_env(env_var: &str) -> Result<Secret, anyhow::Error> {
+    match env::var(env_var) {
+        Ok(secret) => Ok(Secret::new(secret)),
+        Err(err) => match read_credential(env_var) {
+            Some(secret) => Ok(Secret::new(secret)),
+            None => Err(anyhow!("Failed to get `{}`: {}", env_var, err)),
+        },
+    }
+}
+
+/// Like [`read_secret_env`], for Discord webhook URLs, which are masked as their ID.
+pub fn read_webhook_env(env_var: &str) -> Result<Secret, anyhow::Error> {
+    match env::var(env_var).ok().or_else(|| read_credential(env_var)) {
+        Some(url) => Ok(Secret::webhook_url(url)),
+        None => Err(anyhow!("Failed to get webhook url from `{}`", env_var)),
+    }
+}
+
+/// Reads the body of a failed response for its error message, logging it with the status.
+pub async fn error_body(res: Response) -> String {
+    let status = res.status();
+    let url = res.url().clone();
+    let body = res.text().await.unwrap_or_default();
+
+    let truncated = match body.char_indices().nth(ERROR_BODY_LOG_CHARS) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.clone(),
+    };
+    debug!("Request to `{}` returned `{}`: {}", url, status, truncated);
+
+    body
+}
+
 pub fn read_file(path: &PathBuf) -> Result<Vec<u8>, anyhow::Error> {
     match fs::read(path) {
         Ok(file) => Ok(file),
         Err(err) => Err(anyhow!("Failed to read file: {}", err)),
     }
 }
+
+pub fn file_size(path: &Path) -> Result<u64, anyhow::Error> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(err) => Err(anyhow!("Failed to read `{}`: {}", path.display(), err)),
+    }
+}
+
+/// Streams the file from disk instead of reading it into memory, so that large packs don't
+/// have to fit into it. Bodies can only be sent once, so it's opened again for every attempt
+/// of [`send_with_retry`].
+pub fn file_body(path: &Path, progress: Option<&UploadProgress>) -> Result<Body, anyhow::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(anyhow!("Failed to open `{}`: {}", path.display(), err)),
+    };
+
+    let progress = progress.cloned();
+    let mut sent = 0;
+
+    let stream = tokio_util::io::ReaderStream::new(tokio::fs::File::from_std(file)).inspect_ok(
+        move |chunk| {
+            sent += chunk.len() as u64;
+            if let Some(progress) = &progress {
+                progress(sent);
+            }
+        },
+    );
+
+    Ok(Body::wrap_stream(stream))
+}
+
+pub fn mime_type_from_file_name(file_name: &str) -> &'static str {
+    match Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some("zip") | Some("mrpack") => "application/zip",
+        Some("jar") => "application/java-archive",
+        Some("txt") | Some("md") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A multipart part streaming the file, see [`file_body`].
+pub fn file_part(
+    path: &Path,
+    file_name: &str,
+    mime_type: &str,
+    progress: Option<&UploadProgress>,
+) -> Result<Part, anyhow::Error> {
+    let size = file_size(path)?;
+
+    match Part::stream_with_length(file_body(path, progress)?, size)
+        .file_name(file_name.to_string())
+        .mime_str(mime_type)
+    {
+        Ok(part) => Ok(part),
+        Err(err) => Err(anyhow!("Failed to get part from `{}`: {}", file_name, err)),
+    }
+}
+
+/// The client shared by every request, so that connections are reused. It's built from the
+/// first `network` config it's requested with, a run only ever has one.
+///
+/// Proxies are read from `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` (or their
+/// lowercase versions) by reqwest itself.
+pub fn http_client(network: &NetworkConfig) -> Result<Client, anyhow::Error> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client.clone());
+    }
+
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(network.connect_timeout());
+
+    if let Some(ca_bundle) = &network.ca_bundle {
+        let pem = match fs::read(ca_bundle) {
+            Ok(pem) => pem,
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to read CA bundle `{}`: {}",
+                    ca_bundle.display(),
+                    err
+                ))
+            }
+        };
+
+        match Certificate::from_pem(&pem) {
+            Ok(cert) => builder = builder.add_root_certificate(cert),
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to parse CA bundle `{}`: {}",
+                    ca_bundle.display(),
+                    err
+                ))
+            }
+        }
+
+        debug!("Trusting certificates from `{}`", ca_bundle.display());
+    }
+
+    if network.danger_accept_invalid_certs() {
+        warn!(
+            "TLS certificate verification is disabled by `danger_accept_invalid_certs`, tokens \
+             and webhook URLs can be intercepted. Never use this outside of test setups"
+        );
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(err) => return Err(anyhow!("Failed to create HTTP client: {}", err)),
+    };
+
+    Ok(HTTP_CLIENT.get_or_init(|| client).clone())
+}
+
+/// Sends the request built by `make_request`, retrying with exponential backoff.
+///
+/// Rate limited (429) requests are always retried, as they were never processed, after
+/// `Retry-After` or the `retry_after_ms` of Matrix errors when there is one. Server errors
+/// and connection failures are only retried for idempotent methods, so that a release is never
+/// created twice. The request is rebuilt for every attempt, as multipart bodies can't be cloned.
+/// Requests without a timeout of their own get the API timeout of `network`.
+pub async fn send_with_retry<F>(
+    network: &NetworkConfig,
+    make_request: F,
+) -> Result<Response, anyhow::Error>
+where
+    F: Fn(&Client) -> Result<RequestBuilder, anyhow::Error>,
+{
+    let shared_client = http_client(network)?;
+    let max_retries = network.retries();
+    let mut attempt = 0;
+
+    loop {
+        let (client, request) = make_request(&shared_client)?.build_split();
+        let mut request = match request {
+            Ok(req) => req,
+            Err(err) => return Err(anyhow!("Failed to build request: {}", err)),
+        };
+
+        if request.timeout().is_none() {
+            *request.timeout_mut() = Some(network.timeout());
+        }
+
+        let url = request.url().clone();
+        debug!("{} {}", request.method(), url);
+        let idempotent = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        );
+
+        let header_delay = match client.execute(request).await {
+            Ok(res) => {
+                let status = res.status();
+                debug!("Request to `{}` returned `{}`", url, status);
+                let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                    || (idempotent
+                        && matches!(
+                            status,
+                            StatusCode::BAD_GATEWAY
+                                | StatusCode::SERVICE_UNAVAILABLE
+                                | StatusCode::GATEWAY_TIMEOUT
+                        ));
+
+                if !retryable || attempt >= max_retries {
+                    return Ok(res);
+                }
+
+                warn!("Request to `{}` returned `{}`", url, status);
+                match retry_delay_from_headers(&res) {
+                    Some(delay) => Some(delay),
+                    None if status == StatusCode::TOO_MANY_REQUESTS => {
+                        retry_delay_from_body(res).await
+                    }
+                    None => None,
+                }
+            }
+            Err(err) => {
+                let retryable = idempotent && (err.is_connect() || err.is_timeout());
+
+                if !retryable || attempt >= max_retries {
+                    return Err(anyhow::Error::from(err));
+                }
+
+                warn!("Request to `{}` failed: {}", url, err);
+                None
+            }
+        };
+
+        let delay = match header_delay {
+            Some(delay) => delay,
+            None => network.base_delay() * 2u32.pow(attempt),
+        }
+        .min(MAX_RETRY_DELAY);
+
+        attempt += 1;
+        warn!(
+            "Retrying in {:.1}s (attempt {}/{})...",
+            delay.as_secs_f32(),
+            attempt,
+            max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Matrix homeservers send `M_LIMIT_EXCEEDED` errors with the time to wait in the body.
+async fn retry_delay_from_body(res: Response) -> Option<Duration> {
+    let body = res.json::<serde_json::Value>().await.ok()?;
+    body.get("retry_after_ms")?
+        .as_u64()
+        .map(Duration::from_millis)
+}
+
+/// Reads `Retry-After`, or `X-Ratelimit-Reset`, which Modrinth sends as seconds until the
+/// limit resets and GitHub sends as a UNIX timestamp.
+pub fn retry_delay_from_headers(res: &Response) -> Option<Duration> {
+    let header_secs = |name: &str| -> Option<u64> {
+        res.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+
+    if let Some(secs) = header_secs("retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let reset = header_secs("x-ratelimit-reset")?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+
+    if reset > now {
+        Some(Duration::from_secs(reset - now))
+    } else if reset < 1_000_000_000 {
+        Some(Duration::from_secs(reset))
+    } else {
+        Some(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::project_type::modpack::config::ModpackConfig;
+    use crate::test_support::read_test_config;
+
+    fn fast_retries(retries: u32) -> NetworkConfig {
+        NetworkConfig {
+            retries: Some(retries),
+            base_delay_ms: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_request_succeeds() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/project")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("GET", "/project")
+            .with_status(200)
+            .with_body("ok")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let url = format!("{}/project", server.url());
+        let res = send_with_retry(&fast_retries(3), |client| Ok(client.get(&url)))
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await.unwrap(), "ok");
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_configured_retries() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/project")
+            .with_status(502)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let url = format!("{}/project", server.url());
+        let res = send_with_retry(&fast_retries(2), |client| Ok(client.get(&url)))
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn server_errors_of_non_idempotent_requests_are_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("POST", "/version")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let url = format!("{}/version", server.url());
+        let res = send_with_retry(&fast_retries(3), |client| Ok(client.post(&url)))
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_retried_after_retry_after() {
+        let mut server = mockito::Server::new_async().await;
+        let rate_limited = server
+            .mock("POST", "/version")
+            .with_status(429)
+            .with_header("Retry-After", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let created = server
+            .mock("POST", "/version")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let url = format!("{}/version", server.url());
+        let res = send_with_retry(&fast_retries(3), |client| Ok(client.post(&url)))
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+        rate_limited.assert_async().await;
+        created.assert_async().await;
+    }
+
+    async fn response_with_header(name: &str, value: &str) -> Response {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/")
+            .with_header(name, value)
+            .create_async()
+            .await;
+
+        Client::new().get(server.url()).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn retry_delay_from_retry_after() {
+        let res = response_with_header("Retry-After", "7").await;
+        assert_eq!(retry_delay_from_headers(&res), Some(Duration::from_secs(7)));
+    }
+
+    #[tokio::test]
+    async fn retry_delay_from_ratelimit_reset_seconds() {
+        // Modrinth sends the seconds until the limit resets
+        let res = response_with_header("X-Ratelimit-Reset", "12").await;
+        assert_eq!(
+            retry_delay_from_headers(&res),
+            Some(Duration::from_secs(12))
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_delay_from_ratelimit_reset_timestamp() {
+        // GitHub sends the UNIX timestamp the limit resets at
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let res = response_with_header("X-Ratelimit-Reset", &(now + 60).to_string()).await;
+        let delay = retry_delay_from_headers(&res).unwrap();
+        assert!(delay <= Duration::from_secs(60) && delay >= Duration::from_secs(58));
+
+        let res = response_with_header("X-Ratelimit-Reset", &(now - 60).to_string()).await;
+        assert_eq!(retry_delay_from_headers(&res), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn no_retry_delay_without_headers() {
+        let res = response_with_header("Content-Type", "text/plain").await;
+        assert_eq!(retry_delay_from_headers(&res), None);
+    }
+
+    const DISCORD_CONFIG: &str = r#"
+config_format_version = 1
+version_name_format = "{pack_name} {version}"
+
+[github]
+repo_owner = "${PEONY_TEST_CONFIG_REPO_OWNER}"
+repo_name = "${PEONY_TEST_UNSET_VARIABLE:-peony-test}"
+
+[discord]
+github_emoji_id = "<:github:1>"
+modrinth_emoji_id = "<:modrinth:2>"
+discord_ping_role = "${PEONY_TEST_CONFIG_PING_ROLE}"
+title_emoji = "$${not interpolated}"
+
+[[discord.webhooks]]
+env_var = "${PEONY_TEST_UNSET_VARIABLE:-WEBHOOK_URL}"
+"#;
+
+    #[test]
+    fn config_strings_are_interpolated() {
+        env::set_var("PEONY_TEST_CONFIG_REPO_OWNER", "LilydevMC");
+        env::set_var("PEONY_TEST_CONFIG_PING_ROLE", "123456789");
+
+        let config: ModpackConfig = read_test_config(DISCORD_CONFIG, None).unwrap();
+
+        let github = config.project.github.unwrap();
+        assert_eq!(github.repo_owner, "LilydevMC");
+        assert_eq!(github.repo_name, "peony-test");
+
+        let discord = config.project.discord.unwrap();
+        assert_eq!(discord.discord_ping_role, "123456789");
+        assert_eq!(discord.title_emoji, "${not interpolated}");
+        assert_eq!(discord.webhooks()[0].env_var, "WEBHOOK_URL");
+    }
+
+    #[test]
+    fn unset_config_variable_fails_with_its_key() {
+        let config = DISCORD_CONFIG.replace(
+            "${PEONY_TEST_CONFIG_PING_ROLE}",
+            "${PEONY_TEST_UNSET_PING_ROLE}",
+        );
+        env::set_var("PEONY_TEST_CONFIG_REPO_OWNER", "LilydevMC");
+
+        let err = read_test_config::<ModpackConfig>(&config, None)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(
+                "Failed to interpolate `discord.discord_ping_role`: \
+                 Failed to get `PEONY_TEST_UNSET_PING_ROLE` from environment"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn interpolated_values_are_not_parsed_as_toml() {
+        let config = DISCORD_CONFIG.replace(
+            "${PEONY_TEST_CONFIG_REPO_OWNER}",
+            "${PEONY_TEST_INJECTED_REPO_OWNER}",
+        );
+        env::set_var(
+            "PEONY_TEST_INJECTED_REPO_OWNER",
+            "\"\nrepo_name = \"injected",
+        );
+        env::set_var("PEONY_TEST_CONFIG_PING_ROLE", "123456789");
+
+        let config: ModpackConfig = read_test_config(&config, None).unwrap();
+
+        let github = config.project.github.unwrap();
+        assert_eq!(github.repo_owner, "\"\nrepo_name = \"injected");
+        assert_eq!(github.repo_name, "peony-test");
+    }
+
+    const CONFIG_HEADER: &str = r#"
+config_format_version = 1
+version_name_format = "{pack_name} {version}"
+"#;
+
+    const PROFILE_CONFIG: &str = r#"
+config_format_version = 1
+version_name_format = "{pack_name} {version}"
+additional_files = ["README.md", "LICENSE"]
+
+[modrinth]
+project_id = "AABBCCDD"
+
+[profile.staging]
+additional_files = ["README.md"]
+
+[profile.staging.modrinth]
+staging = true
+"#;
+
+    #[test]
+    fn profile_is_merged_into_config() {
+        let config: ModpackConfig = read_test_config(PROFILE_CONFIG, Some("staging")).unwrap();
+
+        let modrinth = config.project.modrinth.unwrap();
+        assert_eq!(modrinth.project_id, "AABBCCDD");
+        assert_eq!(modrinth.staging, Some(true));
+        assert_eq!(config.additional_files, Some(vec!["README.md".to_string()]));
+    }
+
+    #[test]
+    fn profiles_are_ignored_without_profile() {
+        let config: ModpackConfig = read_test_config(PROFILE_CONFIG, None).unwrap();
+
+        assert_eq!(config.project.modrinth.unwrap().staging, None);
+        assert_eq!(
+            config.additional_files,
+            Some(vec!["README.md".to_string(), "LICENSE".to_string()])
+        );
+    }
+
+    #[test]
+    fn missing_profile_fails() {
+        let err = read_test_config::<ModpackConfig>(PROFILE_CONFIG, Some("production"))
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            err.starts_with("Failed to find profile `production` in `"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn profile_must_be_a_table() {
+        let config = format!("{}\n[profile]\nrelease = true\n", CONFIG_HEADER);
+
+        let err = read_test_config::<ModpackConfig>(&config, Some("release"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.ends_with("`profile.release` must be a table"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn merged_tables_keep_their_other_keys() {
+        let mut base: toml::Table = toml::from_str(
+            r#"
+[github]
+repo_owner = "LilydevMC"
+assets = ["a.zip"]
+
+[github.nested]
+kept = true
+"#,
+        )
+        .unwrap();
+        let overrides: toml::Table = toml::from_str(
+            r#"
+[github]
+assets = ["b.zip"]
+
+[github.nested]
+added = true
+"#,
+        )
+        .unwrap();
+
+        merge_toml_tables(&mut base, overrides);
+
+        assert_eq!(
+            base,
+            toml::from_str::<toml::Table>(
+                r#"
+[github]
+repo_owner = "LilydevMC"
+assets = ["b.zip"]
+
+[github.nested]
+kept = true
+added = true
+"#,
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use anyhow::anyhow;
+use std::path::Path;
+
+use crate::models::{
+    project_type::{mc_mod::config::ModConfig, modpack::config::ModpackConfig},
+    GithubConfig, ModrinthConfig,
+};
+use crate::packwiz::{cached_packwiz_path, find_system_packwiz};
+use crate::redact::Secret;
+use crate::release::{ModReleaseOptions, ReleaseOptions};
+use crate::{
+    email::check_smtp_connection,
+    mc_mod::{check_java_version, find_java},
+    pack::get_pack_file,
+    util::{
+        project_dir_from_config, read_config, read_secret_env, read_webhook_env, send_with_retry,
+    },
+    version::{check_output_filename, check_version_name_format},
+};
+
+pub struct Check {
+    pub name: String,
+    pub result: Result<(), anyhow::Error>,
+    pub required: bool,
+}
+
+impl Check {
+    pub fn new(name: &str, result: Result<(), anyhow::Error>) -> Self {
+        Self {
+            name: name.to_string(),
+            result,
+            required: true,
+        }
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+pub async fn validate_modpack(config_path: &Path, profile: Option<&str>) -> Vec<Check> {
+    let mut checks = vec![];
+
+    let config_file: ModpackConfig = match read_config(config_path, profile) {
+        Ok(config) => {
+            checks.push(Check::new("Parse config file", Ok(())));
+            config
+        }
+        Err(err) => {
+            // Nothing else can be checked without a config
+            checks.push(Check::new("Parse config file", Err(err)));
+            return checks;
+        }
+    };
+
+    checks.push(Check::new(
+        "Check release targets",
+        config_file.check_release_targets(),
+    ));
+
+    checks.push(Check::new(
+        "Parse version_name_format",
+        check_version_name_format(&config_file.version_name_format),
+    ));
+
+    if let Some(template) = &config_file.output_filename {
+        checks.push(Check::new(
+            "Parse output_filename",
+            check_output_filename(template),
+        ));
+    }
+
+    let project_dir = project_dir_from_config(config_path);
+
+    checks.push(Check::new(
+        "Parse pack.toml",
+        get_pack_file(&project_dir).map(|_| ()),
+    ));
+
+    match config_file
+        .packwiz
+        .as_ref()
+        .and_then(|packwiz| packwiz.pinned_version())
+    {
+        // Pinned versions are downloaded when releasing
+        Some(version) => checks.push(
+            Check::new(
+                &format!("Find cached packwiz {}", version),
+                cached_packwiz_path(version).and_then(|path| {
+                    if path.is_file() {
+                        Ok(())
+                    } else {
+                        Err(anyhow!("Not downloaded yet, it will be when releasing"))
+                    }
+                }),
+            )
+            .optional(),
+        ),
+        None => checks.push(Check::new(
+            "Find packwiz executable",
+            find_system_packwiz().map(|_| ()),
+        )),
+    }
+
+    if let Some(github_config) = config_file.github() {
+        let token = github_config.token();
+        checks.push(Check::new(
+            &format!(
+                "{} is set",
+                github_config
+                    .token_env_vars()
+                    .iter()
+                    .map(|env_var| format!("`{}`", env_var))
+                    .collect::<Vec<String>>()
+                    .join(" or ")
+            ),
+            token.as_ref().map(|_| ()).map_err(|err| anyhow!("{}", err)),
+        ));
+        let token = token.ok();
+
+        if let Some(token) = token {
+            checks.push(Check::new(
+                "Access GitHub repository",
+                check_github_repo(&config_file, github_config, &token).await,
+            ));
+        }
+    }
+
+    if let Some(gitlab_config) = config_file.gitlab() {
+        checks.push(env_var_check(&gitlab_config.token_env()));
+    }
+
+    if let Some(gitea_config) = config_file.gitea() {
+        checks.push(env_var_check(&gitea_config.token_env()));
+    }
+
+    if let Some(modrinth_config) = config_file.modrinth() {
+        let token_check = env_var_check(&modrinth_config.token_env());
+        let token = modrinth_config.token().ok();
+        checks.push(token_check);
+
+        if let Some(token) = token {
+            checks.push(Check::new(
+                "Access Modrinth project",
+                check_modrinth_project(&config_file, modrinth_config, &token).await,
+            ));
+        }
+    }
+
+    if let Some(curseforge_config) = &config_file.curseforge {
+        if curseforge_config.is_enabled() {
+            checks.push(env_var_check(&curseforge_config.token_env()));
+        }
+    }
+
+    if let Some(discord_config) = &config_file.project.discord {
+        checks.push(Check::new("Check Discord config", discord_config.check()));
+
+        // Only needed when running with `--discord`
+        for webhook in discord_config.webhooks() {
+            checks.push(env_var_check(&webhook.env_var).optional());
+        }
+    }
+
+    // Only needed when running with `--slack`
+    if let Some(slack_config) = &config_file.project.slack {
+        checks.push(env_var_check(&slack_config.webhook_env()).optional());
+    }
+
+    if let Some(matrix_config) = &config_file.project.matrix {
+        checks.push(Check::new("Check Matrix config", matrix_config.check()));
+
+        // Only needed with `--matrix`, unless the config enables it
+        let token_check = env_var_check(&matrix_config.access_token_env());
+        checks.push(match matrix_config.is_enabled() {
+            true => token_check,
+            false => token_check.optional(),
+        });
+    }
+
+    // Failed posts only warn, so neither is required
+    if let Some(mastodon_config) = config_file
+        .project
+        .mastodon
+        .as_ref()
+        .filter(|m| m.is_enabled())
+    {
+        checks.push(Check::new("Check Mastodon template", mastodon_config.check()).optional());
+        checks.push(env_var_check(&mastodon_config.token_env()).optional());
+    }
+
+    if let Some(notify_config) = &config_file.project.notify {
+        for (index, http_config) in notify_config.http().iter().enumerate() {
+            checks.push(Check::new(
+                &format!("Check HTTP notifier `{}`", http_config.name(index)),
+                http_config.check(index),
+            ));
+            if let Some(env_var) = &http_config.url_env {
+                checks.push(env_var_check(env_var));
+            }
+        }
+    }
+
+    // Failed emails only warn as well. Connecting also logs in, but nothing is sent.
+    if let Some(email_config) = config_file
+        .project
+        .email
+        .as_ref()
+        .filter(|e| e.is_enabled())
+    {
+        checks.push(Check::new("Check email config", email_config.check()).optional());
+        if email_config.username.is_some() {
+            checks.push(env_var_check(&email_config.password_env()).optional());
+        }
+
+        let network = config_file.project.network.clone().unwrap_or_default();
+        checks.push(
+            Check::new(
+                &format!(
+                    "Connect to SMTP server `{}:{}`",
+                    email_config.host,
+                    email_config.port()
+                ),
+                check_smtp_connection(email_config, &network).await,
+            )
+            .optional(),
+        );
+    }
+
+    checks
+}
+
+/// Checks everything a modpack release with these options needs before any work is done, so
+/// that a missing token doesn't fail the release halfway. Every problem is reported at once.
+pub fn preflight_modpack(
+    config: &ModpackConfig,
+    opts: &ReleaseOptions,
+) -> Result<(), anyhow::Error> {
+    let mut missing = vec![];
+
+    if config
+        .packwiz
+        .as_ref()
+        .and_then(|packwiz| packwiz.pinned_version())
+        .is_none()
+    {
+        if let Err(err) = find_system_packwiz() {
+            missing.push(err.to_string());
+        }
+    }
+
+    let mut secrets: Vec<Result<Secret, anyhow::Error>> = vec![];
+
+    if let (false, Some(github_config)) = (opts.skip_github, config.github()) {
+        secrets.push(github_config.token());
+    }
+    if let (false, Some(gitlab_config)) = (opts.skip_gitlab, config.gitlab()) {
+        secrets.push(read_secret_env(&gitlab_config.token_env()));
+    }
+    if let (false, Some(gitea_config)) = (opts.skip_gitea, config.gitea()) {
+        secrets.push(read_secret_env(&gitea_config.token_env()));
+    }
+    if let (false, Some(modrinth_config)) = (opts.skip_modrinth, config.modrinth()) {
+        secrets.push(modrinth_config.token());
+    }
+    if let Some(curseforge_config) = &config.curseforge {
+        if !opts.skip_curseforge && curseforge_config.is_enabled() {
+            secrets.push(read_secret_env(&curseforge_config.token_env()));
+        }
+    }
+    if opts.discord {
+        match &config.project.discord {
+            Some(discord_config) => secrets.extend(
+                discord_config
+                    .webhooks()
+                    .iter()
+                    .map(|webhook| read_webhook_env(&webhook.env_var)),
+            ),
+            None => {
+                missing.push("`--discord` needs a `[discord]` section in the config".to_string())
+            }
+        }
+    }
+    if opts.slack {
+        match &config.project.slack {
+            Some(slack_config) => secrets.push(read_secret_env(&slack_config.webhook_env())),
+            None => missing.push("`--slack` needs a `[slack]` section in the config".to_string()),
+        }
+    }
+    match &config.project.matrix {
+        Some(matrix_config) if opts.matrix || matrix_config.is_enabled() => {
+            secrets.push(read_secret_env(&matrix_config.access_token_env()))
+        }
+        None if opts.matrix => {
+            missing.push("`--matrix` needs a `[matrix]` section in the config".to_string())
+        }
+        _ => (),
+    }
+
+    // Dry runs don't upload anything, so they work without tokens
+    if !opts.dry_run {
+        missing.extend(
+            secrets
+                .into_iter()
+                .filter_map(|secret| secret.err())
+                .map(|err| err.to_string()),
+        );
+    }
+
+    preflight_result(missing)
+}
+
+/// Like [`preflight_modpack`], for mod releases.
+pub fn preflight_mod(config: &ModConfig, opts: &ModReleaseOptions) -> Result<(), anyhow::Error> {
+    let mut missing = vec![];
+
+    let java = match config.java_version {
+        Some(java_version) => check_java_version(java_version),
+        None => find_java().map(|_| ()),
+    };
+    if let Err(err) = java {
+        missing.push(err.to_string());
+    }
+
+    let mut secrets: Vec<Result<Secret, anyhow::Error>> = vec![];
+
+    if let (false, Some(github_config)) = (opts.skip_github, config.github()) {
+        secrets.push(github_config.token());
+    }
+    if let (false, Some(modrinth_config)) = (opts.skip_modrinth, config.modrinth()) {
+        secrets.push(modrinth_config.token());
+    }
+    if let Some(curseforge_config) = config.curseforge.as_ref().filter(|cf| cf.is_enabled()) {
+        if !opts.skip_curseforge {
+            secrets.push(read_secret_env(&curseforge_config.token_env()));
+        }
+    }
+    if let Some(maven_config) = &config.maven {
+        secrets.extend(maven_config.env().iter().map(|name| read_secret_env(name)));
+    }
+
+    if opts.discord {
+        match &config.project.discord {
+            Some(discord_config) => secrets.extend(
+                discord_config
+                    .webhooks()
+                    .iter()
+                    .map(|webhook| read_webhook_env(&webhook.env_var)),
+            ),
+            None => {
+                missing.push("`--discord` needs a `[discord]` section in the config".to_string())
+            }
+        }
+    }
+    if opts.slack {
+        match &config.project.slack {
+            Some(slack_config) => secrets.push(read_secret_env(&slack_config.webhook_env())),
+            None => missing.push("`--slack` needs a `[slack]` section in the config".to_string()),
+        }
+    }
+    match &config.project.matrix {
+        Some(matrix_config) if opts.matrix || matrix_config.is_enabled() => {
+            secrets.push(read_secret_env(&matrix_config.access_token_env()))
+        }
+        None if opts.matrix => {
+            missing.push("`--matrix` needs a `[matrix]` section in the config".to_string())
+        }
+        _ => (),
+    }
+
+    if !opts.dry_run {
+        missing.extend(
+            secrets
+                .into_iter()
+                .filter_map(|secret| secret.err())
+                .map(|err| err.to_string()),
+        );
+    }
+
+    preflight_result(missing)
+}
+
+fn preflight_result(missing: Vec<String>) -> Result<(), anyhow::Error> {
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Can't release, fix these first:\n{}",
+        missing
+            .iter()
+            .map(|problem| format!("  - {}", problem))
+            .collect::<Vec<String>>()
+            .join("\n")
+    ))
+}
+
+pub fn env_var_check(name: &str) -> Check {
+    Check::new(
+        &format!("`{}` is set", name),
+        read_secret_env(name).map(|_| ()),
+    )
+}
+
+pub async fn check_github_repo(
+    config: &ModpackConfig,
+    github_config: &GithubConfig,
+    token: &Secret,
+) -> Result<(), anyhow::Error> {
+    let network = config.project.network.clone().unwrap_or_default();
+
+    match send_with_retry(&network, |client| {
+        Ok(client
+            .get(github_config.api_url())
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token.expose()))
+    })
+    .await
+    {
+        Ok(res) if res.status().is_success() => Ok(()),
+        Ok(res) => Err(anyhow!(
+            "GitHub returned `{}` for repository `{}/{}`",
+            res.status(),
+            github_config.repo_owner,
+            github_config.repo_name
+        )),
+        Err(err) => Err(anyhow!("Failed to get GitHub repository: {}", err)),
+    }
+}
+
+pub async fn check_modrinth_project(
+    config: &ModpackConfig,
+    modrinth_config: &ModrinthConfig,
+    token: &Secret,
+) -> Result<(), anyhow::Error> {
+    let modrinth_url = config.modrinth_url();
+    let network = config.project.network.clone().unwrap_or_default();
+
+    match send_with_retry(&network, |client| {
+        Ok(client
+            .get(format!(
+                "{}/project/{}",
+                modrinth_url.labrinth, modrinth_config.project_id
+            ))
+            .header("Authorization", token.expose()))
+    })
+    .await
+    {
+        Ok(res) if res.status().is_success() => Ok(()),
+        Ok(res) => Err(anyhow!(
+            "Modrinth returned `{}` for project `{}`",
+            res.status(),
+            modrinth_config.project_id
+        )),
+        Err(err) => Err(anyhow!("Failed to get Modrinth project: {}", err)),
+    }
+}
+
+pub fn print_checks(checks: &[Check]) {
+    for check in checks {
+        match &check.result {
+            Ok(_) => println!("[pass] {}", check.name),
+            Err(err) if check.required => println!("[fail] {}: {}", check.name, err),
+            Err(err) => println!("[warn] {}: {}", check.name, err),
+        }
+    }
+}
@@ -1,21 +1,26 @@
+use crate::changelog::get_previous_tag;
 use crate::models::modrinth::Loader::*;
 use crate::models::project_type::modpack::{config::ModpackConfig, PackFile};
 use crate::models::util::OutputFileInfo;
-use crate::models::version::VersionInfo;
+use crate::models::version::{BumpLevel, VersionInfo};
+use crate::models::{VersionConfig, VersionSource};
 use anyhow::anyhow;
+use std::env;
 use std::fs;
+use std::path::Path;
 
-pub fn get_modpack_version_info(
+pub fn get_modpack_version_name(
     config_file: &ModpackConfig,
     pack_file: &PackFile,
-    output_info: &OutputFileInfo,
-) -> Result<VersionInfo, anyhow::Error> {
+) -> Result<String, anyhow::Error> {
     let loader_opt = if pack_file.versions.quilt.is_some() {
         Some(Quilt)
     } else if pack_file.versions.fabric.is_some() {
         Some(Fabric)
     } else if pack_file.versions.forge.is_some() {
         Some(Forge)
+    } else if pack_file.versions.neoforge.is_some() {
+        Some(Neoforge)
     } else if pack_file.versions.liteloader.is_some() {
         Some(Liteloader)
     } else {
@@ -27,21 +32,400 @@ pub fn get_modpack_version_info(
         None => return Err(anyhow!("Failed to parse loader name")),
     };
 
-    let version_name = config_file
+    let loader = loader.formatted();
+
+    let placeholders = [
+        ("pack_name", pack_file.name.as_str()),
+        ("version", pack_file.version.as_str()),
+        ("mc_version", pack_file.versions.minecraft.as_str()),
+        ("loader", loader.as_str()),
+    ];
+
+    // The `%placeholder%` syntax is still supported for older configs
+    let escape = |value: &str| value.replace('{', "{{").replace('}', "}}");
+    let version_name_format = config_file
         .version_name_format
-        .replace("%project_name%", &pack_file.name)
-        .replace("%project_version%", &pack_file.version)
-        .replace("%mc_version%", &pack_file.versions.minecraft)
-        .replace("%loader%", &loader.formatted());
+        .replace("%project_name%", &escape(&pack_file.name))
+        .replace("%project_version%", &escape(&pack_file.version))
+        .replace("%mc_version%", &escape(&pack_file.versions.minecraft))
+        .replace("%loader%", &loader);
+
+    render_template("version_name_format", &version_name_format, &placeholders)
+}
+
+pub const VERSION_NAME_PLACEHOLDERS: [&str; 4] = ["pack_name", "version", "mc_version", "loader"];
+
+/// Replaces `{placeholder}`s in the format of the `setting`, with `{{` and `}}` for literal
+/// braces.
+pub fn render_template(
+    setting: &str,
+    format: &str,
+    placeholders: &[(&str, &str)],
+) -> Result<String, anyhow::Error> {
+    let mut rendered = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => {
+                            return Err(anyhow!(
+                                "Unclosed `{{` in {} `{}`, use `{{{{` for a literal brace",
+                                setting,
+                                format
+                            ))
+                        }
+                    }
+                }
+
+                match placeholders.iter().find(|(key, _)| *key == name) {
+                    Some((_, value)) => rendered.push_str(value),
+                    None => {
+                        return Err(anyhow!(
+                            "Unknown placeholder `{{{}}}` in {}, valid placeholders are: {}",
+                            name,
+                            setting,
+                            placeholders
+                                .iter()
+                                .map(|(key, _)| format!("{{{}}}", key))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        ))
+                    }
+                }
+            }
+            '}' => {
+                return Err(anyhow!(
+                    "Unmatched `}}` in {} `{}`, use `}}}}` for a literal brace",
+                    setting,
+                    format
+                ))
+            }
+            c => rendered.push(c),
+        }
+    }
+
+    Ok(rendered)
+}
+
+/// Checks the format with placeholder values, so that typos fail before anything is released.
+pub fn check_version_name_format(format: &str) -> Result<(), anyhow::Error> {
+    let placeholders = VERSION_NAME_PLACEHOLDERS.map(|key| (key, ""));
+    render_template("version_name_format", format, &placeholders).map(|_| ())
+}
+
+pub const OUTPUT_FILENAME_PLACEHOLDERS: [&str; 3] = ["name", "version", "mc_version"];
+
+/// Renders the `output_filename` template, the extension is added when it's missing. `None`
+/// keeps the name packwiz exported the file with.
+pub fn get_output_file_name(
+    config_file: &ModpackConfig,
+    pack_file: &PackFile,
+    extension: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let template = match &config_file.output_filename {
+        Some(template) => template,
+        None => return Ok(None),
+    };
+
+    let placeholders = [
+        ("name", pack_file.name.as_str()),
+        ("version", pack_file.version.as_str()),
+        ("mc_version", pack_file.versions.minecraft.as_str()),
+    ];
+
+    let file_name = sanitize_file_name(&render_template(
+        "output_filename",
+        template,
+        &placeholders,
+    )?);
+
+    if file_name.is_empty() {
+        return Err(anyhow!(
+            "output_filename `{}` renders to an empty name",
+            template
+        ));
+    }
+
+    if file_name.ends_with(&format!(".{}", extension)) {
+        Ok(Some(file_name))
+    } else {
+        Ok(Some(format!("{}.{}", file_name, extension)))
+    }
+}
+
+pub fn check_output_filename(template: &str) -> Result<(), anyhow::Error> {
+    let placeholders = OUTPUT_FILENAME_PLACEHOLDERS.map(|key| (key, ""));
+    render_template("output_filename", template, &placeholders).map(|_| ())
+}
+
+/// Replaces characters that aren't allowed in Windows file names, which includes path
+/// separators. Trailing dots and spaces are dropped, Windows strips them as well.
+pub fn sanitize_file_name(file_name: &str) -> String {
+    file_name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_end_matches(['.', ' '])
+        .to_string()
+}
+
+pub fn get_modpack_version_info(
+    config_file: &ModpackConfig,
+    pack_file: &PackFile,
+    output_info: &OutputFileInfo,
+) -> Result<VersionInfo, anyhow::Error> {
+    let version_name = get_modpack_version_name(config_file, pack_file)?;
 
-    let file_contents = match fs::read(output_info.file_path.clone()) {
-        Ok(file) => file,
+    let file_size = match fs::metadata(&output_info.file_path) {
+        Ok(metadata) => metadata.len(),
         Err(err) => return Err(anyhow!("Failed to read .mrpack file: {}", err)),
     };
 
     Ok(VersionInfo {
         version_name,
-        loader,
-        file_contents,
+        file_size,
     })
 }
+
+/// Pre-release and build suffixes are dropped, `1.4.0-beta.2` bumps to `1.4.1`.
+pub fn bump_version(version: &str, level: BumpLevel) -> Result<String, anyhow::Error> {
+    let mut parsed = match semver::Version::parse(version) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            return Err(anyhow!(
+                "Can't bump version `{}` as it isn't a semver version ({}), \
+                 use `--version` instead",
+                version,
+                err
+            ))
+        }
+    };
+
+    match level {
+        BumpLevel::Major => {
+            parsed.major += 1;
+            parsed.minor = 0;
+            parsed.patch = 0;
+        }
+        BumpLevel::Minor => {
+            parsed.minor += 1;
+            parsed.patch = 0;
+        }
+        BumpLevel::Patch => parsed.patch += 1,
+    }
+
+    parsed.pre = semver::Prerelease::EMPTY;
+    parsed.build = semver::BuildMetadata::EMPTY;
+
+    Ok(parsed.to_string())
+}
+
+/// Returns `None` for the `pack` source, as pack.toml is then used as is.
+pub fn resolve_version(
+    version_config: &VersionConfig,
+    project_dir: &Path,
+) -> Result<Option<String>, anyhow::Error> {
+    match version_config.source() {
+        VersionSource::Pack => Ok(None),
+        VersionSource::GitTag => {
+            let tag = match get_previous_tag(project_dir)? {
+                Some(tag) => tag,
+                None => {
+                    return Err(anyhow!(
+                        "No git tag found to take the version from, tag the release commit \
+                         or use `--version`"
+                    ))
+                }
+            };
+
+            let prefix = version_config.tag_prefix.as_deref().unwrap_or("v");
+            Ok(Some(tag.strip_prefix(prefix).unwrap_or(&tag).to_string()))
+        }
+        VersionSource::Env => {
+            let env_var = match &version_config.env_var {
+                Some(env_var) => env_var,
+                None => {
+                    return Err(anyhow!(
+                        "`version.env_var` must be set when using the `env` version source"
+                    ))
+                }
+            };
+
+            match env::var(env_var) {
+                Ok(version) if !version.trim().is_empty() => Ok(Some(version.trim().to_string())),
+                _ => Err(anyhow!(
+                    "Failed to get the version from `{}`, make sure it's set",
+                    env_var
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACK_TOML: &str = include_str!("../test_projects/modpack/pack.toml");
+
+    fn version_name(version_name_format: &str, pack_name: &str) -> Result<String, anyhow::Error> {
+        let config: ModpackConfig = toml::from_str(&format!(
+            r#"
+config_format_version = 1
+version_name_format = {:?}
+
+[github]
+repo_owner = "LilydevMC"
+repo_name = "peony-test"
+
+[modrinth]
+project_id = "AABBCCDD"
+"#,
+            version_name_format
+        ))
+        .unwrap();
+        let mut pack_file: PackFile = toml::from_str(PACK_TOML).unwrap();
+        pack_file.name = pack_name.to_string();
+
+        get_modpack_version_name(&config, &pack_file)
+    }
+
+    #[test]
+    fn renders_version_name_placeholders() {
+        assert_eq!(
+            version_name(
+                "{pack_name} {version} for {mc_version} ({loader})",
+                "Skylands"
+            )
+            .unwrap(),
+            "Skylands 0.1.0 for 1.20.2 (Quilt)"
+        );
+    }
+
+    #[test]
+    fn renders_legacy_version_name_placeholders() {
+        assert_eq!(
+            version_name(
+                "%project_name% %project_version% [%mc_version%]",
+                "Skylands"
+            )
+            .unwrap(),
+            "Skylands 0.1.0 [1.20.2]"
+        );
+    }
+
+    #[test]
+    fn braces_in_values_of_legacy_placeholders_are_kept() {
+        assert_eq!(
+            version_name("%project_name% {version}", "{Sky}lands").unwrap(),
+            "{Sky}lands 0.1.0"
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(
+            render_template("test", "{{{version}}} {{}} }}{{", &[("version", "1.0.0")]).unwrap(),
+            "{1.0.0} {} }{"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholder_lists_valid_ones() {
+        let err = check_version_name_format("{pack_name} {vesion}")
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(
+            err,
+            "Unknown placeholder `{vesion}` in version_name_format, valid placeholders are: \
+             {pack_name}, {version}, {mc_version}, {loader}"
+        );
+    }
+
+    #[test]
+    fn unclosed_and_unmatched_braces_fail() {
+        let err = check_version_name_format("{pack_name")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Unclosed `{` in version_name_format `{pack_name`, use `{{` for a literal brace"
+        );
+
+        let err = check_version_name_format("pack_name}")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Unmatched `}` in version_name_format `pack_name}`, use `}}` for a literal brace"
+        );
+    }
+
+    #[test]
+    fn valid_version_name_formats() {
+        for format in ["{pack_name} {version}", "{{{version}}}", "Release", ""] {
+            assert!(check_version_name_format(format).is_ok(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn bumps_each_level() {
+        assert_eq!(bump_version("1.4.2", BumpLevel::Major).unwrap(), "2.0.0");
+        assert_eq!(bump_version("1.4.2", BumpLevel::Minor).unwrap(), "1.5.0");
+        assert_eq!(bump_version("1.4.2", BumpLevel::Patch).unwrap(), "1.4.3");
+    }
+
+    #[test]
+    fn bump_strips_pre_release_and_build_suffixes() {
+        assert_eq!(
+            bump_version("1.4.0-beta.2", BumpLevel::Patch).unwrap(),
+            "1.4.1"
+        );
+        assert_eq!(
+            bump_version("1.4.0+mc1.20.1", BumpLevel::Minor).unwrap(),
+            "1.5.0"
+        );
+        assert_eq!(
+            bump_version("1.4.0-rc.1+build.5", BumpLevel::Major).unwrap(),
+            "2.0.0"
+        );
+    }
+
+    #[test]
+    fn bump_fails_for_non_semver_versions() {
+        for version in ["1.4", "v1.4.0", "1.20.1-2024.01"] {
+            let err = bump_version(version, BumpLevel::Patch)
+                .unwrap_err()
+                .to_string();
+
+            assert!(
+                err.starts_with(&format!(
+                    "Can't bump version `{}` as it isn't a semver version",
+                    version
+                )),
+                "{}",
+                err
+            );
+            assert!(err.ends_with("use `--version` instead"), "{}", err);
+        }
+    }
+}